@@ -1,11 +1,11 @@
 use anyhow::Result;
+use chrono::Utc;
+use pingtest::config::Config;
+use pingtest::history::{HistoryEntry, HistoryManager};
 use pingtest::network::SpeedTest;
 use pingtest::ping::PingAnalyzer;
-use pingtest::history::{HistoryManager, HistoryEntry};
-use pingtest::config::Config;
 use pingtest::ui::ThemeManager;
-use pingtest::utils::{format_speed, format_ping, get_quality_score};
-use chrono::Utc;
+use pingtest::utils::{format_ping, format_speed, get_quality_score};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -16,8 +16,14 @@ async fn main() -> Result<()> {
     println!("⚙️  Loading configuration...");
     let config = Config::load().await?;
     println!("Default theme: {}", config.general.theme);
-    println!("Default duration: {} seconds", config.general.default_duration);
-    println!("Default connections: {}", config.general.default_connections);
+    println!(
+        "Default duration: {} seconds",
+        config.general.default_duration
+    );
+    println!(
+        "Default connections: {}",
+        config.general.default_connections
+    );
     println!();
 
     // Initialize history manager
@@ -29,7 +35,7 @@ async fn main() -> Result<()> {
     println!("🎨 Testing themes...");
     let mut theme_manager = ThemeManager::new();
     let themes = theme_manager.get_available_themes();
-    
+
     for theme in &themes {
         theme_manager.set_theme(theme)?;
         println!("  ✓ Theme '{}' loaded", theme);
@@ -39,11 +45,13 @@ async fn main() -> Result<()> {
     // Run multiple ping tests with different targets
     println!("📡 Running ping tests to different targets...");
     let ping_analyzer = PingAnalyzer::new();
-    
+
     let targets = vec!["8.8.8.8", "1.1.1.1", "208.67.222.222"];
     for target in targets {
         println!("  Testing {}...", target);
-        let result = ping_analyzer.run_ping_test_with_params(target, 5, std::time::Duration::from_secs(2)).await?;
+        let result = ping_analyzer
+            .run_ping_test_with_params(target, 5, std::time::Duration::from_secs(2))
+            .await?;
         println!("    Average ping: {}", format_ping(result.avg_ping));
         println!("    Jitter: {}", format_ping(result.jitter));
         println!("    Packet loss: {:.1}%", result.packet_loss);
@@ -53,7 +61,7 @@ async fn main() -> Result<()> {
     // Run speed test with different parameters
     println!("🌐 Running speed tests with different parameters...");
     let speed_test = SpeedTest::new();
-    
+
     // Test with different durations
     let durations = vec![5, 10, 15];
     for duration in durations {
@@ -61,7 +69,10 @@ async fn main() -> Result<()> {
         let result = speed_test.run_test(duration, 4, false, false).await?;
         println!("    Download: {}", format_speed(result.download_speed));
         println!("    Upload: {}", format_speed(result.upload_speed));
-        println!("    Quality Score: {}/100", get_quality_score(result.download_speed, result.upload_speed, result.ping));
+        println!(
+            "    Quality Score: {}/100",
+            get_quality_score(result.download_speed, result.upload_speed, result.ping)
+        );
     }
     println!();
 
@@ -79,12 +90,21 @@ async fn main() -> Result<()> {
     // Test download-only and upload-only tests
     println!("⬇️  Testing download-only...");
     let download_result = speed_test.run_test(10, 4, false, true).await?;
-    println!("  Download: {}", format_speed(download_result.download_speed));
-    println!("  Upload: {} (skipped)", format_speed(download_result.upload_speed));
-    
+    println!(
+        "  Download: {}",
+        format_speed(download_result.download_speed)
+    );
+    println!(
+        "  Upload: {} (skipped)",
+        format_speed(download_result.upload_speed)
+    );
+
     println!("⬆️  Testing upload-only...");
     let upload_result = speed_test.run_test(10, 4, true, false).await?;
-    println!("  Download: {} (skipped)", format_speed(upload_result.download_speed));
+    println!(
+        "  Download: {} (skipped)",
+        format_speed(upload_result.download_speed)
+    );
     println!("  Upload: {}", format_speed(upload_result.upload_speed));
     println!();
 
@@ -93,8 +113,10 @@ async fn main() -> Result<()> {
     let servers = speed_test.get_servers().await?;
     println!("Available servers:");
     for server in &servers {
-        println!("  {} - {} ({}) - {:.1} km", 
-                 server.id, server.name, server.location, server.distance);
+        println!(
+            "  {} - {} ({}) - {:.1} km",
+            server.id, server.name, server.location, server.distance_km
+        );
     }
     println!();
 
@@ -103,15 +125,17 @@ async fn main() -> Result<()> {
     let nearest = speed_test.get_nearest_servers(3).await?;
     println!("Nearest servers:");
     for server in &nearest {
-        println!("  {} - {} ({}) - {:.1} km", 
-                 server.id, server.name, server.location, server.distance);
+        println!(
+            "  {} - {} ({}) - {:.1} km",
+            server.id, server.name, server.location, server.distance_km
+        );
     }
     println!();
 
     // Save test results to history
     println!("💾 Saving test results to history...");
     let test_result = speed_test.run_test(10, 4, false, false).await?;
-    
+
     let history_entry = HistoryEntry {
         id: uuid::Uuid::new_v4().to_string(),
         timestamp: Utc::now(),
@@ -122,8 +146,11 @@ async fn main() -> Result<()> {
         server_name: test_result.server_name.clone(),
         server_location: test_result.server_location.clone(),
         tag: Some("advanced_example".to_string()),
+        method: Some(test_result.method.clone()),
+        batch_id: None,
+        partial: false,
     };
-    
+
     history_manager.add_entry(history_entry).await?;
     println!("  ✓ Test result saved to history");
     println!();
@@ -143,7 +170,9 @@ async fn main() -> Result<()> {
 
     // Export history
     println!("📤 Exporting history...");
-    history_manager.export_history("pingtest_history.json").await?;
+    history_manager
+        .export_history("pingtest_history.json")
+        .await?;
     println!("  ✓ History exported to pingtest_history.json");
     println!();
 
@@ -152,11 +181,15 @@ async fn main() -> Result<()> {
     let download_trends = history_manager.get_trends("download").await?;
     println!("Download trends (last {} days):", download_trends.len());
     for trend in download_trends.iter().take(5) {
-        println!("  {}: {}", trend.date.format("%Y-%m-%d"), format_speed(trend.value));
+        println!(
+            "  {}: {}",
+            trend.date.format("%Y-%m-%d"),
+            format_speed(trend.value)
+        );
     }
     println!();
 
     println!("✅ Advanced usage example completed successfully!");
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use pingtest::ui::{ThemeManager, Theme, ThemeColors};
+use pingtest::ui::{Theme, ThemeColors, ThemeManager};
 use ratatui::style::Color;
 
 #[tokio::main]
@@ -19,19 +19,19 @@ async fn main() -> Result<()> {
 
     // Create a custom theme
     println!("Creating custom 'ocean' theme...");
-    
+
     let ocean_theme = Theme {
         name: "ocean".to_string(),
         colors: ThemeColors {
-            background: Color::Rgb(0, 20, 40),      // Deep ocean blue
-            foreground: Color::Rgb(173, 216, 230),  // Light blue
-            primary: Color::Rgb(0, 191, 255),       // Deep sky blue
-            secondary: Color::Rgb(72, 209, 204),    // Medium turquoise
-            success: Color::Rgb(0, 255, 127),       // Spring green
-            warning: Color::Rgb(255, 215, 0),       // Gold
-            error: Color::Rgb(255, 69, 0),          // Red orange
+            background: Color::Rgb(0, 20, 40),       // Deep ocean blue
+            foreground: Color::Rgb(173, 216, 230),   // Light blue
+            primary: Color::Rgb(0, 191, 255),        // Deep sky blue
+            secondary: Color::Rgb(72, 209, 204),     // Medium turquoise
+            success: Color::Rgb(0, 255, 127),        // Spring green
+            warning: Color::Rgb(255, 215, 0),        // Gold
+            error: Color::Rgb(255, 69, 0),           // Red orange
             info: Color::Rgb(30, 144, 255),          // Dodger blue
-            accent: Color::Rgb(138, 43, 226),       // Blue violet
+            accent: Color::Rgb(138, 43, 226),        // Blue violet
             border: Color::Rgb(25, 25, 112),         // Midnight blue
             text_primary: Color::Rgb(173, 216, 230), // Light blue
             text_secondary: Color::Rgb(0, 191, 255), // Deep sky blue
@@ -52,10 +52,11 @@ async fn main() -> Result<()> {
 
     // Demonstrate theme switching
     println!("Demonstrating theme switching...");
-    for theme in &themes[..3] { // Show first 3 themes
+    for theme in themes.iter().take(3) {
+        // Show first 3 themes
         theme_manager.set_theme(theme)?;
         println!("  ✓ Switched to '{}' theme", theme);
-        
+
         // In a real TUI application, this would change the visual appearance
         // For this example, we'll just show the theme name
         let current_theme = theme_manager.get_current_theme();
@@ -65,10 +66,10 @@ async fn main() -> Result<()> {
 
     // Show theme color information
     println!("Theme color information:");
-    for theme_name in &themes[..3] {
+    for theme_name in themes.iter().take(3) {
         theme_manager.set_theme(theme_name)?;
         let theme = theme_manager.get_theme(theme_name).unwrap();
-        
+
         println!("  Theme: {}", theme.name);
         println!("    Primary: {:?}", theme.colors.primary);
         println!("    Secondary: {:?}", theme.colors.secondary);
@@ -80,13 +81,13 @@ async fn main() -> Result<()> {
 
     // Demonstrate theme validation
     println!("Testing theme validation...");
-    
+
     // Valid theme
     match theme_manager.set_theme("dracula") {
         Ok(_) => println!("  ✓ 'dracula' theme is valid"),
         Err(e) => println!("  ✗ Error setting 'dracula' theme: {}", e),
     }
-    
+
     // Invalid theme
     match theme_manager.set_theme("invalid_theme") {
         Ok(_) => println!("  ✓ 'invalid_theme' theme is valid"),
@@ -96,7 +97,7 @@ async fn main() -> Result<()> {
 
     // Show how to create theme variations
     println!("Creating theme variations...");
-    
+
     // Create a dark variation of the ocean theme
     let ocean_dark = Theme {
         name: "ocean-dark".to_string(),
@@ -117,7 +118,7 @@ async fn main() -> Result<()> {
         },
     };
 
-    println!("Created 'ocean-dark' variation:");
+    println!("Created '{}' variation:", ocean_dark.name);
     println!("  Background: Even darker ocean blue");
     println!("  Text: Sky blue for better contrast");
     println!("  Accents: Darker variants for subtle appearance");
@@ -143,4 +144,4 @@ async fn main() -> Result<()> {
     println!("  6. Provide both light and dark variants");
 
     Ok(())
-}
\ No newline at end of file
+}
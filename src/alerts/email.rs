@@ -0,0 +1,414 @@
+//! Alerts as plain-text email over SMTP.
+//!
+//! Connections use STARTTLS on port 587 by default, or TLS from the start on
+//! port 465. Each message lists the rules failing in the run that caused
+//! it, the run itself with its quality and the most recent saved runs.
+
+use super::Alert;
+use crate::history::HistoryEntry;
+use crate::network::TestResult;
+use crate::utils::{get_quality_description, get_quality_score, hostname};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Local;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Used when the config file has no `password`.
+pub const PASSWORD_ENV: &str = "PINGTEST_SMTP_PASSWORD";
+
+/// Saved runs listed in each message.
+const RECENT_RUNS: usize = 5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Security {
+    /// Upgrade a plain connection, failing if the server does not offer it.
+    #[default]
+    Starttls,
+    /// TLS from the start (SMTPS).
+    Tls,
+    /// No encryption, e.g. for a relay on the same host.
+    None,
+}
+
+impl Security {
+    fn default_port(self) -> u16 {
+        match self {
+            Security::Starttls => 587,
+            Security::Tls => 465,
+            Security::None => 25,
+        }
+    }
+}
+
+/// The `[alerts.email]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub server: String,
+    /// Defaults to the port for `security`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: Security,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// E.g. `PingTest <pingtest@example.com>`.
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+impl EmailConfig {
+    /// Checks the server and that the addresses parse.
+    pub fn validate(&self) -> Result<()> {
+        if self.server.trim().is_empty() {
+            bail!("server: must not be empty");
+        }
+        mailbox(&self.from).context("from")?;
+        if self.to.is_empty() {
+            bail!("to: needs at least one address");
+        }
+        for (i, to) in self.to.iter().enumerate() {
+            mailbox(to).with_context(|| format!("to[{}]", i))?;
+        }
+        if self.username.is_none() && self.password.is_some() {
+            bail!("password: needs a username");
+        }
+        Ok(())
+    }
+}
+
+fn mailbox(address: &str) -> Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|e| anyhow!("`{}` is not an email address: {}", address, e))
+}
+
+/// Sends alerts to a list of addresses through one SMTP server, retrying
+/// temporary failures and connection errors with exponential backoff.
+pub struct Email {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    server: String,
+    host: String,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl Email {
+    pub fn new(config: EmailConfig) -> Result<Self> {
+        let mut builder = match config.security {
+            Security::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.server)?
+            }
+            Security::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.server)?,
+            Security::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.server)
+            }
+        }
+        .port(config.port.unwrap_or(config.security.default_port()))
+        .timeout(Some(Duration::from_secs(10)));
+        if let Some(username) = config.username {
+            let password = match config.password {
+                Some(password) => password,
+                None => std::env::var(PASSWORD_ENV).with_context(|| {
+                    format!(
+                        "alerts.email: set password or {} for {}",
+                        PASSWORD_ENV, username
+                    )
+                })?,
+            };
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        Ok(Self {
+            transport: builder.build(),
+            from: mailbox(&config.from)?,
+            to: config
+                .to
+                .iter()
+                .map(|to| mailbox(to))
+                .collect::<Result<_>>()?,
+            server: config.server,
+            host: hostname().unwrap_or_else(|| "unknown host".to_string()),
+            max_retries: 3,
+            backoff: Duration::from_secs(1),
+        })
+    }
+
+    /// The SMTP server, for messages.
+    pub fn target(&self) -> &str {
+        &self.server
+    }
+
+    /// `failing` describes the rules whose condition holds in `result`;
+    /// `history` ends with the most recent saved run.
+    pub async fn send(
+        &self,
+        alert: &Alert,
+        result: &TestResult,
+        failing: &[String],
+        history: &[HistoryEntry],
+    ) -> Result<()> {
+        let mut message = Message::builder().from(self.from.clone());
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        let message = message
+            .subject(alert.headline(&self.host))
+            .header(ContentType::TEXT_PLAIN)
+            .body(body(alert, result, failing, history, &self.host))?;
+
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match self.transport.send(message.clone()).await {
+                Ok(_) => return Ok(()),
+                // Rejected addresses and failed logins will not change.
+                Err(e) if e.is_permanent() || e.is_client() => {
+                    return Err(anyhow!(e).context(format!("Email via {} failed", self.server)))
+                }
+                Err(e) => anyhow!(e).context(format!("Email via {} failed", self.server)),
+            };
+            if attempt == self.max_retries {
+                return Err(error.context(format!("Gave up after {} attempts", attempt + 1)));
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+/// The message text.
+fn body(
+    alert: &Alert,
+    result: &TestResult,
+    failing: &[String],
+    history: &[HistoryEntry],
+    host: &str,
+) -> String {
+    let score = get_quality_score(result.download_speed, result.upload_speed, result.ping);
+    let speed = |measured: bool, mbps: f64| {
+        if measured {
+            format!("{:.1} Mbps", mbps)
+        } else {
+            "skipped".to_string()
+        }
+    };
+
+    let mut text = String::new();
+    let _ = writeln!(text, "{}\n", alert.headline(host));
+    if failing.is_empty() {
+        let _ = writeln!(text, "No alert rules are failing.\n");
+    } else {
+        let _ = writeln!(text, "Failing now:");
+        for line in failing {
+            let _ = writeln!(text, "  - {}", line);
+        }
+        text.push('\n');
+    }
+    let _ = writeln!(
+        text,
+        "Latest test ({}, {}):",
+        result
+            .timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M"),
+        result.server_name
+    );
+    let _ = writeln!(
+        text,
+        "  Download:     {}",
+        speed(result.method.download, result.download_speed)
+    );
+    let _ = writeln!(
+        text,
+        "  Upload:       {}",
+        speed(result.method.upload, result.upload_speed)
+    );
+    let _ = writeln!(text, "  Ping:         {:.1} ms", result.ping);
+    let _ = writeln!(text, "  Jitter:       {:.1} ms", result.jitter);
+    let _ = writeln!(text, "  Packet loss:  {:.1}%", result.packet_loss);
+    let _ = writeln!(
+        text,
+        "  Quality:      {}/100 ({})",
+        score,
+        get_quality_description(score)
+    );
+
+    if !history.is_empty() {
+        let _ = writeln!(text, "\nRecent runs:");
+        for entry in history.iter().rev().take(RECENT_RUNS) {
+            let _ = writeln!(
+                text,
+                "  {}  ↓ {:.1} Mbps  ↑ {:.1} Mbps  {:.1} ms{}",
+                entry
+                    .timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M"),
+                entry.download_speed,
+                entry.upload_speed,
+                entry.ping,
+                if entry.partial { " (partial)" } else { "" }
+            );
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::tests::result;
+    use crate::alerts::{AlertKind, AlertRule, Comparison};
+    use crate::thresholds::Check;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    fn breach() -> Alert {
+        Alert {
+            kind: AlertKind::Breach,
+            rule: AlertRule {
+                metric: Check::Download,
+                comparison: Comparison::Below,
+                value: 50.0,
+                consecutive: 3,
+            },
+            measured: 42.1,
+        }
+    }
+
+    fn config(server: &str, port: u16) -> EmailConfig {
+        EmailConfig {
+            server: server.to_string(),
+            port: Some(port),
+            security: Security::None,
+            username: Some("alerts".to_string()),
+            password: Some("s3cret".to_string()),
+            from: "PingTest <pingtest@example.com>".to_string(),
+            to: vec!["noc@example.com".to_string()],
+        }
+    }
+
+    #[test]
+    fn body_lists_failing_rules_quality_and_recent_runs() {
+        let history: Vec<HistoryEntry> = [80.0, 45.0, 42.1]
+            .into_iter()
+            .map(|download| HistoryEntry::from_result(&result(download), None, None))
+            .collect();
+        let failing = ["download 42.1 Mbps (alert when download < 50 Mbps)".to_string()];
+        let text = body(&breach(), &result(42.1), &failing, &history, "office-pc");
+
+        assert!(text.starts_with(
+            "🔴 pingtest alert on office-pc: download 42.1 Mbps < 50.0 Mbps for 3 runs in a row\n"
+        ));
+        assert!(
+            text.contains("Failing now:\n  - download 42.1 Mbps (alert when download < 50 Mbps)\n")
+        );
+        assert!(text.contains("  Upload:       skipped\n"));
+        assert!(text.contains("  Quality:      "));
+        let recent: Vec<&str> = text
+            .split("Recent runs:\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .collect();
+        assert_eq!(recent.len(), 3);
+        assert!(recent[0].contains("↓ 42.1 Mbps"));
+        assert!(recent[2].contains("↓ 80.0 Mbps"));
+    }
+
+    #[test]
+    fn config_names_the_bad_address() {
+        let mut config = config("smtp.example.com", 587);
+        config.validate().unwrap();
+        config.to = vec!["noc@example.com".to_string(), "noc".to_string()];
+        assert!(format!("{:#}", config.validate().unwrap_err())
+            .starts_with("to[1]: `noc` is not an email address"));
+    }
+
+    /// The commands and message a sink received on each connection.
+    #[derive(Default)]
+    struct Sink {
+        sessions: Mutex<Vec<Vec<String>>>,
+    }
+
+    /// A minimal SMTP server that offers AUTH and accepts every message,
+    /// except that the first MAIL FROM gets a temporary failure.
+    async fn sink(listener: tokio::net::TcpListener, sink: Arc<Sink>) {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (read, mut write) = socket.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let mut session = Vec::new();
+            write.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let command = line.to_ascii_uppercase();
+                let reply: &[u8] = if command.starts_with("EHLO") {
+                    b"250-sink\r\n250 AUTH PLAIN LOGIN\r\n"
+                } else if command.starts_with("AUTH") {
+                    b"235 2.7.0 Authenticated\r\n"
+                } else if command.starts_with("MAIL") && sink.sessions.lock().unwrap().is_empty() {
+                    b"451 4.3.0 Try again later\r\n"
+                } else if command.starts_with("DATA") {
+                    write.write_all(b"354 Go ahead\r\n").await.unwrap();
+                    let mut data = String::new();
+                    while let Some(line) = lines.next_line().await.unwrap() {
+                        if line == "." {
+                            break;
+                        }
+                        data.push_str(&line);
+                        data.push('\n');
+                    }
+                    session.push(data);
+                    b"250 2.0.0 Queued\r\n"
+                } else if command.starts_with("QUIT") {
+                    write.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 OK\r\n"
+                };
+                session.push(line);
+                write.write_all(reply).await.unwrap();
+            }
+            sink.sessions.lock().unwrap().push(session);
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_through_an_smtp_server_after_a_temporary_failure() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let received = Arc::new(Sink::default());
+        tokio::spawn(sink(listener, Arc::clone(&received)));
+
+        let mut email = Email::new(config("127.0.0.1", port)).unwrap();
+        email.backoff = Duration::from_millis(1);
+        email
+            .send(&breach(), &result(42.1), &[], &[])
+            .await
+            .unwrap();
+
+        let sessions = received.sessions.lock().unwrap();
+        assert_eq!(sessions.len(), 2);
+        let session = &sessions[1];
+        // AUTH PLAIN carries base64("\0alerts\0s3cret").
+        assert!(session.contains(&"AUTH PLAIN AGFsZXJ0cwBzM2NyZXQ=".to_string()));
+        assert!(session.contains(&"MAIL FROM:<pingtest@example.com>".to_string()));
+        assert!(session.contains(&"RCPT TO:<noc@example.com>".to_string()));
+        let message = session
+            .iter()
+            .find(|line| line.contains("Subject:"))
+            .unwrap();
+        assert!(message.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(message.contains("No alert rules are failing."));
+    }
+}
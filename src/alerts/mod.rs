@@ -0,0 +1,343 @@
+//! Alert rules checked against every scheduled test (`[alerts]` in the
+//! config file).
+//!
+//! A rule breaches once its condition has held for `consecutive` runs in a
+//! row, and recovers once it has not held for as many runs. Each transition
+//! sends one alert; runs in between send nothing. Rule state lives in memory,
+//! so it starts afresh when the daemon restarts.
+
+pub mod email;
+pub mod webhook;
+
+use crate::history::HistoryEntry;
+use crate::network::TestResult;
+use crate::thresholds::Check;
+use email::Email;
+use serde::{Deserialize, Serialize};
+use webhook::Webhook;
+
+/// How a measured value is compared with a rule's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    #[serde(rename = "<")]
+    Below,
+    #[serde(rename = "<=")]
+    AtMost,
+    #[serde(rename = ">")]
+    Above,
+    #[serde(rename = ">=")]
+    AtLeast,
+}
+
+impl Comparison {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Below => "<",
+            Comparison::AtMost => "<=",
+            Comparison::Above => ">",
+            Comparison::AtLeast => ">=",
+        }
+    }
+
+    fn holds(self, measured: f64, value: f64) -> bool {
+        match self {
+            Comparison::Below => measured < value,
+            Comparison::AtMost => measured <= value,
+            Comparison::Above => measured > value,
+            Comparison::AtLeast => measured >= value,
+        }
+    }
+}
+
+/// Breach when `metric comparison value` holds for `consecutive` runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    pub metric: Check,
+    pub comparison: Comparison,
+    pub value: f64,
+    #[serde(default = "one_run")]
+    pub consecutive: u32,
+}
+
+fn one_run() -> u32 {
+    1
+}
+
+impl AlertRule {
+    /// E.g. `download < 50 Mbps`.
+    pub fn condition(&self) -> String {
+        format!(
+            "{} {} {}{}",
+            self.metric.name(),
+            self.comparison.symbol(),
+            self.value,
+            self.metric.unit()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    Breach,
+    Recovery,
+}
+
+impl AlertKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            AlertKind::Breach => "breach",
+            AlertKind::Recovery => "recovery",
+        }
+    }
+}
+
+/// A rule changing state after a run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub rule: AlertRule,
+    /// The rule's metric in the run that caused the alert.
+    pub measured: f64,
+}
+
+impl Alert {
+    /// The summary with where it comes from, e.g.
+    /// `🔴 pingtest alert on office-pc: download 42.1 Mbps < 50.0 Mbps`.
+    pub fn headline(&self, host: &str) -> String {
+        match self.kind {
+            AlertKind::Breach => format!("🔴 pingtest alert on {}: {}", host, self.summary()),
+            AlertKind::Recovery => format!("✅ pingtest on {}: {}", host, self.summary()),
+        }
+    }
+
+    /// One line for people, e.g. `download 42.1 Mbps < 50.0 Mbps for 3 runs in a row`.
+    pub fn summary(&self) -> String {
+        let metric = self.rule.metric;
+        let measured = metric.format_value(self.measured);
+        let limit = format!(
+            "{} {}",
+            self.rule.comparison.symbol(),
+            metric.format_value(self.rule.value)
+        );
+        let runs = match self.rule.consecutive {
+            1 => String::new(),
+            n => format!(" for {} runs in a row", n),
+        };
+        match self.kind {
+            AlertKind::Breach => format!("{} {} {}{}", metric.name(), measured, limit, runs),
+            AlertKind::Recovery => format!(
+                "{} recovered: {}, not {}{}",
+                metric.name(),
+                measured,
+                limit,
+                runs
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RuleState {
+    /// Runs in a row in which the condition held, or did not.
+    streak: u32,
+    breached: bool,
+}
+
+/// Tracks every rule across runs and delivers the alerts.
+pub struct Alerter {
+    rules: Vec<(AlertRule, RuleState)>,
+    webhooks: Vec<Webhook>,
+    email: Option<Email>,
+}
+
+impl Alerter {
+    pub fn new(rules: Vec<AlertRule>, webhooks: Vec<Webhook>, email: Option<Email>) -> Self {
+        Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| (rule, RuleState::default()))
+                .collect(),
+            webhooks,
+            email,
+        }
+    }
+
+    /// The rules, described as for the daemon's startup message.
+    pub fn rules(&self) -> impl Iterator<Item = String> + '_ {
+        self.rules.iter().map(|(rule, _)| match rule.consecutive {
+            1 => rule.condition(),
+            n => format!("{} for {} runs in a row", rule.condition(), n),
+        })
+    }
+
+    /// Updates each rule with a run and returns the rules that changed state.
+    /// A rule on a phase the run skipped is left as it was.
+    pub fn observe(&mut self, result: &TestResult) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        for (rule, state) in &mut self.rules {
+            let Some(measured) = rule.metric.measure(result) else {
+                continue;
+            };
+            // The streak counts runs that point away from the current state.
+            if rule.comparison.holds(measured, rule.value) == state.breached {
+                state.streak = 0;
+                continue;
+            }
+            state.streak += 1;
+            if state.streak >= rule.consecutive {
+                state.breached = !state.breached;
+                state.streak = 0;
+                alerts.push(Alert {
+                    kind: if state.breached {
+                        AlertKind::Breach
+                    } else {
+                        AlertKind::Recovery
+                    },
+                    rule: rule.clone(),
+                    measured,
+                });
+            }
+        }
+        alerts
+    }
+
+    /// The rules whose condition holds in a run, with the measured value,
+    /// e.g. `download 42.1 Mbps (alert when download < 50 Mbps)`.
+    pub fn failing(&self, result: &TestResult) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter_map(|(rule, _)| {
+                let measured = rule.metric.measure(result)?;
+                rule.comparison.holds(measured, rule.value).then(|| {
+                    format!(
+                        "{} {} (alert when {})",
+                        rule.metric.name(),
+                        rule.metric.format_value(measured),
+                        rule.condition()
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Checks a run and sends any alerts, reporting delivery on the console.
+    /// `history` is the saved runs, ending with this one, for email.
+    pub async fn check(&mut self, result: &TestResult, history: &[HistoryEntry]) {
+        let alerts = self.observe(result);
+        if alerts.is_empty() {
+            return;
+        }
+        let failing = self.failing(result);
+        for alert in alerts {
+            for webhook in &self.webhooks {
+                report(&alert, webhook.target(), webhook.send(&alert, result).await);
+            }
+            if let Some(email) = &self.email {
+                let sent = email.send(&alert, result, &failing, history).await;
+                report(&alert, email.target(), sent);
+            }
+        }
+    }
+}
+
+fn report(alert: &Alert, target: &str, sent: anyhow::Result<()>) {
+    match sent {
+        Ok(()) => println!(
+            "🔔 Sent {} alert to {}: {}",
+            alert.kind.name(),
+            target,
+            alert.summary()
+        ),
+        Err(e) => eprintln!("❌ {:#}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::speedtest::TestMethod;
+
+    pub(super) fn result(download: f64) -> TestResult {
+        TestResult {
+            download_speed: download,
+            upload_speed: 0.0,
+            test_duration: 20.0,
+            method: TestMethod {
+                download: download > 0.0,
+                upload: false,
+                ..TestResult::sample().method
+            },
+            ..TestResult::sample()
+        }
+    }
+
+    #[test]
+    fn alerts_once_on_breach_and_once_on_recovery() {
+        let rule = AlertRule {
+            metric: Check::Download,
+            comparison: Comparison::Below,
+            value: 50.0,
+            consecutive: 2,
+        };
+        let mut alerter = Alerter::new(vec![rule], Vec::new(), None);
+        let mut kinds = Vec::new();
+        // 0.0 skips the download phase, which neither breaks nor extends a streak.
+        for download in [
+            40.0, 90.0, 40.0, 0.0, 45.0, 30.0, 20.0, 90.0, 40.0, 95.0, 96.0,
+        ] {
+            let alerts = alerter.observe(&result(download));
+            kinds.push(alerts.first().map(|alert| alert.kind));
+        }
+
+        use AlertKind::{Breach, Recovery};
+        assert_eq!(
+            kinds,
+            [
+                None,
+                None,
+                None,
+                None,
+                Some(Breach),
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(Recovery)
+            ]
+        );
+    }
+
+    #[test]
+    fn summaries_read_as_sentences() {
+        let rule = AlertRule {
+            metric: Check::Latency,
+            comparison: Comparison::Above,
+            value: 50.0,
+            consecutive: 3,
+        };
+        let breach = Alert {
+            kind: AlertKind::Breach,
+            rule: rule.clone(),
+            measured: 72.4,
+        };
+        let recovery = Alert {
+            kind: AlertKind::Recovery,
+            rule: AlertRule {
+                consecutive: 1,
+                ..rule
+            },
+            measured: 18.0,
+        };
+
+        assert_eq!(
+            breach.summary(),
+            "latency 72.4 ms > 50.0 ms for 3 runs in a row"
+        );
+        assert_eq!(
+            recovery.summary(),
+            "latency recovered: 18.0 ms, not > 50.0 ms"
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! Alerts as HTTP POSTs with a templated JSON body.
+//!
+//! A body template is JSON with `{{name}}` placeholders. String values are
+//! JSON-escaped but not quoted, so they go inside quotes in the template;
+//! numbers go outside. The `generic` and `slack` formats are built-in
+//! templates.
+
+use super::Alert;
+use crate::network::TestResult;
+use crate::utils::{get_quality_description, get_quality_score, hostname};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Placeholders a body template may use.
+pub const PLACEHOLDERS: [&str; 14] = [
+    "state",
+    "text",
+    "summary",
+    "condition",
+    "metric",
+    "comparison",
+    "threshold",
+    "consecutive",
+    "measured",
+    "host",
+    "server",
+    "timestamp",
+    "quality_score",
+    "quality",
+];
+
+const GENERIC: &str = r#"{"state": "{{state}}", "summary": "{{summary}}", "metric": "{{metric}}", "comparison": "{{comparison}}", "threshold": {{threshold}}, "consecutive": {{consecutive}}, "measured": {{measured}}, "host": "{{host}}", "server": "{{server}}", "timestamp": "{{timestamp}}", "quality_score": {{quality_score}}, "quality": "{{quality}}"}"#;
+
+const SLACK: &str = r#"{"text": "{{text}}"}"#;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// Every value as its own field.
+    #[default]
+    Generic,
+    /// A Slack incoming-webhook message; also accepted by Mattermost and
+    /// Rocket.Chat.
+    Slack,
+}
+
+/// One `[[alerts.webhooks]]` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// A body template used instead of `format`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Extra request headers, e.g. for authentication.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl WebhookConfig {
+    fn template(&self) -> &str {
+        match (&self.body, self.format) {
+            (Some(body), _) => body,
+            (None, WebhookFormat::Generic) => GENERIC,
+            (None, WebhookFormat::Slack) => SLACK,
+        }
+    }
+
+    /// Checks the URL, the header names and that the body renders to JSON.
+    pub fn validate(&self) -> Result<()> {
+        let url = reqwest::Url::parse(&self.url)
+            .map_err(|e| anyhow!("url: `{}` is not a URL: {}", self.url, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("url: `{}` is not an http or https URL", self.url);
+        }
+        for name in self.headers.keys() {
+            if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                bail!("headers: `{}` is not a valid header name", name);
+            }
+        }
+        let sample = render(self.template(), &sample_values()).context("body")?;
+        serde_json::from_str::<serde_json::Value>(&sample)
+            .map_err(|e| anyhow!("body: not valid JSON once filled in: {}", e))?;
+        Ok(())
+    }
+}
+
+/// Replaces every `{{name}}` in `template` with its value.
+pub fn render(template: &str, values: &[(&str, String)]) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            bail!("unclosed `{{{{` placeholder");
+        };
+        let name = rest[start + 2..start + end].trim();
+        let Some((_, value)) = values.iter().find(|(key, _)| *key == name) else {
+            bail!(
+                "unknown placeholder `{}` (expected one of {})",
+                name,
+                PLACEHOLDERS.join(", ")
+            );
+        };
+        out.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// A string's JSON encoding without the surrounding quotes.
+fn json_escape(s: &str) -> String {
+    let quoted = serde_json::to_string(s).expect("strings serialize");
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Placeholder values for an alert about `result`.
+fn values(alert: &Alert, result: &TestResult, host: &str) -> Vec<(&'static str, String)> {
+    let rule = &alert.rule;
+    let score = get_quality_score(result.download_speed, result.upload_speed, result.ping);
+    let metric = serde_json::to_value(rule.metric).expect("metrics serialize");
+    vec![
+        ("state", alert.kind.name().to_string()),
+        ("text", json_escape(&alert.headline(host))),
+        ("summary", json_escape(&alert.summary())),
+        ("condition", json_escape(&rule.condition())),
+        ("metric", metric.as_str().unwrap_or_default().to_string()),
+        ("comparison", json_escape(rule.comparison.symbol())),
+        ("threshold", rule.value.to_string()),
+        ("consecutive", rule.consecutive.to_string()),
+        ("measured", alert.measured.to_string()),
+        ("host", json_escape(host)),
+        ("server", json_escape(&result.server_name)),
+        ("timestamp", result.timestamp.to_rfc3339()),
+        ("quality_score", score.to_string()),
+        ("quality", get_quality_description(score).to_string()),
+    ]
+}
+
+/// Values that only form JSON where strings and numbers are placed right.
+fn sample_values() -> Vec<(&'static str, String)> {
+    const NUMBERS: [&str; 4] = ["threshold", "consecutive", "measured", "quality_score"];
+    PLACEHOLDERS
+        .iter()
+        .map(|&name| {
+            let sample = if NUMBERS.contains(&name) { "1" } else { "x" };
+            (name, sample.to_string())
+        })
+        .collect()
+}
+
+/// Sends alerts to one URL, retrying throttled requests, server errors and
+/// connection failures with exponential backoff.
+pub struct Webhook {
+    client: reqwest::Client,
+    config: WebhookConfig,
+    host: String,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl Webhook {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("the HTTP client builds"),
+            config,
+            host: hostname().unwrap_or_else(|| "unknown host".to_string()),
+            max_retries: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// The host alerts go to, for messages.
+    pub fn target(&self) -> &str {
+        let url = self
+            .config
+            .url
+            .split_once("://")
+            .map_or("", |(_, rest)| rest);
+        url.split(['/', '?']).next().unwrap_or_default()
+    }
+
+    pub async fn send(&self, alert: &Alert, result: &TestResult) -> Result<()> {
+        let body = render(self.config.template(), &values(alert, result, &self.host))?;
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match self.post(&body).await {
+                Ok(()) => return Ok(()),
+                Err(Attempt::Fatal(e)) => return Err(e),
+                Err(Attempt::Retryable(e)) => e,
+            };
+            if attempt == self.max_retries {
+                return Err(error.context(format!("Gave up after {} attempts", attempt + 1)));
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    async fn post(&self, body: &str) -> Result<(), Attempt> {
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            Attempt::Retryable(anyhow!(e).context(format!("Failed to reach {}", self.target())))
+        })?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        let error = anyhow!(
+            "Webhook {} failed with {}: {}",
+            self.target(),
+            status,
+            body.trim()
+        );
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            Err(Attempt::Retryable(error))
+        } else {
+            Err(Attempt::Fatal(error))
+        }
+    }
+}
+
+enum Attempt {
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::tests::result;
+    use crate::alerts::{AlertKind, AlertRule, Comparison};
+    use crate::thresholds::Check;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::{Arc, Mutex};
+
+    fn breach() -> Alert {
+        Alert {
+            kind: AlertKind::Breach,
+            rule: AlertRule {
+                metric: Check::Download,
+                comparison: Comparison::Below,
+                value: 50.0,
+                consecutive: 3,
+            },
+            measured: 42.1,
+        }
+    }
+
+    fn config(format: WebhookFormat, body: Option<&str>) -> WebhookConfig {
+        WebhookConfig {
+            url: "https://hooks.example.com/alerts".to_string(),
+            format,
+            body: body.map(str::to_string),
+            headers: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn templates_render_to_json() {
+        let values = values(&breach(), &result(42.1), "office \"pc\"");
+
+        let generic: serde_json::Value =
+            serde_json::from_str(&render(GENERIC, &values).unwrap()).unwrap();
+        assert_eq!(generic["state"], "breach");
+        assert_eq!(generic["metric"], "download");
+        assert_eq!(generic["threshold"], 50.0);
+        assert_eq!(generic["measured"], 42.1);
+        assert_eq!(generic["host"], "office \"pc\"");
+        assert_eq!(
+            generic["summary"],
+            "download 42.1 Mbps < 50.0 Mbps for 3 runs in a row"
+        );
+
+        let slack: serde_json::Value =
+            serde_json::from_str(&render(SLACK, &values).unwrap()).unwrap();
+        assert_eq!(
+            slack["text"],
+            "🔴 pingtest alert on office \"pc\": download 42.1 Mbps < 50.0 Mbps for 3 runs in a row"
+        );
+
+        config(WebhookFormat::Slack, None).validate().unwrap();
+        let error = |body| {
+            format!(
+                "{:#}",
+                config(WebhookFormat::Generic, Some(body))
+                    .validate()
+                    .unwrap_err()
+            )
+        };
+        assert!(error(r#"{"text": "{{speed}}"}"#).starts_with("body: unknown placeholder `speed`"));
+        assert!(error(r#"{"text": {{summary}}}"#).starts_with("body: not valid JSON"));
+    }
+
+    /// Bodies and one header of the requests received.
+    #[derive(Default)]
+    struct StandIn {
+        failures_left: Mutex<u32>,
+        requests: Mutex<Vec<(Option<String>, String)>>,
+    }
+
+    async fn receive(
+        State(stand_in): State<Arc<StandIn>>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        let key = headers
+            .get("x-api-key")
+            .map(|v| v.to_str().unwrap().to_string());
+        stand_in.requests.lock().unwrap().push((key, body));
+        let mut failures = stand_in.failures_left.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            StatusCode::BAD_GATEWAY
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_receiver_accepts() {
+        let stand_in = Arc::new(StandIn {
+            failures_left: Mutex::new(2),
+            ..StandIn::default()
+        });
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(Arc::clone(&stand_in));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut webhook = Webhook::new(WebhookConfig {
+            url,
+            headers: BTreeMap::from([("X-Api-Key".to_string(), "k3y".to_string())]),
+            ..config(WebhookFormat::Generic, None)
+        });
+        webhook.backoff = Duration::from_millis(1);
+        webhook.send(&breach(), &result(42.1)).await.unwrap();
+
+        let requests = stand_in.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        let (key, body) = &requests[2];
+        assert_eq!(key.as_deref(), Some("k3y"));
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["state"], "breach");
+    }
+}
@@ -0,0 +1,675 @@
+//! `pingtest api`: a local HTTP/JSON API for starting tests remotely.
+//!
+//! Every `/api` request needs `Authorization: Bearer <token>`. Only one test
+//! runs at a time; starting another while it runs is refused with 409
+//! Conflict, and `DELETE` stops it early. Progress is streamed as
+//! Server-Sent Events named like the `--stream-json` events, and the most
+//! recent tests are kept in memory.
+
+use crate::export::stream::StreamEvent;
+use crate::export::ResultDocument;
+use crate::history::{HistoryEntry, HistoryManager};
+use crate::network::speedtest::TestMethod;
+use crate::network::{Phase, Progress, SpeedTest, TestSettings};
+use crate::utils::{
+    get_quality_description, get_quality_score, validate_connections, validate_duration,
+};
+use anyhow::{Context, Result};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, watch, OwnedMutexGuard};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Finished tests kept for `GET /api/tests`.
+const KEPT_TESTS: usize = 20;
+
+/// Progress events buffered for a slow event-stream client.
+const EVENT_BUFFER: usize = 256;
+
+pub struct ApiOptions {
+    pub listen: SocketAddr,
+    pub token: String,
+    /// Used for whatever a start request leaves out.
+    pub method: TestMethod,
+    pub settings: TestSettings,
+    pub tag: Option<String>,
+}
+
+/// The body of `POST /api/tests`; every field is optional. `duration` must be
+/// 5-300 seconds and `connections` 1-16.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StartRequest {
+    /// Length of each throughput phase in seconds.
+    pub duration: Option<u64>,
+    pub connections: Option<u32>,
+    pub download: Option<bool>,
+    pub upload: Option<bool>,
+    pub tag: Option<String>,
+    /// Save the result to history.
+    #[serde(default)]
+    pub save: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Running,
+    Finished,
+    Failed,
+}
+
+/// What the API reports about one test.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestStatus {
+    pub id: String,
+    pub status: Status,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The phase in progress, while the test runs.
+    pub phase: Option<Phase>,
+    pub method: TestMethod,
+    pub tag: Option<String>,
+    pub save: bool,
+    pub error: Option<String>,
+}
+
+/// An event-stream message: its name and JSON data.
+type Message = (String, String);
+
+struct TestRun {
+    id: String,
+    state: Mutex<RunState>,
+    /// Set to stop the test early.
+    stop: watch::Sender<bool>,
+}
+
+struct RunState {
+    status: TestStatus,
+    result: Option<ResultDocument>,
+    /// Every message so far, replayed to clients that connect late.
+    sent: Vec<Message>,
+    /// Dropped when the test ends, which ends the event streams.
+    live: Option<broadcast::Sender<Message>>,
+}
+
+impl TestRun {
+    fn status(&self) -> TestStatus {
+        self.state.lock().unwrap().status.clone()
+    }
+
+    /// Records a message and passes it to connected event streams.
+    fn push(&self, data: serde_json::Value) {
+        let name = data["event"].as_str().unwrap_or("message").to_string();
+        let message = (name, data.to_string());
+        let mut state = self.state.lock().unwrap();
+        if let Some(live) = &state.live {
+            let _ = live.send(message.clone());
+        }
+        state.sent.push(message);
+    }
+
+    fn send(&self, event: &StreamEvent) {
+        self.push(serde_json::to_value(event).expect("stream events serialize"));
+    }
+
+    fn progress(&self, progress: Progress) {
+        if let Progress::PhaseStarted(phase) = progress {
+            self.state.lock().unwrap().status.phase = Some(phase);
+        }
+        self.send(&StreamEvent::from_progress(progress));
+    }
+
+    /// Stores the outcome, then sends it, so a client reacting to the
+    /// `result` or `error` event finds the test finished.
+    fn finish(&self, outcome: Result<ResultDocument>) {
+        let (status, error) = match &outcome {
+            Ok(_) => (Status::Finished, None),
+            Err(e) => (Status::Failed, Some(format!("{:#}", e))),
+        };
+        {
+            let mut state = self.state.lock().unwrap();
+            state.status.status = status;
+            state.status.finished_at = Some(Utc::now());
+            state.status.phase = None;
+            state.status.error = error.clone();
+            state.result = outcome.as_ref().ok().cloned();
+        }
+        match outcome {
+            Ok(document) => self.send(&StreamEvent::Result {
+                timestamp: Utc::now(),
+                result: &document,
+            }),
+            Err(_) => self.push(serde_json::json!({ "event": "error", "message": error })),
+        }
+        self.state.lock().unwrap().live = None;
+    }
+
+    /// The messages so far followed by live ones until the test ends.
+    fn events(&self) -> impl Stream<Item = Message> {
+        let state = self.state.lock().unwrap();
+        let live = match &state.live {
+            Some(live) => live.subscribe(),
+            // Already closed, so the stream ends after the replay.
+            None => broadcast::channel(1).1,
+        };
+        tokio_stream::iter(state.sent.clone())
+            .chain(BroadcastStream::new(live).filter_map(|message| message.ok()))
+    }
+}
+
+pub struct Api {
+    token: String,
+    method: TestMethod,
+    settings: TestSettings,
+    tag: Option<String>,
+    history: HistoryManager,
+    tests: Mutex<VecDeque<Arc<TestRun>>>,
+    /// Held by the test in progress.
+    running: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Api {
+    pub fn new(options: &ApiOptions, history: HistoryManager) -> Self {
+        Self {
+            token: options.token.clone(),
+            method: options.method.clone(),
+            settings: options.settings.clone(),
+            tag: options.tag.clone(),
+            history,
+            tests: Mutex::new(VecDeque::new()),
+            running: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    fn test(&self, id: &str) -> Result<Arc<TestRun>, ApiError> {
+        self.tests
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|test| test.id == id)
+            .cloned()
+            .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("No test {}", id)))
+    }
+
+    /// Starts a test in the background unless one is already running.
+    async fn start(self: &Arc<Self>, request: StartRequest) -> Result<TestStatus, ApiError> {
+        let method = TestMethod {
+            duration: request.duration.unwrap_or(self.method.duration),
+            connections: request.connections.unwrap_or(self.method.connections),
+            download: request.download.unwrap_or(self.method.download),
+            upload: request.upload.unwrap_or(self.method.upload),
+        };
+        if request
+            .duration
+            .is_some_and(|duration| !validate_duration(duration))
+        {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "duration must be 5-300 seconds",
+            ));
+        }
+        if request
+            .connections
+            .is_some_and(|connections| !validate_connections(connections))
+        {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "connections must be 1-16",
+            ));
+        }
+        if method.duration == 0 || method.connections == 0 {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "duration and connections must be at least 1",
+            ));
+        }
+        let guard = match Arc::clone(&self.running).try_lock_owned() {
+            Ok(guard) => guard,
+            Err(_) => {
+                let running = self
+                    .tests
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|test| test.status().status == Status::Running)
+                    .map(|test| test.id.clone());
+                match running {
+                    Some(id) => {
+                        return Err(ApiError::new(
+                            StatusCode::CONFLICT,
+                            format!("Test {} is already running", id),
+                        ))
+                    }
+                    // The last test has finished and is only sending its result.
+                    None => Arc::clone(&self.running).lock_owned().await,
+                }
+            }
+        };
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let status = TestStatus {
+            id: id.clone(),
+            status: Status::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            phase: None,
+            method,
+            tag: request.tag.or_else(|| self.tag.clone()),
+            save: request.save,
+            error: None,
+        };
+        let test = Arc::new(TestRun {
+            id,
+            state: Mutex::new(RunState {
+                status: status.clone(),
+                result: None,
+                sent: Vec::new(),
+                live: Some(broadcast::channel(EVENT_BUFFER).0),
+            }),
+            stop: watch::channel(false).0,
+        });
+        {
+            let mut tests = self.tests.lock().unwrap();
+            tests.push_back(Arc::clone(&test));
+            while tests.len() > KEPT_TESTS {
+                tests.pop_front();
+            }
+        }
+        tokio::spawn(Arc::clone(self).run(test, guard));
+        Ok(status)
+    }
+
+    /// Asks a running test to stop; it finishes with a partial result.
+    fn stop(&self, id: &str) -> Result<TestStatus, ApiError> {
+        let test = self.test(id)?;
+        let status = test.status();
+        if status.status != Status::Running {
+            return Err(ApiError::new(
+                StatusCode::CONFLICT,
+                format!("Test {} is not running", id),
+            ));
+        }
+        test.stop.send_replace(true);
+        Ok(status)
+    }
+
+    async fn run(self: Arc<Self>, test: Arc<TestRun>, running: OwnedMutexGuard<()>) {
+        let TestStatus {
+            method, tag, save, ..
+        } = test.status();
+        let outcome = async {
+            let result = SpeedTest::new()
+                .with_settings(self.settings.clone())
+                .with_stop(test.stop.subscribe())
+                .run_test_with_progress(
+                    method.duration,
+                    method.connections,
+                    !method.download,
+                    !method.upload,
+                    |progress| test.progress(progress),
+                )
+                .await?;
+            let quality_score =
+                get_quality_score(result.download_speed, result.upload_speed, result.ping);
+            let document = ResultDocument::new(
+                &result,
+                quality_score,
+                get_quality_description(quality_score),
+                tag.clone(),
+            );
+            if save {
+                self.history
+                    .add_entry(HistoryEntry::from_result(&result, tag, None))
+                    .await?;
+            }
+            Ok(document)
+        };
+        let outcome = outcome.await;
+        test.finish(outcome);
+        // Held until the result is stored and sent, so no other test starts
+        // while this one still reports running.
+        drop(running);
+    }
+}
+
+/// An error response with a JSON `{"error": ...}` body.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.message }));
+        (self.status, body).into_response()
+    }
+}
+
+/// The `/api` routes, all of which need the bearer token.
+pub fn routes(api: Arc<Api>) -> Router {
+    Router::new()
+        .route("/api/tests", get(list_tests).post(start_test))
+        .route("/api/tests/{id}", get(test_status).delete(stop_test))
+        .route("/api/tests/{id}/events", get(test_events))
+        .route("/api/tests/{id}/result", get(test_result))
+        .route("/api/history", get(history))
+        .route_layer(middleware::from_fn_with_state(Arc::clone(&api), authorize))
+        .with_state(api)
+}
+
+/// Serves the API until the process is stopped.
+pub async fn serve(options: ApiOptions) -> Result<()> {
+    let listen = options.listen;
+    let api = Arc::new(Api::new(&options, HistoryManager::new().await?));
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    println!("🔌 Serving the pingtest API on http://{}/api", listen);
+    let app = Router::new().route("/", get(index)).merge(routes(api));
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn authorize(State(api): State<Arc<Api>>, request: Request, next: Next) -> Response {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !token.is_some_and(|token| same_token(token, &api.token)) {
+        let error = ApiError::new(StatusCode::UNAUTHORIZED, "Missing or wrong bearer token");
+        return ([(header::WWW_AUTHENTICATE, "Bearer")], error).into_response();
+    }
+    next.run(request).await
+}
+
+/// Compares in time independent of where the tokens differ.
+pub(crate) fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn index() -> impl IntoResponse {
+    "pingtest API - POST /api/tests to start a test\n"
+}
+
+async fn list_tests(State(api): State<Arc<Api>>) -> Json<Vec<TestStatus>> {
+    let tests = api.tests.lock().unwrap();
+    Json(tests.iter().rev().map(|test| test.status()).collect())
+}
+
+async fn start_test(State(api): State<Arc<Api>>, body: String) -> Result<Response, ApiError> {
+    let request = if body.trim().is_empty() {
+        StartRequest::default()
+    } else {
+        serde_json::from_str(&body).map_err(|e| {
+            ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid request: {}", e))
+        })?
+    };
+    let status = api.start(request).await?;
+    let location = format!("/api/tests/{}", status.id);
+    Ok((
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, location)],
+        Json(status),
+    )
+        .into_response())
+}
+
+async fn test_status(
+    State(api): State<Arc<Api>>,
+    Path(id): Path<String>,
+) -> Result<Json<TestStatus>, ApiError> {
+    Ok(Json(api.test(&id)?.status()))
+}
+
+async fn stop_test(
+    State(api): State<Arc<Api>>,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    Ok((StatusCode::ACCEPTED, Json(api.stop(&id)?)).into_response())
+}
+
+async fn test_events(
+    State(api): State<Arc<Api>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let events = api
+        .test(&id)?
+        .events()
+        .map(|(name, data)| Ok::<_, Infallible>(Event::default().event(name).data(data)));
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+async fn test_result(
+    State(api): State<Arc<Api>>,
+    Path(id): Path<String>,
+) -> Result<Json<ResultDocument>, ApiError> {
+    let test = api.test(&id)?;
+    let state = test.state.lock().unwrap();
+    match (&state.result, &state.status.error) {
+        (Some(result), _) => Ok(Json(result.clone())),
+        (None, Some(error)) => Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("Test {} failed: {}", id, error),
+        )),
+        (None, None) => Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("Test {} is still running", id),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    #[serde(default = "default_days")]
+    days: u32,
+}
+
+fn default_days() -> u32 {
+    30
+}
+
+/// A history entry with the quality score the CLI would give it.
+#[derive(Serialize)]
+struct ScoredEntry {
+    #[serde(flatten)]
+    entry: HistoryEntry,
+    quality_score: u8,
+}
+
+async fn history(
+    State(api): State<Arc<Api>>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<ScoredEntry>>, ApiError> {
+    let entries = api.history.get_entries_since(query.days).await?;
+    Ok(Json(
+        entries
+            .into_iter()
+            .map(|entry| ScoredEntry {
+                quality_score: get_quality_score(
+                    entry.download_speed,
+                    entry.upload_speed,
+                    entry.ping,
+                ),
+                entry,
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn start() -> (String, reqwest::Client) {
+        let options = ApiOptions {
+            listen: "127.0.0.1:0".parse().unwrap(),
+            token: "secret".to_string(),
+            method: TestMethod {
+                duration: 1,
+                connections: 1,
+                download: true,
+                upload: false,
+            },
+            settings: TestSettings::default(),
+            tag: None,
+        };
+        let path = std::env::temp_dir().join(format!("pingtest-{}.json", uuid::Uuid::new_v4()));
+        let history = HistoryManager::with_path(path).await.unwrap();
+        let app = routes(Arc::new(Api::new(&options, history)));
+        let listener = tokio::net::TcpListener::bind(options.listen).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}", addr), reqwest::Client::new())
+    }
+
+    async fn get_json(client: &reqwest::Client, url: &str) -> serde_json::Value {
+        let response = client.get(url).bearer_auth("secret").send().await.unwrap();
+        serde_json::from_str(&response.text().await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_bearer_token() {
+        let (url, client) = start().await;
+
+        let missing = client.get(format!("{}/api/tests", url)).send().await;
+        let wrong = client
+            .get(format!("{}/api/history", url))
+            .bearer_auth("guess")
+            .send()
+            .await;
+        let right = client
+            .get(format!("{}/api/history", url))
+            .bearer_auth("secret")
+            .send()
+            .await;
+
+        assert_eq!(missing.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(wrong.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(right.unwrap().text().await.unwrap(), "[]");
+    }
+
+    #[tokio::test]
+    async fn runs_one_test_at_a_time_and_streams_its_progress() {
+        let (url, client) = start().await;
+        let start_test = || {
+            client
+                .post(format!("{}/api/tests", url))
+                .bearer_auth("secret")
+                .body(r#"{"tag": "api", "save": true}"#)
+                .send()
+        };
+
+        let started = start_test().await.unwrap();
+        assert_eq!(started.status(), StatusCode::ACCEPTED);
+        let status: serde_json::Value =
+            serde_json::from_str(&started.text().await.unwrap()).unwrap();
+        let id = status["id"].as_str().unwrap().to_string();
+        assert_eq!(status["status"], "running");
+        assert_eq!(status["method"]["upload"], false);
+        assert_eq!(start_test().await.unwrap().status(), StatusCode::CONFLICT);
+
+        // The stream replays what happened so far and ends with the result.
+        let events = client
+            .get(format!("{}/api/tests/{}/events", url, id))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(events.starts_with("event: phase_start\ndata: {"));
+        assert!(events.contains("event: sample\n"));
+        assert!(events.contains("event: result\n"));
+
+        let status = get_json(&client, &format!("{}/api/tests/{}", url, id)).await;
+        assert_eq!(status["status"], "finished");
+        let result = get_json(&client, &format!("{}/api/tests/{}/result", url, id)).await;
+        assert_eq!(result["tag"], "api");
+        assert!(result["upload"].is_null());
+        let history = get_json(&client, &format!("{}/api/history?days=1", url)).await;
+        assert_eq!(history.as_array().unwrap().len(), 1);
+        assert_eq!(history[0]["tag"], "api");
+        assert_eq!(history[0]["quality_score"], result["quality"]["score"]);
+
+        assert_eq!(start_test().await.unwrap().status(), StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn rejects_out_of_range_requests_and_stops_tests() {
+        let (url, client) = start().await;
+        let post = |body: &'static str| {
+            client
+                .post(format!("{}/api/tests", url))
+                .bearer_auth("secret")
+                .body(body)
+                .send()
+        };
+        for body in [r#"{"duration": 100000000}"#, r#"{"connections": 64}"#] {
+            assert_eq!(post(body).await.unwrap().status(), StatusCode::BAD_REQUEST);
+        }
+
+        let started = post(r#"{"duration": 300}"#).await.unwrap();
+        assert_eq!(started.status(), StatusCode::ACCEPTED);
+        let status: serde_json::Value =
+            serde_json::from_str(&started.text().await.unwrap()).unwrap();
+        let test_url = format!("{}/api/tests/{}", url, status["id"].as_str().unwrap());
+        let stop = || client.delete(&test_url).bearer_auth("secret").send();
+        assert_eq!(stop().await.unwrap().status(), StatusCode::ACCEPTED);
+
+        // The event stream ends once the stopped test has finished.
+        client
+            .get(format!("{}/events", test_url))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let result = get_json(&client, &format!("{}/result", test_url)).await;
+        assert_eq!(result["partial"], true);
+        assert_eq!(stop().await.unwrap().status(), StatusCode::CONFLICT);
+        let missing = client
+            .delete(format!("{}/api/tests/nope", url))
+            .bearer_auth("secret")
+            .send()
+            .await;
+        assert_eq!(missing.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+}
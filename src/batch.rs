@@ -0,0 +1,101 @@
+//! Aggregate statistics over repeated runs (`--runs`).
+
+use crate::history::anomaly::median;
+use crate::network::TestResult;
+use crate::thresholds::Check;
+
+/// Two-sided 95% Student's t critical values for 1 to 30 degrees of freedom.
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// The metrics summarised for a batch, in display order.
+pub const METRICS: [Check; 6] = [
+    Check::Download,
+    Check::Upload,
+    Check::Latency,
+    Check::Jitter,
+    Check::Loss,
+    Check::Score,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Half-width of the 95% confidence interval of the mean, or `None`
+    /// with fewer than two values.
+    pub ci95: Option<f64>,
+}
+
+impl Summary {
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let ci95 = (values.len() >= 2).then(|| {
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            t_critical_95(values.len() - 1) * variance.sqrt() / n.sqrt()
+        });
+        Some(Self {
+            count: values.len(),
+            mean,
+            median: median(values),
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            ci95,
+        })
+    }
+}
+
+/// A summary per metric. Metrics whose phase was skipped in every run are
+/// left out.
+pub fn summarize(results: &[TestResult]) -> Vec<(Check, Summary)> {
+    METRICS
+        .into_iter()
+        .filter_map(|check| {
+            let values: Vec<f64> = results.iter().filter_map(|r| check.measure(r)).collect();
+            Summary::from_values(&values).map(|summary| (check, summary))
+        })
+        .collect()
+}
+
+fn t_critical_95(degrees_of_freedom: usize) -> f64 {
+    match degrees_of_freedom {
+        0 => f64::NAN,
+        1..=30 => T_95[degrees_of_freedom - 1],
+        31..=40 => 2.021,
+        41..=60 => 2.000,
+        61..=120 => 1.980,
+        _ => 1.960,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_with_a_t_based_interval() {
+        let summary = Summary::from_values(&[48.0, 52.0, 50.0, 46.0, 54.0]).unwrap();
+
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.mean, 50.0);
+        assert_eq!(summary.median, 50.0);
+        assert_eq!((summary.min, summary.max), (46.0, 54.0));
+        // s = sqrt(10), t(4) = 2.776
+        let ci = summary.ci95.unwrap();
+        assert!((ci - 2.776 * 10f64.sqrt() / 5f64.sqrt()).abs() < 1e-9);
+
+        let single = Summary::from_values(&[50.0]).unwrap();
+        assert_eq!(single.ci95, None);
+        assert_eq!(Summary::from_values(&[]), None);
+    }
+}
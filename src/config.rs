@@ -0,0 +1,495 @@
+//! The config file, `config.toml` in the platform config directory
+//! (`~/.config/pingtest/config.toml` on Linux).
+//!
+//! Settings resolve as command-line flags, then `PINGTEST_*` environment
+//! variables, then this file, then built-in defaults. File values are
+//! applied as the defaults of the matching flags, so a flag or variable
+//! always wins. A missing file is the same as an empty one.
+//!
+//! `[profiles.<name>]` sections hold option values for one kind of test,
+//! selected with `--profile <name>`. They sit between the file's other
+//! settings and environment variables.
+
+use crate::alerts::email::EmailConfig;
+use crate::alerts::webhook::WebhookConfig;
+use crate::alerts::AlertRule;
+use crate::daemon::cron::Schedule;
+use crate::export::ExportFormat;
+use crate::network::TestSettings;
+use crate::utils;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable overriding the config file location.
+pub const CONFIG_FILE_ENV: &str = "PINGTEST_CONFIG";
+
+pub const THEMES: [&str; 8] = [
+    "auto",
+    "dracula",
+    "nord",
+    "solarized-dark",
+    "solarized-light",
+    "monokai",
+    "github",
+    "tokyo-night",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub general: GeneralConfig,
+    pub ping: PingConfig,
+    pub servers: ServersConfig,
+    pub export: ExportConfig,
+    pub daemon: DaemonConfig,
+    #[serde(skip_serializing_if = "AlertsConfig::is_empty")]
+    pub alerts: AlertsConfig,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Option values keyed by option name, e.g. `connections = 8` or
+/// `otlp_header = ["X-Site=office"]`. Dashes and underscores are both
+/// accepted in names.
+pub type Profile = BTreeMap<String, toml::Value>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeneralConfig {
+    pub theme: String,
+    /// Length of each throughput phase in seconds.
+    pub default_duration: u64,
+    /// Save every result to history, as if `--save` were given.
+    pub auto_save: bool,
+    pub default_connections: u32,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        Self {
+            theme: "auto".to_string(),
+            default_duration: 15,
+            auto_save: false,
+            default_connections: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PingConfig {
+    /// Latency probes sent per test.
+    pub packet_count: u32,
+    /// A probe answered later than this counts as lost.
+    pub timeout_ms: u64,
+}
+
+impl Default for PingConfig {
+    fn default() -> Self {
+        Self {
+            packet_count: 10,
+            timeout_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServersConfig {
+    /// ISO 3166 country codes, e.g. `US`, tried before nearer servers.
+    pub preferred_countries: Vec<String>,
+    /// Servers farther away are never used.
+    pub max_distance_km: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportConfig {
+    /// Format for `--export` files whose extension does not name one.
+    pub default_format: Option<ExportFormat>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// Cron expressions used when `pingtest daemon` gets no `--schedule`.
+    pub schedules: Vec<String>,
+    /// Maximum random delay per run, e.g. `60s`.
+    pub random_delay: Option<String>,
+}
+
+/// Rules checked after every scheduled test, and where their alerts go.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    pub rules: Vec<AlertRule>,
+    pub webhooks: Vec<WebhookConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<EmailConfig>,
+}
+
+impl AlertsConfig {
+    fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.webhooks.is_empty() && self.email.is_none()
+    }
+}
+
+impl Config {
+    /// Loads [`Config::default_path`], or the defaults if it does not exist.
+    pub async fn load() -> Result<Self> {
+        Self::load_from(&Self::default_path()).await
+    }
+
+    pub async fn load_from(path: &Path) -> Result<Self> {
+        let data = match tokio::fs::read_to_string(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let config: Self =
+            toml::from_str(&data).with_context(|| format!("Failed to parse {}", path.display()))?;
+        config
+            .validate()
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Writes the config to [`Config::default_path`].
+    pub async fn save(&self) -> Result<()> {
+        self.save_to(&Self::default_path()).await
+    }
+
+    pub async fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        tokio::fs::write(path, self.to_toml()?)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// `$PINGTEST_CONFIG`, or `pingtest/config.toml` in the platform config
+    /// directory.
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os(CONFIG_FILE_ENV) {
+            return PathBuf::from(path);
+        }
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("pingtest")
+            .join("config.toml")
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// Checks values the types alone do not constrain. Errors name the key.
+    pub fn validate(&self) -> Result<()> {
+        let general = &self.general;
+        if !THEMES.contains(&general.theme.as_str()) {
+            bail!(
+                "general.theme: unknown theme `{}` (expected one of {})",
+                general.theme,
+                THEMES.join(", ")
+            );
+        }
+        in_range(
+            "general.default_duration",
+            general.default_duration,
+            1,
+            3600,
+        )?;
+        in_range(
+            "general.default_connections",
+            general.default_connections.into(),
+            1,
+            64,
+        )?;
+        in_range("ping.packet_count", self.ping.packet_count.into(), 1, 1000)?;
+        in_range("ping.timeout_ms", self.ping.timeout_ms, 1, 60_000)?;
+        for (i, code) in self.servers.preferred_countries.iter().enumerate() {
+            if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+                bail!(
+                    "servers.preferred_countries[{}]: `{}` is not a two-letter country code",
+                    i,
+                    code
+                );
+            }
+        }
+        for (i, expression) in self.daemon.schedules.iter().enumerate() {
+            if let Err(e) = expression.parse::<Schedule>() {
+                bail!("daemon.schedules[{}]: {}", i, e);
+            }
+        }
+        if let Some(delay) = &self.daemon.random_delay {
+            if let Err(e) = utils::parse_duration(delay) {
+                bail!("daemon.random_delay: {}", e);
+            }
+        }
+        for (i, rule) in self.alerts.rules.iter().enumerate() {
+            let key = format!("alerts.rules[{}].consecutive", i);
+            in_range(&key, rule.consecutive.into(), 1, 1000)?;
+        }
+        for (i, webhook) in self.alerts.webhooks.iter().enumerate() {
+            webhook
+                .validate()
+                .map_err(|e| anyhow!("alerts.webhooks[{}].{:#}", i, e))?;
+        }
+        if let Some(email) = &self.alerts.email {
+            email
+                .validate()
+                .map_err(|e| anyhow!("alerts.email.{:#}", e))?;
+        }
+        if !self.alerts.rules.is_empty()
+            && self.alerts.webhooks.is_empty()
+            && self.alerts.email.is_none()
+        {
+            bail!("alerts.rules: there are no alerts.webhooks or alerts.email to send alerts to");
+        }
+        Ok(())
+    }
+
+    /// The profile called `name`.
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        match self.profiles.get(name) {
+            Some(profile) => Ok(profile),
+            None if self.profiles.is_empty() => {
+                bail!("Unknown profile `{}`: the config file defines none", name)
+            }
+            None => bail!(
+                "Unknown profile `{}` (expected one of {})",
+                name,
+                self.profiles
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// The `[servers]` and `[ping]` settings for a [`SpeedTest`](crate::network::SpeedTest).
+    pub fn test_settings(&self) -> TestSettings {
+        TestSettings {
+            preferred_countries: self.servers.preferred_countries.clone(),
+            max_distance_km: self.servers.max_distance_km,
+            ping_count: self.ping.packet_count,
+            ping_timeout: Duration::from_millis(self.ping.timeout_ms),
+        }
+    }
+
+    /// File values as defaults for command-line options, by option id.
+    pub fn cli_defaults(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("theme", self.general.theme.clone()),
+            ("duration", self.general.default_duration.to_string()),
+            ("connections", self.general.default_connections.to_string()),
+            ("save", self.general.auto_save.to_string()),
+        ]
+    }
+}
+
+/// A profile value as command-line values: one per array element.
+pub fn option_values(value: &toml::Value) -> Result<Vec<String>> {
+    Ok(match value {
+        toml::Value::String(s) => vec![s.clone()],
+        toml::Value::Integer(n) => vec![n.to_string()],
+        toml::Value::Float(x) => vec![x.to_string()],
+        toml::Value::Boolean(b) => vec![b.to_string()],
+        toml::Value::Array(items) => {
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                if matches!(item, toml::Value::Array(_) | toml::Value::Table(_)) {
+                    bail!("arrays may only hold strings, numbers or booleans");
+                }
+                values.extend(option_values(item)?);
+            }
+            values
+        }
+        toml::Value::Datetime(_) | toml::Value::Table(_) => {
+            bail!("expected a string, number, boolean or array")
+        }
+    })
+}
+
+fn in_range(key: &str, value: u64, min: u64, max: u64) -> Result<()> {
+    if !(min..=max).contains(&value) {
+        bail!(
+            "{}: must be between {} and {}, got {}",
+            key,
+            min,
+            max,
+            value
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thresholds::Check;
+
+    #[test]
+    fn parses_the_documented_file() {
+        let config: Config = toml::from_str(
+            r#"
+            [general]
+            theme = "dracula"
+            default_duration = 15
+            auto_save = true
+            default_connections = 4
+
+            [ping]
+            packet_count = 20
+            timeout_ms = 500
+
+            [servers]
+            preferred_countries = ["US", "CA", "GB"]
+            max_distance_km = 1000
+
+            [export]
+            default_format = "json"
+            "#,
+        )
+        .unwrap();
+
+        config.validate().unwrap();
+        assert_eq!(config.general.theme, "dracula");
+        assert!(config.general.auto_save);
+        assert_eq!(
+            config.test_settings(),
+            TestSettings {
+                preferred_countries: vec!["US".into(), "CA".into(), "GB".into()],
+                max_distance_km: Some(1000),
+                ping_count: 20,
+                ping_timeout: Duration::from_millis(500),
+            }
+        );
+        assert_eq!(config.export.default_format, Some(ExportFormat::Json));
+
+        let round_trip: Config = toml::from_str(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(round_trip, config);
+    }
+
+    #[test]
+    fn errors_name_the_bad_key() {
+        let invalid = |toml: &str| {
+            let config: Config = toml::from_str(toml).unwrap();
+            config.validate().unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            invalid("[general]\ndefault_duration = 0"),
+            "general.default_duration: must be between 1 and 3600, got 0"
+        );
+        assert!(invalid("[general]\ntheme = \"neon\"")
+            .starts_with("general.theme: unknown theme `neon`"));
+        assert_eq!(
+            invalid("[daemon]\nschedules = [\"@hourly\", \"0 24 * * *\"]"),
+            "daemon.schedules[1]: invalid hour field in cron expression `0 24 * * *`: 24 is out of range 0-23"
+        );
+        assert_eq!(
+            invalid("[[alerts.rules]]\nmetric = \"download\"\ncomparison = \"<\"\nvalue = 50"),
+            "alerts.rules: there are no alerts.webhooks or alerts.email to send alerts to"
+        );
+        assert_eq!(
+            invalid("[alerts.email]\nserver = \"smtp.example.com\"\nfrom = \"pingtest@example.com\"\nto = []"),
+            "alerts.email.to: needs at least one address"
+        );
+        assert_eq!(
+            invalid("[[alerts.webhooks]]\nurl = \"ftp://example.com\""),
+            "alerts.webhooks[0].url: `ftp://example.com` is not an http or https URL"
+        );
+        let unknown = toml::from_str::<Config>("[general]\nthem = \"auto\"").unwrap_err();
+        assert!(unknown.to_string().contains("unknown field `them`"));
+        assert!(toml::from_str::<Config>("[display]\nshow_graph = true").is_err());
+    }
+
+    #[test]
+    fn profiles_hold_option_values() {
+        let config: Config = toml::from_str(
+            r#"
+            [profiles.office]
+            connections = 8
+            duration = 30
+            tag = "office"
+
+            [profiles.tethered]
+            connections = 1
+            otlp-header = ["X-Site=car", "X-Link=lte"]
+            "#,
+        )
+        .unwrap();
+
+        let office = config.profile("office").unwrap();
+        assert_eq!(option_values(&office["duration"]).unwrap(), ["30"]);
+        assert_eq!(option_values(&office["tag"]).unwrap(), ["office"]);
+        let tethered = config.profile("tethered").unwrap();
+        assert_eq!(
+            option_values(&tethered["otlp-header"]).unwrap(),
+            ["X-Site=car", "X-Link=lte"]
+        );
+        assert_eq!(
+            config.profile("home").unwrap_err().to_string(),
+            "Unknown profile `home` (expected one of office, tethered)"
+        );
+
+        let round_trip: Config = toml::from_str(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(round_trip, config);
+    }
+
+    #[test]
+    fn alert_rules_name_a_metric_and_comparison() {
+        let config: Config = toml::from_str(
+            r#"
+            [[alerts.rules]]
+            metric = "loss"
+            comparison = ">="
+            value = 2
+            consecutive = 3
+
+            [[alerts.webhooks]]
+            url = "https://hooks.slack.com/services/T0/B0/X"
+            format = "slack"
+            "#,
+        )
+        .unwrap();
+
+        config.validate().unwrap();
+        assert_eq!(config.alerts.rules[0].metric, Check::Loss);
+        assert_eq!(config.alerts.rules[0].condition(), "packet loss >= 2%");
+        let bad_metric = toml::from_str::<Config>(
+            "[[alerts.rules]]\nmetric = \"speed\"\ncomparison = \"<\"\nvalue = 1",
+        );
+        assert!(bad_metric
+            .unwrap_err()
+            .to_string()
+            .contains("unknown variant `speed`"));
+
+        let round_trip: Config = toml::from_str(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(round_trip, config);
+    }
+
+    #[tokio::test]
+    async fn missing_file_loads_defaults_and_saves() {
+        let path = std::env::temp_dir()
+            .join(format!("pingtest-{}", uuid::Uuid::new_v4()))
+            .join("config.toml");
+
+        let config = Config::load_from(&path).await.unwrap();
+        assert_eq!(config.general.theme, "auto");
+        assert_eq!(config.general.default_duration, 15);
+        assert_eq!(config.general.default_connections, 4);
+
+        config.save_to(&path).await.unwrap();
+        assert_eq!(Config::load_from(&path).await.unwrap(), config);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
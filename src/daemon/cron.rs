@@ -0,0 +1,246 @@
+//! Standard five-field cron expressions (`minute hour day month weekday`).
+//!
+//! Fields accept `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`),
+//! lists (`1,15`) and English month and weekday names. Weekdays run from 0
+//! (Sunday) to 6, with 7 also meaning Sunday. As in crontab, when both the
+//! day-of-month and weekday fields are restricted a time matches either.
+//! `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are shorthands.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use std::fmt;
+use std::str::FromStr;
+
+/// Stop looking for a match this many years ahead (e.g. for `0 0 30 2 *`).
+const SEARCH_YEARS: i32 = 5;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    source: String,
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let source = s.trim();
+        let expanded = match source {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!(
+                "invalid cron expression `{}`: expected 5 fields (minute hour day month weekday), found {}",
+                source,
+                fields.len()
+            );
+        };
+        // One flat message, so it reads in full wherever it is displayed.
+        let field = |name: &str, parsed: Result<u64>| {
+            parsed.map_err(|e| {
+                anyhow!(
+                    "invalid {} field in cron expression `{}`: {}",
+                    name,
+                    source,
+                    e
+                )
+            })
+        };
+
+        let days_of_week = field("weekday", parse_field(weekday, 0, 7, &WEEKDAYS))?;
+        Ok(Self {
+            source: source.to_string(),
+            minutes: field("minute", parse_field(minute, 0, 59, &[]))?,
+            hours: field("hour", parse_field(hour, 0, 23, &[]))? as u32,
+            days_of_month: field("day", parse_field(day, 1, 31, &[]))? as u32,
+            months: field("month", parse_field(month, 1, 12, &MONTHS))? as u16,
+            // Fold 7 onto 0 so both mean Sunday.
+            days_of_week: ((days_of_week | days_of_week >> 7) & 0x7f) as u8,
+            day_of_month_restricted: !day.starts_with('*'),
+            day_of_week_restricted: !weekday.starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Schedule {
+    /// The first matching minute strictly after `after`, or `None` if there
+    /// is none in the next few years. Times that do not exist in `after`'s
+    /// time zone (a daylight-saving gap) are skipped.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let start = after.naive_local();
+        let mut t = start.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        while t.year() <= start.year() + SEARCH_YEARS {
+            if !bit(u64::from(self.months), t.month()) {
+                t = first_of_next_month(t)?;
+            } else if !self.matches_day(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(u64::from(self.hours), t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+            } else if !bit(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else if let Some(at) = tz.from_local_datetime(&t).earliest() {
+                return Some(at);
+            } else {
+                t += Duration::minutes(1);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = bit(u64::from(self.days_of_month), date.day());
+        let weekday = bit(
+            u64::from(self.days_of_week),
+            date.weekday().num_days_from_sunday(),
+        );
+        if self.day_of_month_restricted && self.day_of_week_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+fn bit(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn first_of_next_month(t: NaiveDateTime) -> Option<NaiveDateTime> {
+    let (year, month) = if t.month() == 12 {
+        (t.year() + 1, 1)
+    } else {
+        (t.year(), t.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)
+}
+
+/// Parses one field into a bit set of the values it allows.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .with_context(|| format!("`{}` is not a valid step", step))?;
+                if step == 0 {
+                    bail!("step in `{}` must be at least 1", part);
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (low, high) = if range == "*" {
+            (min, max)
+        } else if let Some((low, high)) = range.split_once('-') {
+            (
+                parse_value(low, min, max, names)?,
+                parse_value(high, min, max, names)?,
+            )
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // `5/15` means every 15 from 5 onwards.
+            (value, if step > 1 { max } else { value })
+        };
+        if low > high {
+            bail!("range `{}` is backwards", range);
+        }
+        for value in (low..=high).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32> {
+    let lower = value.to_ascii_lowercase();
+    if let Some(index) = names.iter().position(|name| *name == lower) {
+        // Months are numbered from 1 and weekdays from 0, like their fields.
+        return Ok(index as u32 + min);
+    }
+    match value.parse::<u32>() {
+        Ok(n) if (min..=max).contains(&n) => Ok(n),
+        Ok(n) => bail!("{} is out of range {}-{}", n, min, max),
+        Err(_) => bail!("`{}` is not a number or name", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn finds_next_weekday_quarter_hour() {
+        let schedule: Schedule = "*/15 * * * mon-fri".parse().unwrap();
+
+        // Friday 2026-01-09 23:40 → 23:45, then Monday 00:00.
+        let first = schedule.next_after(&at("2026-01-09T23:40:00Z")).unwrap();
+        assert_eq!(first, at("2026-01-09T23:45:00Z"));
+        let second = schedule.next_after(&first).unwrap();
+        assert_eq!(second, at("2026-01-12T00:00:00Z"));
+    }
+
+    #[test]
+    fn matches_day_of_month_or_weekday_when_both_are_restricted() {
+        let schedule: Schedule = "30 6 1 * 0".parse().unwrap();
+
+        // Thursday 1 January, then Sunday 4 January.
+        let first = schedule.next_after(&at("2025-12-31T12:00:00Z")).unwrap();
+        assert_eq!(first, at("2026-01-01T06:30:00Z"));
+        assert_eq!(
+            schedule.next_after(&first).unwrap(),
+            at("2026-01-04T06:30:00Z")
+        );
+        let sunday: Schedule = "0 0 * * 7".parse().unwrap();
+        assert_eq!(
+            sunday.next_after(&at("2026-01-01T00:00:00Z")).unwrap(),
+            at("2026-01-04T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn reports_the_bad_field() {
+        let error = "*/0 * * * *".parse::<Schedule>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid minute field in cron expression `*/0 * * * *`: step in `*/0` must be at least 1"
+        );
+        let error = "0 24 * * *".parse::<Schedule>().unwrap_err();
+        assert!(error.to_string().contains("invalid hour field"));
+        assert!("0 0 30 2 *"
+            .parse::<Schedule>()
+            .unwrap()
+            .next_after(&at("2026-01-01T00:00:00Z"))
+            .is_none());
+        assert!("* * * *".parse::<Schedule>().is_err());
+    }
+}
@@ -0,0 +1,429 @@
+//! Unattended, cron-scheduled test runs (`pingtest daemon`).
+//!
+//! Every scheduled time gets its own random delay so a fleet started from
+//! the same configuration does not test in lockstep. A run that is due while
+//! the previous one is still going is skipped rather than queued. Time comes
+//! from a [`Clock`] so the loop can be driven by a fake clock in tests.
+
+pub mod cron;
+
+use crate::alerts::Alerter;
+use crate::history::{HistoryEntry, HistoryManager};
+use crate::metrics::mqtt::Publisher;
+use crate::network::speedtest::TestMethod;
+use crate::network::{SpeedTest, TestSettings};
+use anyhow::{bail, Result};
+use chrono::{DateTime, FixedOffset, Local};
+use cron::Schedule;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub struct DaemonOptions {
+    pub schedules: Vec<Schedule>,
+    /// Upper bound of the random delay added to each scheduled run.
+    pub random_delay: Duration,
+    pub method: TestMethod,
+    pub settings: TestSettings,
+    pub tag: Option<String>,
+    /// Receives every saved run.
+    pub mqtt: Option<Publisher>,
+    /// Checked after every saved run.
+    pub alerter: Alerter,
+}
+
+/// A source of wall-clock time and sleeps.
+pub trait Clock {
+    fn now(&self) -> DateTime<FixedOffset>;
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send;
+}
+
+/// Local time and Tokio sleeps.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        Local::now().fixed_offset()
+    }
+
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+        tokio::time::sleep(duration)
+    }
+}
+
+/// Something the daemon loop did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The next run will start at `at` (the scheduled `slot` plus its delay).
+    Scheduled {
+        slot: DateTime<FixedOffset>,
+        at: DateTime<FixedOffset>,
+    },
+    Started(DateTime<FixedOffset>),
+    /// The run due at this time was skipped because the previous one was
+    /// still in progress.
+    Skipped(DateTime<FixedOffset>),
+    /// Shutdown was requested; waiting for a run in progress to finish.
+    Stopping {
+        waiting: bool,
+    },
+}
+
+/// Picks run times from cron schedules plus a random delay.
+pub struct Scheduler {
+    schedules: Vec<Schedule>,
+    max_delay: Duration,
+    last_slot: Option<DateTime<FixedOffset>>,
+    rng: StdRng,
+}
+
+impl Scheduler {
+    pub fn new(schedules: Vec<Schedule>, max_delay: Duration) -> Self {
+        Self {
+            schedules,
+            max_delay,
+            last_slot: None,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// The next scheduled slot after `now` (and after the previous slot), and
+    /// the time to start it with a random delay of up to `max_delay` added.
+    pub fn next_run(
+        &mut self,
+        now: DateTime<FixedOffset>,
+    ) -> Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+        let after = self.last_slot.map_or(now, |last| last.max(now));
+        let slot = self
+            .schedules
+            .iter()
+            .filter_map(|schedule| schedule.next_after(&after))
+            .min()?;
+        self.last_slot = Some(slot);
+        let max_ms = self.max_delay.as_millis() as i64;
+        let delay = chrono::Duration::milliseconds(self.rng.gen_range(0..=max_ms));
+        Some((slot, slot + delay))
+    }
+}
+
+/// Runs `job` at every scheduled time until `shutdown` completes, then waits
+/// for a run in progress to finish.
+pub async fn run<C, J, F>(
+    clock: &C,
+    scheduler: &mut Scheduler,
+    shutdown: impl Future<Output = ()>,
+    mut job: J,
+    mut on_event: impl FnMut(Event),
+) -> Result<()>
+where
+    C: Clock,
+    J: FnMut() -> F,
+    F: Future<Output = ()> + Send + 'static,
+{
+    tokio::pin!(shutdown);
+    let mut running: Option<tokio::task::JoinHandle<()>> = None;
+    loop {
+        let Some((slot, at)) = scheduler.next_run(clock.now()) else {
+            bail!("The schedule has no upcoming run times");
+        };
+        on_event(Event::Scheduled { slot, at });
+        let wait = (at - clock.now()).to_std().unwrap_or_default();
+        tokio::select! {
+            biased;
+            () = &mut shutdown => break,
+            () = clock.sleep(wait) => {}
+        }
+
+        if running.as_ref().is_some_and(|run| !run.is_finished()) {
+            on_event(Event::Skipped(slot));
+            continue;
+        }
+        on_event(Event::Started(slot));
+        running = Some(tokio::spawn(job()));
+    }
+
+    let in_progress = running.filter(|run| !run.is_finished());
+    on_event(Event::Stopping {
+        waiting: in_progress.is_some(),
+    });
+    if let Some(run) = in_progress {
+        run.await?;
+    }
+    Ok(())
+}
+
+/// Runs and saves tests on the configured schedules until SIGTERM or Ctrl-C.
+pub async fn serve(options: DaemonOptions) -> Result<()> {
+    let history = Arc::new(HistoryManager::new().await?);
+    println!(
+        "🕒 PingTest daemon started (random delay up to {} s)",
+        options.random_delay.as_secs()
+    );
+    for schedule in &options.schedules {
+        println!("   Schedule: {}", schedule);
+    }
+    for rule in options.alerter.rules() {
+        println!("   Alert: {}", rule);
+    }
+    if let Some(mqtt) = &options.mqtt {
+        println!("   MQTT: {}", mqtt.url());
+    }
+    println!("   History: {}", HistoryManager::default_path().display());
+    let alerter = Arc::new(tokio::sync::Mutex::new(options.alerter));
+    let mqtt = options.mqtt.map(Arc::new);
+
+    let mut scheduler = Scheduler::new(options.schedules, options.random_delay);
+    run(
+        &SystemClock,
+        &mut scheduler,
+        shutdown_signal(),
+        || {
+            scheduled_test(
+                options.method.clone(),
+                options.settings.clone(),
+                options.tag.clone(),
+                history.clone(),
+                mqtt.clone(),
+                alerter.clone(),
+            )
+        },
+        |event| match event {
+            Event::Scheduled { at, .. } => {
+                println!("⏰ Next run at {}", at.format("%Y-%m-%d %H:%M:%S"))
+            }
+            Event::Started(slot) => println!(
+                "🚀 Starting run scheduled for {}",
+                slot.format("%Y-%m-%d %H:%M")
+            ),
+            Event::Skipped(slot) => println!(
+                "⏭️  Skipped run scheduled for {}: previous run still in progress",
+                slot.format("%Y-%m-%d %H:%M")
+            ),
+            Event::Stopping { waiting: true } => {
+                println!("🛑 Stopping after the run in progress finishes...")
+            }
+            Event::Stopping { waiting: false } => println!("🛑 Stopping"),
+        },
+    )
+    .await
+}
+
+async fn scheduled_test(
+    method: TestMethod,
+    settings: TestSettings,
+    tag: Option<String>,
+    history: Arc<HistoryManager>,
+    mqtt: Option<Arc<Publisher>>,
+    alerter: Arc<tokio::sync::Mutex<Alerter>>,
+) {
+    let outcome = async {
+        let result = SpeedTest::new()
+            .with_settings(settings)
+            .run_test_with_progress(
+                method.duration,
+                method.connections,
+                !method.download,
+                !method.upload,
+                |_| {},
+            )
+            .await?;
+        history
+            .add_entry(HistoryEntry::from_result(&result, tag, None))
+            .await?;
+        Ok::<_, anyhow::Error>(result)
+    };
+    match outcome.await {
+        Ok(result) => {
+            println!(
+                "✅ Saved: ↓ {:.1} Mbps ↑ {:.1} Mbps {:.1} ms",
+                result.download_speed, result.upload_speed, result.ping
+            );
+            if let Some(mqtt) = &mqtt {
+                if let Err(e) = mqtt.publish(&result).await {
+                    eprintln!("❌ {:#}", e);
+                }
+            }
+            // Alerts still go out when the history cannot be read back.
+            let entries = history.get_history().await.unwrap_or_default();
+            alerter.lock().await.check(&result, &entries).await;
+        }
+        Err(e) => eprintln!("❌ Scheduled test failed: {:#}", e),
+    }
+}
+
+/// Completes on SIGTERM or Ctrl-C.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::Notify;
+
+    /// A clock whose sleeps return at once after moving time forward.
+    struct FakeClock {
+        now: Mutex<DateTime<FixedOffset>>,
+    }
+
+    impl FakeClock {
+        fn at(time: &str) -> Self {
+            Self {
+                now: Mutex::new(DateTime::parse_from_rfc3339(time).unwrap()),
+            }
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<FixedOffset> {
+            *self.now.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+            *self.now.lock().unwrap() += chrono::Duration::from_std(duration).unwrap();
+            // Let spawned runs make progress, as real time passing would.
+            tokio::task::yield_now()
+        }
+    }
+
+    fn scheduler(expression: &str, max_delay: Duration) -> Scheduler {
+        Scheduler {
+            schedules: vec![expression.parse().unwrap()],
+            max_delay,
+            last_slot: None,
+            rng: StdRng::seed_from_u64(7),
+        }
+    }
+
+    fn time(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    #[tokio::test]
+    async fn runs_at_scheduled_times_with_a_bounded_delay() {
+        let clock = FakeClock::at("2026-01-09T23:40:00+00:00");
+        let mut scheduler = scheduler("*/15 * * * 1-5", Duration::from_secs(60));
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let stop = Notify::new();
+
+        let mut events = Vec::new();
+        run(
+            &clock,
+            &mut scheduler,
+            stop.notified(),
+            || {
+                let started = started.clone();
+                let now = clock.now();
+                async move { started.lock().unwrap().push(now) }
+            },
+            |event| {
+                if let Event::Started(_) = event {
+                    if events.len() >= 4 {
+                        stop.notify_one();
+                    }
+                }
+                events.push(event);
+            },
+        )
+        .await
+        .unwrap();
+
+        let slots: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Started(slot) => Some(*slot),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            slots,
+            [
+                time("2026-01-09T23:45:00+00:00"),
+                time("2026-01-12T00:00:00+00:00"),
+                time("2026-01-12T00:15:00+00:00"),
+            ]
+        );
+        for event in &events {
+            if let Event::Scheduled { slot, at } = event {
+                let delay = (*at - *slot).num_seconds();
+                assert!((0..=60).contains(&delay), "delay {}", delay);
+            }
+        }
+        assert_eq!(started.lock().unwrap().len(), 3);
+        assert!(matches!(events.last(), Some(Event::Stopping { .. })));
+    }
+
+    #[tokio::test]
+    async fn skips_a_run_while_the_previous_one_is_in_progress() {
+        let clock = FakeClock::at("2026-01-05T10:00:30+00:00");
+        let mut scheduler = scheduler("* * * * *", Duration::ZERO);
+        let release = Arc::new(Notify::new());
+        let stop = Notify::new();
+
+        let mut events = Vec::new();
+        let mut jobs = 0;
+        run(
+            &clock,
+            &mut scheduler,
+            stop.notified(),
+            || {
+                jobs += 1;
+                let release = release.clone();
+                let first = jobs == 1;
+                async move {
+                    if first {
+                        release.notified().await;
+                    }
+                }
+            },
+            |event| {
+                match event {
+                    Event::Skipped(_) => release.notify_one(),
+                    Event::Started(_) if events.iter().any(|e| matches!(e, Event::Skipped(_))) => {
+                        stop.notify_one()
+                    }
+                    _ => {}
+                }
+                events.push(event);
+            },
+        )
+        .await
+        .unwrap();
+
+        let outcomes: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e, Event::Started(_) | Event::Skipped(_)))
+            .copied()
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                Event::Started(time("2026-01-05T10:01:00+00:00")),
+                Event::Skipped(time("2026-01-05T10:02:00+00:00")),
+                Event::Started(time("2026-01-05T10:03:00+00:00")),
+            ]
+        );
+        assert_eq!(jobs, 2);
+    }
+}
@@ -0,0 +1,217 @@
+//! `pingtest dashboard`: a local web page for running tests and watching
+//! history trends.
+//!
+//! The page is a single HTML file with inline scripts and styles, so it works
+//! offline. It drives the same routes as `pingtest api`, with a token made up
+//! at startup and written into the page. The page itself is only served with
+//! that token in its URL, and on a loopback address only to requests naming
+//! a loopback host, so another site cannot read it through DNS rebinding.
+
+use crate::api::{self, Api, ApiOptions};
+use crate::history::HistoryManager;
+use crate::network::speedtest::TestMethod;
+use crate::network::TestSettings;
+use anyhow::{Context, Result};
+use axum::extract::{Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+const PAGE: &str = include_str!("dashboard.html");
+
+/// Where the page expects its API token.
+const TOKEN_PLACEHOLDER: &str = "__PINGTEST_TOKEN__";
+
+pub struct DashboardOptions {
+    pub listen: SocketAddr,
+    pub method: TestMethod,
+    pub settings: TestSettings,
+    pub tag: Option<String>,
+}
+
+struct Page {
+    token: String,
+    html: String,
+}
+
+#[derive(Deserialize)]
+struct PageQuery {
+    token: Option<String>,
+}
+
+/// The dashboard page and the API routes it uses, all needing `token`.
+pub fn router(options: DashboardOptions, token: &str, history: HistoryManager) -> Router {
+    let loopback = options.listen.ip().is_loopback();
+    let api = Api::new(
+        &ApiOptions {
+            listen: options.listen,
+            token: token.to_string(),
+            method: options.method,
+            settings: options.settings,
+            tag: options.tag,
+        },
+        history,
+    );
+    let page = Page {
+        token: token.to_string(),
+        html: PAGE.replace(TOKEN_PLACEHOLDER, token),
+    };
+    let app = Router::new()
+        .route("/", get(page_handler))
+        .with_state(Arc::new(page))
+        .merge(api::routes(Arc::new(api)));
+    if loopback {
+        app.layer(middleware::from_fn(loopback_host_only))
+    } else {
+        app
+    }
+}
+
+/// Serves the dashboard until the process is stopped.
+pub async fn serve(options: DashboardOptions) -> Result<()> {
+    let listen = options.listen;
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let app = router(options, &token, HistoryManager::new().await?);
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    println!("📊 Dashboard running at http://{}/?token={}", listen, token);
+    if !listen.ip().is_loopback() {
+        eprintln!(
+            "⚠️  The dashboard is reachable from other hosts on {}: anyone with its URL can run tests and read history",
+            listen
+        );
+    }
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn page_handler(State(page): State<Arc<Page>>, Query(query): Query<PageQuery>) -> Response {
+    match query.token {
+        Some(token) if api::same_token(&token, &page.token) => {
+            Html(page.html.clone()).into_response()
+        }
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            "Open the dashboard with the URL it printed at startup, including ?token=",
+        )
+            .into_response(),
+    }
+}
+
+/// Rejects requests whose `Host` is not a loopback name or address.
+async fn loopback_host_only(request: Request, next: Next) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok());
+    if !host.is_some_and(is_loopback_host) {
+        return (StatusCode::FORBIDDEN, "The Host header must name localhost").into_response();
+    }
+    next.run(request).await
+}
+
+/// `localhost`, `127.0.0.1:9518`, `[::1]` and the like.
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    name.eq_ignore_ascii_case("localhost")
+        || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn serves_a_self_contained_page_that_can_use_the_api() {
+        let path = std::env::temp_dir().join(format!("pingtest-{}.json", uuid::Uuid::new_v4()));
+        let options = DashboardOptions {
+            listen: "127.0.0.1:0".parse().unwrap(),
+            method: TestMethod {
+                duration: 1,
+                connections: 1,
+                download: true,
+                upload: false,
+            },
+            settings: TestSettings::default(),
+            tag: None,
+        };
+        let listener = tokio::net::TcpListener::bind(options.listen).await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = router(
+            options,
+            "secret",
+            HistoryManager::with_path(path).await.unwrap(),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+
+        let page = client
+            .get(format!("{}/?token=secret", url))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(!page.contains(TOKEN_PLACEHOLDER));
+        for external in ["src=\"http", "href=\"http", "url(http", "@import"] {
+            assert!(!page.contains(external), "page loads {}", external);
+        }
+
+        let token = page
+            .split("const TOKEN = \"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap();
+        let history = client
+            .get(format!("{}/api/history", url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(history.status(), reqwest::StatusCode::OK);
+
+        for page_url in [url.clone(), format!("{}/?token=guess", url)] {
+            let denied = client.get(page_url).send().await.unwrap();
+            assert_eq!(denied.status(), reqwest::StatusCode::UNAUTHORIZED);
+        }
+        let rebound = client
+            .get(format!("{}/?token=secret", url))
+            .header(header::HOST, "attacker.example:9518")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(rebound.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn recognises_loopback_hosts() {
+        for host in [
+            "localhost",
+            "LOCALHOST:9518",
+            "127.0.0.1:9518",
+            "[::1]:9518",
+            "[::1]",
+        ] {
+            assert!(is_loopback_host(host), "{}", host);
+        }
+        for host in [
+            "attacker.example",
+            "192.168.1.10:9518",
+            "localhost.example.com",
+            "",
+        ] {
+            assert!(!is_loopback_host(host), "{}", host);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! Writing test results to files.
+//!
+//! JSON and Markdown files are overwritten with the latest result. CSV and
+//! NDJSON are line-oriented, so each run is appended and a file can
+//! accumulate results over time.
+
+pub mod schema;
+pub mod stream;
+
+pub use schema::ResultDocument;
+
+use anyhow::{bail, Context, Result};
+use chrono::SecondsFormat;
+use schema::ThroughputSection;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// CSV columns, in order. Append-only: existing files are checked against it.
+pub const CSV_HEADER: [&str; 13] = [
+    "timestamp",
+    "server_id",
+    "server_name",
+    "server_location",
+    "download_speed",
+    "upload_speed",
+    "ping",
+    "test_duration",
+    "connections",
+    "quality_score",
+    "quality_description",
+    "tag",
+    "partial",
+];
+
+/// Columns of files written before `partial` was added. They still take
+/// complete results.
+const LEGACY_CSV_COLUMNS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Ndjson,
+    Markdown,
+}
+
+impl ExportFormat {
+    /// Infers the format from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+fn csv_fields(document: &ResultDocument) -> [String; CSV_HEADER.len()] {
+    let mbps = |section: &Option<ThroughputSection>| {
+        section
+            .as_ref()
+            .map(|t| format!("{:.2}", t.bits_per_second / 1_000_000.0))
+            .unwrap_or_default()
+    };
+    [
+        document
+            .timestamp
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+        document.server.id.to_string(),
+        document.server.name.clone(),
+        document.server.location.clone(),
+        mbps(&document.download),
+        mbps(&document.upload),
+        format!("{:.2}", document.latency.ping_ms),
+        format!("{:.2}", document.elapsed_ms / 1000.0),
+        document.method.connections.to_string(),
+        document.quality.score.to_string(),
+        document.quality.description.clone(),
+        document.tag.clone().unwrap_or_default(),
+        document.partial.to_string(),
+    ]
+}
+
+/// Picks the explicit format if given, or else infers it from the extension,
+/// or else uses `fallback` (the configured default format).
+pub fn resolve_format(
+    path: &Path,
+    format: Option<ExportFormat>,
+    fallback: Option<ExportFormat>,
+) -> Result<ExportFormat> {
+    match format
+        .or_else(|| ExportFormat::from_path(path))
+        .or(fallback)
+    {
+        Some(format) => Ok(format),
+        None => bail!(
+            "Cannot infer export format from {}; use a .json, .csv, .ndjson or .md extension or pass --format",
+            path.display()
+        ),
+    }
+}
+
+/// Writes `document` to `path`. JSON and NDJSON carry the whole document;
+/// CSV flattens it to [`CSV_HEADER`] and Markdown renders it as a table.
+pub fn export(document: &ResultDocument, path: &Path, format: ExportFormat) -> Result<()> {
+    match format {
+        ExportFormat::Json => std::fs::write(path, serde_json::to_string_pretty(document)?)
+            .with_context(|| format!("Failed to write {}", path.display())),
+        ExportFormat::Csv => append_csv(document, path),
+        ExportFormat::Ndjson => append_line(path, &serde_json::to_string(document)?),
+        ExportFormat::Markdown => std::fs::write(path, crate::report::markdown::result(document))
+            .with_context(|| format!("Failed to write {}", path.display())),
+    }
+}
+
+fn append_csv(document: &ResultDocument, path: &Path) -> Result<()> {
+    let header = csv_line(&CSV_HEADER);
+    let existing = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    let legacy = csv_line(&CSV_HEADER[..LEGACY_CSV_COLUMNS]);
+    let mut out = String::new();
+    let columns = match existing.lines().next() {
+        None => {
+            out.push_str(&header);
+            CSV_HEADER.len()
+        }
+        Some(first) if first.trim_end() == header.trim_end() => CSV_HEADER.len(),
+        Some(first) if first.trim_end() == legacy.trim_end() => {
+            if document.partial {
+                bail!(
+                    "{} has no partial column to mark this partial result; export to a new file instead",
+                    path.display()
+                );
+            }
+            LEGACY_CSV_COLUMNS
+        }
+        Some(_) => bail!(
+            "{} has a different CSV header; export to a new file instead",
+            path.display()
+        ),
+    };
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        out.insert(0, '\n');
+    }
+    out.push_str(&csv_line(&csv_fields(document)[..columns]));
+    append_line(path, out.trim_end_matches('\n'))
+}
+
+fn append_line(path: &Path, line: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn csv_line<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|f| csv_field(f.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::TestResult;
+
+    fn record(tag: Option<&str>) -> ResultDocument {
+        let result = TestResult::sample();
+        ResultDocument::new(&result, 86, "Very Good", tag.map(str::to_string))
+    }
+
+    #[test]
+    fn infers_format_from_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out.CSV")),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out.jsonl")),
+            Some(ExportFormat::Ndjson)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("out.txt")), None);
+        assert!(resolve_format(Path::new("out.txt"), None, None).is_err());
+        assert_eq!(
+            resolve_format(Path::new("out.txt"), Some(ExportFormat::Json), None).unwrap(),
+            ExportFormat::Json
+        );
+        // The configured default only applies when the extension is unknown.
+        assert_eq!(
+            resolve_format(Path::new("out.csv"), None, Some(ExportFormat::Json)).unwrap(),
+            ExportFormat::Csv
+        );
+        assert_eq!(
+            resolve_format(Path::new("out.txt"), None, Some(ExportFormat::Ndjson)).unwrap(),
+            ExportFormat::Ndjson
+        );
+    }
+
+    #[test]
+    fn csv_appends_rows_under_one_header() {
+        let path = std::env::temp_dir().join(format!("pingtest-{}.csv", uuid::Uuid::new_v4()));
+        export(&record(None), &path, ExportFormat::Csv).unwrap();
+        export(&record(Some("office, 2nd floor")), &path, ExportFormat::Csv).unwrap();
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER.join(","));
+        assert!(lines[1].ends_with(",86,Very Good,,false"));
+        assert!(lines[2].ends_with(",\"office, 2nd floor\",false"));
+    }
+
+    #[test]
+    fn csv_files_without_a_partial_column_take_only_complete_results() {
+        let path = std::env::temp_dir().join(format!("pingtest-{}.csv", uuid::Uuid::new_v4()));
+        let legacy = CSV_HEADER[..LEGACY_CSV_COLUMNS].join(",");
+        std::fs::write(&path, format!("{}\n", legacy)).unwrap();
+        export(&record(None), &path, ExportFormat::Csv).unwrap();
+        let partial = ResultDocument {
+            partial: true,
+            ..record(None)
+        };
+        let refused = export(&partial, &path, ExportFormat::Csv);
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], legacy);
+        assert!(lines[1].ends_with(",86,Very Good,"));
+        assert!(refused.is_err());
+    }
+
+    #[test]
+    fn csv_rejects_foreign_header() {
+        let path = std::env::temp_dir().join(format!("pingtest-{}.csv", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "a,b,c\n1,2,3\n").unwrap();
+        let result = export(&record(None), &path, ExportFormat::Csv);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! The versioned JSON result document.
+//!
+//! JSON and NDJSON exports use [`ResultDocument`]. Fields carry their unit in
+//! the name. Adding optional fields keeps [`SCHEMA_VERSION`]; renaming,
+//! removing or changing the meaning of a field bumps it. The JSON Schema in
+//! `schema/result.schema.json` is generated with `pingtest schema`.
+
+use crate::network::{SteadyWindow, TestResult};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A single speed test result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[schemars(title = "PingTest result")]
+pub struct ResultDocument {
+    /// Version of this document layout.
+    pub schema_version: u32,
+    /// When the test finished (RFC 3339, UTC).
+    pub timestamp: DateTime<Utc>,
+    /// Tag given with `--tag`, if any.
+    pub tag: Option<String>,
+    pub server: ServerSection,
+    pub client: ClientSection,
+    pub method: MethodSection,
+    /// Download phase, or null when it was skipped.
+    pub download: Option<ThroughputSection>,
+    /// Upload phase, or null when it was skipped.
+    pub upload: Option<ThroughputSection>,
+    pub latency: LatencySection,
+    pub quality: QualitySection,
+    /// Wall-clock time of the whole test in milliseconds.
+    pub elapsed_ms: f64,
+    /// The test was stopped early; phases it never reached are null.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+}
+
+/// The server the test ran against.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ServerSection {
+    pub id: u32,
+    pub name: String,
+    pub location: String,
+    pub host: String,
+}
+
+/// The machine and program that ran the test.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ClientSection {
+    /// pingtest version.
+    pub version: String,
+    /// Operating system, e.g. `linux` or `macos`.
+    pub os: String,
+    /// CPU architecture, e.g. `x86_64` or `aarch64`.
+    pub arch: String,
+}
+
+impl ClientSection {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
+}
+
+/// How the test was configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MethodSection {
+    /// Parallel connections per throughput phase.
+    pub connections: u32,
+    /// Configured length of each throughput phase in seconds.
+    pub phase_duration_s: u64,
+    pub download_enabled: bool,
+    pub upload_enabled: bool,
+}
+
+/// Throughput measured in one direction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ThroughputSection {
+    pub bits_per_second: f64,
+    /// Data moved during the whole phase, slow start included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    /// The part of the phase `bits_per_second` is averaged over, after TCP
+    /// slow start.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steady_state: Option<SteadyStateSection>,
+}
+
+/// A window of a throughput phase, in seconds from the phase's start.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SteadyStateSection {
+    pub start_s: u64,
+    /// Also the length of the phase.
+    pub end_s: u64,
+    /// Whether the phase ended early because the throughput converged.
+    pub converged: bool,
+}
+
+/// Round-trip latency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LatencySection {
+    /// Mean round-trip time of answered probes.
+    pub ping_ms: f64,
+    /// Mean difference between consecutive round-trip times.
+    pub jitter_ms: f64,
+    /// Share of probes that were lost, from 0 to 100.
+    pub packet_loss_percent: f64,
+}
+
+/// Overall connection quality.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QualitySection {
+    /// Score from 0 to 100.
+    #[schemars(range(max = 100))]
+    pub score: u8,
+    pub description: String,
+}
+
+impl ResultDocument {
+    pub fn new(
+        result: &TestResult,
+        quality_score: u8,
+        quality_description: &str,
+        tag: Option<String>,
+    ) -> Self {
+        let throughput = |enabled: bool, mbps: f64, window: Option<SteadyWindow>, bytes| {
+            enabled.then_some(ThroughputSection {
+                bits_per_second: mbps * 1_000_000.0,
+                bytes: Some(bytes),
+                steady_state: window.map(|w| SteadyStateSection {
+                    start_s: w.start_s,
+                    end_s: w.end_s,
+                    converged: w.converged,
+                }),
+            })
+        };
+        Self {
+            schema_version: SCHEMA_VERSION,
+            timestamp: result.timestamp,
+            tag,
+            server: ServerSection {
+                id: result.server_id,
+                name: result.server_name.clone(),
+                location: result.server_location.clone(),
+                host: result.server_host.clone(),
+            },
+            client: ClientSection::current(),
+            method: MethodSection {
+                connections: result.method.connections,
+                phase_duration_s: result.method.duration,
+                download_enabled: result.method.download,
+                upload_enabled: result.method.upload,
+            },
+            download: throughput(
+                result.method.download,
+                result.download_speed,
+                result.download_window,
+                result.download_bytes,
+            ),
+            upload: throughput(
+                result.method.upload,
+                result.upload_speed,
+                result.upload_window,
+                result.upload_bytes,
+            ),
+            latency: LatencySection {
+                ping_ms: result.ping,
+                jitter_ms: result.jitter,
+                packet_loss_percent: result.packet_loss,
+            },
+            quality: QualitySection {
+                score: quality_score,
+                description: quality_description.to_string(),
+            },
+            elapsed_ms: result.test_duration * 1000.0,
+            partial: result.partial,
+        }
+    }
+}
+
+/// The JSON Schema for [`ResultDocument`], pretty-printed.
+pub fn json_schema() -> String {
+    let schema = schemars::schema_for!(ResultDocument);
+    let mut out = serde_json::to_string_pretty(&schema).expect("schema serializes");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{export, ExportFormat};
+    use crate::network::SpeedTest;
+
+    const CHECKED_IN_SCHEMA: &str = include_str!("../../schema/result.schema.json");
+
+    #[test]
+    fn checked_in_schema_is_up_to_date() {
+        assert_eq!(
+            json_schema(),
+            CHECKED_IN_SCHEMA,
+            "schema/result.schema.json is stale; regenerate it with `pingtest schema`"
+        );
+    }
+
+    #[tokio::test]
+    async fn exported_result_matches_schema() {
+        let result = SpeedTest::new()
+            .run_test_with_progress(1, 1, false, true, |_| {})
+            .await
+            .unwrap();
+        let document = ResultDocument::new(&result, 80, "Very Good", Some("ci".to_string()));
+        let path = std::env::temp_dir().join(format!("pingtest-{}.json", uuid::Uuid::new_v4()));
+        export(&document, &path, ExportFormat::Json).unwrap();
+        let output = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let schema: serde_json::Value = serde_json::from_str(CHECKED_IN_SCHEMA).unwrap();
+        let instance: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let errors: Vec<String> = validator
+            .iter_errors(&instance)
+            .map(|e| e.to_string())
+            .collect();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        assert_eq!(instance["schema_version"], SCHEMA_VERSION);
+        assert!(instance["upload"].is_null());
+    }
+}
@@ -0,0 +1,104 @@
+//! Newline-delimited JSON events for `--stream-json`.
+//!
+//! Each line on stdout is one object with an `event` field: `phase_start`,
+//! `sample`, `latency_probe`, `phase_end`, and finally `result` carrying the
+//! full [`ResultDocument`].
+
+use super::ResultDocument;
+use crate::network::{Phase, Progress};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent<'a> {
+    PhaseStart {
+        timestamp: DateTime<Utc>,
+        phase: Phase,
+    },
+    Sample {
+        timestamp: DateTime<Utc>,
+        phase: Phase,
+        interval: u64,
+        bytes: u64,
+        mbps: f64,
+    },
+    LatencyProbe {
+        timestamp: DateTime<Utc>,
+        seq: u32,
+        /// `null` when the probe was lost.
+        rtt_ms: Option<f64>,
+    },
+    PhaseEnd {
+        timestamp: DateTime<Utc>,
+        phase: Phase,
+    },
+    Result {
+        timestamp: DateTime<Utc>,
+        result: &'a ResultDocument,
+    },
+}
+
+impl StreamEvent<'_> {
+    pub fn from_progress(progress: Progress) -> Self {
+        let timestamp = Utc::now();
+        match progress {
+            Progress::PhaseStarted(phase) => StreamEvent::PhaseStart { timestamp, phase },
+            Progress::Sample {
+                phase,
+                interval,
+                bytes,
+                mbps,
+            } => StreamEvent::Sample {
+                timestamp,
+                phase,
+                interval,
+                bytes,
+                mbps,
+            },
+            Progress::LatencyProbe { seq, rtt_ms } => StreamEvent::LatencyProbe {
+                timestamp,
+                seq,
+                rtt_ms,
+            },
+            Progress::PhaseFinished(phase) => StreamEvent::PhaseEnd { timestamp, phase },
+        }
+    }
+}
+
+/// Writes one event as a line on stdout.
+pub fn emit(event: &StreamEvent) {
+    println!(
+        "{}",
+        serde_json::to_string(event).expect("stream events serialize")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_tagged_single_lines() {
+        let sample = StreamEvent::from_progress(Progress::Sample {
+            phase: Phase::Download,
+            interval: 3,
+            bytes: 11_775_000,
+            mbps: 94.2,
+        });
+        let line = serde_json::to_string(&sample).unwrap();
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "sample");
+        assert_eq!(value["phase"], "download");
+        assert_eq!(value["bytes"], 11_775_000);
+
+        let lost = StreamEvent::from_progress(Progress::LatencyProbe {
+            seq: 4,
+            rtt_ms: None,
+        });
+        let value = serde_json::to_value(&lost).unwrap();
+        assert_eq!(value["event"], "latency_probe");
+        assert!(value["rtt_ms"].is_null());
+    }
+}
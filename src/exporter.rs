@@ -0,0 +1,161 @@
+//! `pingtest exporter`: runs tests and serves the results to Prometheus.
+//!
+//! Tests run either on a fixed schedule or when `/metrics` is scraped and the
+//! cached result is older than the cache TTL. Results can also be written to
+//! a node_exporter textfile-collector `.prom` file.
+
+use crate::metrics::prometheus::PrometheusMetrics;
+use crate::network::speedtest::TestMethod;
+use crate::network::{SpeedTest, TestSettings};
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub struct ExporterOptions {
+    pub listen: SocketAddr,
+    /// Time between scheduled tests.
+    pub interval: Duration,
+    /// Test when scraped instead of on a schedule.
+    pub on_scrape: bool,
+    /// How long a result is served before a scrape triggers a new test.
+    pub cache_ttl: Duration,
+    pub textfile: Option<PathBuf>,
+    pub method: TestMethod,
+    pub settings: TestSettings,
+    pub tag: Option<String>,
+}
+
+struct Exporter {
+    options: ExporterOptions,
+    metrics: Mutex<PrometheusMetrics>,
+    last_run: Mutex<Option<Instant>>,
+    /// Held while a test runs so concurrent scrapes share one test.
+    running: tokio::sync::Mutex<()>,
+}
+
+impl Exporter {
+    fn new(options: ExporterOptions) -> Self {
+        Self {
+            options,
+            metrics: Mutex::new(PrometheusMetrics::new()),
+            last_run: Mutex::new(None),
+            running: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    async fn run_test(&self) {
+        let method = &self.options.method;
+        let result = SpeedTest::new()
+            .with_settings(self.options.settings.clone())
+            .run_test_with_progress(
+                method.duration,
+                method.connections,
+                !method.download,
+                !method.upload,
+                |_| {},
+            )
+            .await;
+
+        let rendered = {
+            let mut metrics = self.metrics.lock().unwrap();
+            match &result {
+                Ok(result) => metrics.observe(result, self.options.tag.as_deref()),
+                Err(e) => {
+                    eprintln!("❌ Speed test failed: {:#}", e);
+                    metrics.observe_failure();
+                }
+            }
+            metrics.render()
+        };
+        *self.last_run.lock().unwrap() = Some(Instant::now());
+
+        if let Some(path) = &self.options.textfile {
+            if let Err(e) = write_textfile(path, &rendered) {
+                eprintln!("❌ {:#}", e);
+            }
+        }
+    }
+
+    async fn refresh_if_stale(&self) {
+        let _running = self.running.lock().await;
+        let stale = self
+            .last_run
+            .lock()
+            .unwrap()
+            .is_none_or(|at| at.elapsed() >= self.options.cache_ttl);
+        if stale {
+            self.run_test().await;
+        }
+    }
+}
+
+/// Serves `/metrics` until the process is stopped.
+pub async fn serve(options: ExporterOptions) -> Result<()> {
+    let listen = options.listen;
+    let exporter = Arc::new(Exporter::new(options));
+
+    if !exporter.options.on_scrape {
+        let scheduled = Arc::clone(&exporter);
+        tokio::spawn(async move {
+            loop {
+                {
+                    let _running = scheduled.running.lock().await;
+                    scheduled.run_test().await;
+                }
+                tokio::time::sleep(scheduled.options.interval).await;
+            }
+        });
+    }
+
+    let app = Router::new()
+        .route("/", get(index))
+        .route("/metrics", get(metrics))
+        .with_state(exporter);
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    println!("📡 Serving Prometheus metrics on http://{}/metrics", listen);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Runs one test and writes the textfile-collector file.
+pub async fn write_once(options: ExporterOptions) -> Result<()> {
+    let exporter = Exporter::new(options);
+    exporter.run_test().await;
+    if let Some(path) = &exporter.options.textfile {
+        println!("📁 Metrics written to: {}", path.display());
+    }
+    Ok(())
+}
+
+async fn index() -> impl IntoResponse {
+    "pingtest exporter - metrics at /metrics\n"
+}
+
+async fn metrics(State(exporter): State<Arc<Exporter>>) -> impl IntoResponse {
+    if exporter.options.on_scrape {
+        exporter.refresh_if_stale().await;
+    }
+    let body = exporter.metrics.lock().unwrap().render();
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], body)
+}
+
+/// Writes via a temporary file and rename so node_exporter never reads a
+/// partial file.
+fn write_textfile(path: &Path, contents: &str) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, contents)
+        .with_context(|| format!("Failed to write {}", Path::new(&tmp).display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Failed to write {}", path.display()))
+}
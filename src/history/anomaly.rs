@@ -0,0 +1,511 @@
+//! Outlier and level-shift detection over saved history.
+//!
+//! Outliers are individual runs whose robust z-score (median/MAD based) is
+//! extreme compared with runs from the same hour of the day and day of the
+//! week. Level shifts are sustained changes in a metric, found by binary
+//! segmentation of the series with a BIC-style penalty.
+
+use super::HistoryEntry;
+use chrono::{DateTime, Datelike, Local, Timelike, Utc, Weekday};
+
+/// Scales MAD so it estimates the standard deviation of normal data.
+const MAD_SCALE: f64 = 0.6745;
+/// Scales the mean absolute deviation when MAD is zero.
+const MEAN_AD_SCALE: f64 = 1.253314;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Metric {
+    Download,
+    Upload,
+    Latency,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 3] = [Metric::Download, Metric::Upload, Metric::Latency];
+
+    /// The metric's value in a run, or `None` if the run skipped it or was
+    /// stopped early, when even the phases it reached may be cut short.
+    pub fn value(&self, entry: &HistoryEntry) -> Option<f64> {
+        if entry.partial {
+            return None;
+        }
+        let method = entry.method.as_ref();
+        match self {
+            Metric::Download => method
+                .is_none_or(|m| m.download)
+                .then_some(entry.download_speed),
+            Metric::Upload => method
+                .is_none_or(|m| m.upload)
+                .then_some(entry.upload_speed),
+            Metric::Latency => Some(entry.ping),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Download => "Download",
+            Metric::Upload => "Upload",
+            Metric::Latency => "Latency",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Metric::Download | Metric::Upload => "Mbps",
+            Metric::Latency => "ms",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnomalySettings {
+    /// Absolute robust z-score above which a run is an outlier.
+    pub outlier_threshold: f64,
+    /// Fewest runs a time-of-day bucket needs before it is used as a baseline.
+    pub min_baseline_runs: usize,
+    /// Fewest runs on each side of a level shift.
+    pub min_segment: usize,
+    /// Smallest change between levels, in percent, reported as a shift.
+    pub min_shift_percent: f64,
+}
+
+impl Default for AnomalySettings {
+    fn default() -> Self {
+        Self {
+            outlier_threshold: 3.5,
+            min_baseline_runs: 5,
+            min_segment: 5,
+            min_shift_percent: 15.0,
+        }
+    }
+}
+
+/// The runs an outlier was compared against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Baseline {
+    /// Runs on the same weekday within the same hour.
+    WeekdayHour(Weekday, u32),
+    /// Runs within the same hour on any day.
+    Hour(u32),
+    /// Every run in the range.
+    All,
+}
+
+impl std::fmt::Display for Baseline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Baseline::WeekdayHour(day, hour) => write!(f, "{} {:02}:00", day, hour),
+            Baseline::Hour(hour) => write!(f, "{:02}:00", hour),
+            Baseline::All => write!(f, "all runs"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnomalyKind {
+    Outlier {
+        value: f64,
+        typical: f64,
+        z_score: f64,
+        baseline: Baseline,
+    },
+    LevelShift {
+        before: f64,
+        after: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    /// When the outlier run happened, or the first run at the new level.
+    pub timestamp: DateTime<Utc>,
+    pub metric: Metric,
+    pub kind: AnomalyKind,
+}
+
+/// Runs both detectors for each metric and returns the events in date order.
+/// Runs that skipped a metric or were stopped early are left out of its
+/// series.
+///
+/// Outliers are scored within the regime between level shifts, so runs after
+/// a plan change are not all reported against the old level.
+pub fn detect_anomalies(
+    entries: &[HistoryEntry],
+    metrics: &[Metric],
+    settings: &AnomalySettings,
+) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    for &metric in metrics {
+        let entries: Vec<HistoryEntry> = entries
+            .iter()
+            .filter(|e| metric.value(e).is_some())
+            .cloned()
+            .collect();
+        let entries = entries.as_slice();
+        let bounds = regime_bounds(entries, metric, settings);
+        for regime in bounds.windows(2) {
+            anomalies.extend(detect_outliers(
+                &entries[regime[0]..regime[1]],
+                metric,
+                settings,
+            ));
+        }
+        anomalies.extend(level_shifts_at(entries, metric, &bounds));
+    }
+    anomalies.sort_by_key(|a| a.timestamp);
+    anomalies
+}
+
+/// Flags runs whose robust z-score exceeds the threshold relative to runs at
+/// the same local hour and weekday, falling back to the same hour and then to
+/// all runs when a bucket is too small. Runs that skipped the metric are
+/// neither scored nor part of a baseline.
+pub fn detect_outliers(
+    entries: &[HistoryEntry],
+    metric: Metric,
+    settings: &AnomalySettings,
+) -> Vec<Anomaly> {
+    let runs: Vec<(&HistoryEntry, f64, Weekday, u32)> = entries
+        .iter()
+        .filter_map(|e| {
+            let local = e.timestamp.with_timezone(&Local);
+            Some((e, metric.value(e)?, local.weekday(), local.hour()))
+        })
+        .collect();
+
+    let mut anomalies = Vec::new();
+    for &(entry, value, day, hour) in &runs {
+        let candidates = [
+            Baseline::WeekdayHour(day, hour),
+            Baseline::Hour(hour),
+            Baseline::All,
+        ];
+        let Some((baseline, values)) = candidates.into_iter().find_map(|baseline| {
+            let values: Vec<f64> = runs
+                .iter()
+                .filter(|&&(_, _, d, h)| match baseline {
+                    Baseline::WeekdayHour(..) => d == day && h == hour,
+                    Baseline::Hour(_) => h == hour,
+                    Baseline::All => true,
+                })
+                .map(|&(_, v, _, _)| v)
+                .collect();
+            (values.len() >= settings.min_baseline_runs).then_some((baseline, values))
+        }) else {
+            continue;
+        };
+
+        if let Some((typical, z_score)) = robust_z_score(value, &values) {
+            if z_score.abs() > settings.outlier_threshold {
+                anomalies.push(Anomaly {
+                    timestamp: entry.timestamp,
+                    metric,
+                    kind: AnomalyKind::Outlier {
+                        value,
+                        typical,
+                        z_score,
+                        baseline,
+                    },
+                });
+            }
+        }
+    }
+    anomalies
+}
+
+/// Reports each boundary between regimes as a level shift.
+fn level_shifts_at(entries: &[HistoryEntry], metric: Metric, bounds: &[usize]) -> Vec<Anomaly> {
+    let values = series(entries, metric);
+    bounds
+        .windows(3)
+        .map(|w| Anomaly {
+            timestamp: entries[w[1]].timestamp,
+            metric,
+            kind: AnomalyKind::LevelShift {
+                before: median(&values[w[0]..w[1]]),
+                after: median(&values[w[1]..w[2]]),
+            },
+        })
+        .collect()
+}
+
+/// Start and end indices of each regime, i.e. `[0, shift.., len]`.
+fn regime_bounds(
+    entries: &[HistoryEntry],
+    metric: Metric,
+    settings: &AnomalySettings,
+) -> Vec<usize> {
+    let values = series(entries, metric);
+    let min_segment = settings.min_segment.max(1);
+    if values.len() < 2 * min_segment {
+        return vec![0, values.len()];
+    }
+
+    // Noise is estimated from successive differences so the shifts being
+    // looked for do not inflate it.
+    let diffs: Vec<f64> = values.windows(2).map(|w| w[1] - w[0]).collect();
+    let sigma = median_absolute_deviation(&diffs) / (MAD_SCALE * std::f64::consts::SQRT_2);
+    let penalty = 2.0 * sigma * sigma * (values.len() as f64).ln();
+
+    let segmenter = Segmenter::new(&values, min_segment, penalty);
+    let mut change_points = Vec::new();
+    segmenter.split(0, values.len(), &mut change_points);
+    change_points.sort_unstable();
+    change_points.push(values.len());
+
+    // Change points too small to matter are merged into their neighbours.
+    let mut bounds = vec![0];
+    for w in change_points.windows(2) {
+        let before = median(&values[*bounds.last().unwrap()..w[0]]);
+        let after = median(&values[w[0]..w[1]]);
+        if relative_change_percent(before, after)
+            .is_none_or(|change| change.abs() >= settings.min_shift_percent)
+        {
+            bounds.push(w[0]);
+        }
+    }
+    bounds.push(values.len());
+    bounds
+}
+
+/// The metric's values in `entries`, which [`detect_anomalies`] has already
+/// narrowed to the runs that measured it.
+fn series(entries: &[HistoryEntry], metric: Metric) -> Vec<f64> {
+    entries.iter().filter_map(|e| metric.value(e)).collect()
+}
+
+/// The change from `before` to `after` in percent of `before`, or `None`
+/// when `before` is zero and `after` is not.
+pub fn relative_change_percent(before: f64, after: f64) -> Option<f64> {
+    if before == 0.0 {
+        return (after == 0.0).then_some(0.0);
+    }
+    Some((after - before) / before.abs() * 100.0)
+}
+
+/// The change from `before` to `after`, e.g. `+12.5%`, or `new` when there
+/// was nothing before.
+pub fn format_change(before: f64, after: f64) -> String {
+    relative_change_percent(before, after)
+        .map_or_else(|| "new".to_string(), |change| format!("{:+.1}%", change))
+}
+
+/// Returns the baseline median and the robust z-score of `value`, or `None`
+/// when the baseline has no spread.
+fn robust_z_score(value: f64, baseline: &[f64]) -> Option<(f64, f64)> {
+    let med = median(baseline);
+    let mad = median_absolute_deviation(baseline);
+    if mad > 0.0 {
+        return Some((med, MAD_SCALE * (value - med) / mad));
+    }
+    let mean_ad = baseline.iter().map(|v| (v - med).abs()).sum::<f64>() / baseline.len() as f64;
+    (mean_ad > 0.0).then(|| (med, (value - med) / (MEAN_AD_SCALE * mean_ad)))
+}
+
+pub fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.is_empty() {
+        0.0
+    } else if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+fn median_absolute_deviation(values: &[f64]) -> f64 {
+    let med = median(values);
+    let deviations: Vec<f64> = values.iter().map(|v| (v - med).abs()).collect();
+    median(&deviations)
+}
+
+/// Least-squares segmentation with O(1) segment costs from prefix sums.
+struct Segmenter {
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+    min_segment: usize,
+    penalty: f64,
+}
+
+impl Segmenter {
+    fn new(values: &[f64], min_segment: usize, penalty: f64) -> Self {
+        let mut sum = vec![0.0];
+        let mut sum_sq = vec![0.0];
+        for v in values {
+            sum.push(sum.last().unwrap() + v);
+            sum_sq.push(sum_sq.last().unwrap() + v * v);
+        }
+        Self {
+            sum,
+            sum_sq,
+            min_segment,
+            penalty,
+        }
+    }
+
+    /// Sum of squared deviations from the mean of `start..end`.
+    fn cost(&self, start: usize, end: usize) -> f64 {
+        let n = (end - start) as f64;
+        let s = self.sum[end] - self.sum[start];
+        let sq = self.sum_sq[end] - self.sum_sq[start];
+        (sq - s * s / n).max(0.0)
+    }
+
+    fn split(&self, start: usize, end: usize, out: &mut Vec<usize>) {
+        if end - start < 2 * self.min_segment {
+            return;
+        }
+        let total = self.cost(start, end);
+        let best = (start + self.min_segment..=end - self.min_segment)
+            .map(|k| (k, total - self.cost(start, k) - self.cost(k, end)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((k, gain)) = best {
+            if gain > self.penalty && gain > f64::EPSILON * total.max(1.0) {
+                out.push(k);
+                self.split(start, k, out);
+                self.split(k, end, out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::speedtest::TestMethod;
+    use chrono::TimeZone;
+
+    fn entry(timestamp: DateTime<Utc>, download: f64) -> HistoryEntry {
+        HistoryEntry {
+            id: timestamp.to_rfc3339(),
+            timestamp,
+            download_speed: download,
+            upload_speed: 20.0,
+            ping: 25.0,
+            server_id: 12345,
+            server_name: "Test Server".to_string(),
+            server_location: "Test Location".to_string(),
+            tag: None,
+            method: None,
+            batch_id: None,
+            partial: false,
+        }
+    }
+
+    #[test]
+    fn flags_outlier_against_same_slot() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 5, 14, 0, 0).unwrap();
+        let mut entries: Vec<HistoryEntry> = (0..8)
+            .map(|week| {
+                entry(
+                    start + chrono::Duration::weeks(week),
+                    95.0 + (week % 3) as f64,
+                )
+            })
+            .collect();
+        entries.push(entry(start + chrono::Duration::weeks(8), 12.0));
+
+        let outliers = detect_outliers(&entries, Metric::Download, &AnomalySettings::default());
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].timestamp, entries[8].timestamp);
+        match outliers[0].kind {
+            AnomalyKind::Outlier {
+                z_score, baseline, ..
+            } => {
+                assert!(z_score < -3.5);
+                assert!(matches!(baseline, Baseline::WeekdayHour(..)));
+            }
+            _ => panic!("expected an outlier"),
+        }
+    }
+
+    #[test]
+    fn detects_sustained_level_shift() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap();
+        let entries: Vec<HistoryEntry> = (0..30)
+            .map(|day| {
+                let level = if day < 15 { 100.0 } else { 300.0 };
+                let noise = [-2.0, 1.0, 0.5, -1.0, 2.0][day % 5];
+                entry(start + chrono::Duration::days(day as i64), level + noise)
+            })
+            .collect();
+
+        let shifts = detect_anomalies(&entries, &[Metric::Download], &AnomalySettings::default());
+        assert_eq!(shifts.len(), 1);
+        assert_eq!(shifts[0].timestamp, entries[15].timestamp);
+        match shifts[0].kind {
+            AnomalyKind::LevelShift { before, after } => {
+                assert!((before - 100.0).abs() < 3.0);
+                assert!((after - 300.0).abs() < 3.0);
+            }
+            _ => panic!("expected a level shift"),
+        }
+    }
+
+    #[test]
+    fn runs_after_shift_are_not_outliers() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap();
+        let entries: Vec<HistoryEntry> = (0..60)
+            .map(|day| {
+                let level = if day < 30 { 100.0 } else { 300.0 };
+                let noise = [-2.0, 1.0, 0.5, -1.0, 2.0, -0.5][day % 6];
+                entry(start + chrono::Duration::days(day as i64), level + noise)
+            })
+            .collect();
+
+        let anomalies =
+            detect_anomalies(&entries, &[Metric::Download], &AnomalySettings::default());
+        assert_eq!(anomalies.len(), 1);
+        assert!(matches!(anomalies[0].kind, AnomalyKind::LevelShift { .. }));
+    }
+
+    #[test]
+    fn skipped_phases_are_not_measurements() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap();
+        let entries: Vec<HistoryEntry> = (0..40)
+            .map(|day| {
+                let noise = [-2.0, 1.0, 0.5, -1.0, 2.0, -0.5][day % 6];
+                let mut entry = entry(start + chrono::Duration::days(day as i64), 100.0 + noise);
+                // From day 20 every other run is upload-only.
+                if day >= 20 && day % 2 == 0 {
+                    entry.download_speed = 0.0;
+                    entry.method = Some(TestMethod {
+                        duration: 10,
+                        connections: 4,
+                        download: false,
+                        upload: true,
+                    });
+                }
+                entry
+            })
+            .collect();
+
+        assert_eq!(Metric::Download.value(&entries[20]), None);
+        assert_eq!(Metric::Upload.value(&entries[20]), Some(20.0));
+        assert!(detect_anomalies(&entries, &Metric::ALL, &AnomalySettings::default()).is_empty());
+    }
+
+    #[test]
+    fn change_from_zero_is_new() {
+        assert_eq!(relative_change_percent(80.0, 100.0), Some(25.0));
+        assert_eq!(relative_change_percent(0.0, 0.0), Some(0.0));
+        assert_eq!(relative_change_percent(0.0, 50.0), None);
+        assert_eq!(format_change(80.0, 60.0), "-25.0%");
+        assert_eq!(format_change(0.0, 50.0), "new");
+    }
+
+    #[test]
+    fn stable_series_has_no_anomalies() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap();
+        let entries: Vec<HistoryEntry> = (0..40)
+            .map(|day| {
+                let noise = [-2.0, 1.0, 0.5, -1.0, 2.0, -0.5][day % 6];
+                entry(start + chrono::Duration::days(day as i64), 100.0 + noise)
+            })
+            .collect();
+
+        assert!(detect_anomalies(&entries, &Metric::ALL, &AnomalySettings::default()).is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+//! Persistent test history.
+
+pub mod anomaly;
+
+use crate::network::speedtest::TestMethod;
+use crate::network::TestResult;
+use anomaly::Metric;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the history file location.
+pub const HISTORY_FILE_ENV: &str = "PINGTEST_HISTORY_FILE";
+
+/// A saved test result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    /// Download throughput in Mbps.
+    pub download_speed: f64,
+    /// Upload throughput in Mbps.
+    pub upload_speed: f64,
+    /// Round-trip latency in milliseconds.
+    pub ping: f64,
+    pub server_id: u32,
+    pub server_name: String,
+    pub server_location: String,
+    pub tag: Option<String>,
+    /// How the run was configured; a phase it skipped is saved as 0 Mbps.
+    /// `None` for runs saved before this was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<TestMethod>,
+    /// Shared by every run of one `--runs` invocation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// The test was stopped early; see [`TestResult::partial`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+}
+
+impl HistoryEntry {
+    pub fn from_result(result: &TestResult, tag: Option<String>, batch_id: Option<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: result.timestamp,
+            download_speed: result.download_speed,
+            upload_speed: result.upload_speed,
+            ping: result.ping,
+            server_id: result.server_id,
+            server_name: result.server_name.clone(),
+            server_location: result.server_location.clone(),
+            tag,
+            method: Some(result.method.clone()),
+            batch_id,
+            partial: result.partial,
+        }
+    }
+}
+
+/// Averages and extremes of saved results. Best is the highest throughput and
+/// the lowest ping. Runs stopped early are left out, and runs that skipped a
+/// metric do not count towards it; a metric no run measured is zero.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HistoryStatistics {
+    pub total_tests: usize,
+    pub avg_download: f64,
+    pub avg_upload: f64,
+    pub avg_ping: f64,
+    pub best_download: f64,
+    pub best_upload: f64,
+    pub best_ping: f64,
+    pub worst_download: f64,
+    pub worst_upload: f64,
+    pub worst_ping: f64,
+}
+
+impl HistoryStatistics {
+    pub fn from_entries(entries: &[HistoryEntry]) -> Self {
+        let complete: Vec<&HistoryEntry> = entries.iter().filter(|e| !e.partial).collect();
+        if complete.is_empty() {
+            return Self::default();
+        }
+        let [download, upload, ping] = Metric::ALL.map(|metric| {
+            let values: Vec<f64> = complete.iter().filter_map(|e| metric.value(e)).collect();
+            if values.is_empty() {
+                return (0.0, 0.0, 0.0);
+            }
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            if metric == Metric::Latency {
+                (mean, min, max)
+            } else {
+                (mean, max, min)
+            }
+        });
+        Self {
+            total_tests: complete.len(),
+            avg_download: download.0,
+            avg_upload: upload.0,
+            avg_ping: ping.0,
+            best_download: download.1,
+            best_upload: upload.1,
+            best_ping: ping.1,
+            worst_download: download.2,
+            worst_upload: upload.2,
+            worst_ping: ping.2,
+        }
+    }
+
+    /// Average, best and worst of one metric.
+    pub fn summary(&self, metric: Metric) -> (f64, f64, f64) {
+        match metric {
+            Metric::Download => (self.avg_download, self.best_download, self.worst_download),
+            Metric::Upload => (self.avg_upload, self.best_upload, self.worst_upload),
+            Metric::Latency => (self.avg_ping, self.best_ping, self.worst_ping),
+        }
+    }
+}
+
+/// The average of one metric over the runs of one day (UTC).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trend {
+    pub date: NaiveDate,
+    pub value: f64,
+    pub runs: usize,
+}
+
+/// Reads and writes the history file.
+pub struct HistoryManager {
+    path: PathBuf,
+}
+
+impl HistoryManager {
+    /// Opens the history stored at [`HistoryManager::default_path`].
+    pub async fn new() -> Result<Self> {
+        Self::with_path(Self::default_path()).await
+    }
+
+    pub async fn with_path(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        Ok(Self { path })
+    }
+
+    /// `$PINGTEST_HISTORY_FILE`, or `history.json` in the platform data directory.
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os(HISTORY_FILE_ENV) {
+            return PathBuf::from(path);
+        }
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("pingtest")
+            .join("history.json")
+    }
+
+    pub async fn add_entry(&self, entry: HistoryEntry) -> Result<()> {
+        let _lock = self.lock().await?;
+        let mut entries = self.get_history().await?;
+        entries.push(entry);
+        self.write(&entries).await
+    }
+
+    /// All saved entries, oldest first.
+    pub async fn get_history(&self) -> Result<Vec<HistoryEntry>> {
+        let data = match tokio::fs::read_to_string(&self.path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()))
+            }
+        };
+        let mut entries: Vec<HistoryEntry> = serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse {}", self.path.display()))?;
+        entries.sort_by_key(|e| e.timestamp);
+        Ok(entries)
+    }
+
+    /// Entries saved within the last `days` days, oldest first.
+    pub async fn get_entries_since(&self, days: u32) -> Result<Vec<HistoryEntry>> {
+        let cutoff = Utc::now() - Duration::days(i64::from(days));
+        let mut entries = self.get_history().await?;
+        entries.retain(|e| e.timestamp >= cutoff);
+        Ok(entries)
+    }
+
+    /// Statistics over the entries saved within the last `days` days.
+    pub async fn get_statistics(&self, days: u32) -> Result<HistoryStatistics> {
+        Ok(HistoryStatistics::from_entries(
+            &self.get_entries_since(days).await?,
+        ))
+    }
+
+    /// Daily averages of `metric` (`download`, `upload` or `latency`),
+    /// oldest first. Days without a complete run that measured it are left
+    /// out.
+    pub async fn get_trends(&self, metric: &str) -> Result<Vec<Trend>> {
+        let metric = Metric::from_str(metric, true).map_err(|_| {
+            anyhow::anyhow!(
+                "Unknown metric '{}': use download, upload or latency",
+                metric
+            )
+        })?;
+        let mut days: BTreeMap<NaiveDate, (f64, usize)> = BTreeMap::new();
+        for entry in self.get_history().await? {
+            if let Some(value) = metric.value(&entry) {
+                let day = days.entry(entry.timestamp.date_naive()).or_default();
+                day.0 += value;
+                day.1 += 1;
+            }
+        }
+        Ok(days
+            .into_iter()
+            .map(|(date, (sum, runs))| Trend {
+                date,
+                value: sum / runs as f64,
+                runs,
+            })
+            .collect())
+    }
+
+    /// Writes every saved entry to `path` as a JSON array.
+    pub async fn export_history(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let entries = self.get_history().await?;
+        tokio::fs::write(path, serde_json::to_string_pretty(&entries)?)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Deletes every saved entry.
+    pub async fn clear_history(&self) -> Result<()> {
+        let _lock = self.lock().await?;
+        self.write(&[]).await
+    }
+
+    /// Blocks until no other writer, in this process or another, holds the
+    /// history, and keeps it until the returned file is dropped.
+    async fn lock(&self) -> Result<std::fs::File> {
+        let path = self.sibling("lock");
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            file.lock()
+                .with_context(|| format!("Failed to lock {}", path.display()))?;
+            Ok(file)
+        })
+        .await?
+    }
+
+    /// Writes a temporary file and renames it over the history, so a crash
+    /// mid-write leaves the previous history intact. Callers hold the lock.
+    async fn write(&self, entries: &[HistoryEntry]) -> Result<()> {
+        let temp = self.sibling("tmp");
+        tokio::fs::write(&temp, serde_json::to_string_pretty(entries)?)
+            .await
+            .with_context(|| format!("Failed to write {}", temp.display()))?;
+        tokio::fs::rename(&temp, &self.path)
+            .await
+            .with_context(|| format!("Failed to replace {}", self.path.display()))
+    }
+
+    /// `history.json.<extension>` next to the history file.
+    fn sibling(&self, extension: &str) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(extension);
+        self.path.with_file_name(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: &str, download_speed: f64) -> HistoryEntry {
+        HistoryEntry {
+            id: timestamp.to_string(),
+            timestamp: timestamp.parse().unwrap(),
+            download_speed,
+            upload_speed: 20.0,
+            ping: 25.0,
+            server_id: 12345,
+            server_name: "PingTest Frankfurt".to_string(),
+            server_location: "Frankfurt, DE".to_string(),
+            tag: None,
+            method: None,
+            batch_id: None,
+            partial: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn averages_trends_per_day_and_clears() {
+        let path = std::env::temp_dir().join(format!("pingtest-{}.json", uuid::Uuid::new_v4()));
+        let history = HistoryManager::with_path(&path).await.unwrap();
+        for (timestamp, download) in [
+            ("2026-03-02T08:00:00Z", 40.0),
+            ("2026-03-01T09:00:00Z", 50.0),
+            ("2026-03-02T20:00:00Z", 60.0),
+        ] {
+            history.add_entry(entry(timestamp, download)).await.unwrap();
+        }
+
+        let trends = history.get_trends("download").await.unwrap();
+        let days: Vec<_> = trends
+            .iter()
+            .map(|t| (t.date.to_string(), t.value, t.runs))
+            .collect();
+        assert_eq!(
+            days,
+            [
+                ("2026-03-01".to_string(), 50.0, 1),
+                ("2026-03-02".to_string(), 50.0, 2)
+            ]
+        );
+        assert!(history.get_trends("jitter").await.is_err());
+
+        history.clear_history().await.unwrap();
+        assert!(history.get_history().await.unwrap().is_empty());
+        std::fs::remove_file(history.sibling("lock")).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn partial_runs_leave_statistics_unchanged() {
+        let path = std::env::temp_dir().join(format!("pingtest-{}.json", uuid::Uuid::new_v4()));
+        let history = HistoryManager::with_path(&path).await.unwrap();
+        let hours_ago = |hours| (Utc::now() - Duration::hours(hours)).to_rfc3339();
+        for (hours, download) in [(3, 90.0), (2, 100.0)] {
+            history
+                .add_entry(entry(&hours_ago(hours), download))
+                .await
+                .unwrap();
+        }
+        let before = history.get_statistics(7).await.unwrap();
+        let trends_before = history.get_trends("upload").await.unwrap();
+
+        // Stopped during the download phase: upload and ping never ran.
+        history
+            .add_entry(HistoryEntry {
+                upload_speed: 0.0,
+                ping: 0.0,
+                partial: true,
+                ..entry(&hours_ago(1), 35.0)
+            })
+            .await
+            .unwrap();
+        assert_eq!(history.get_history().await.unwrap().len(), 3);
+        assert_eq!(history.get_statistics(7).await.unwrap(), before);
+        assert_eq!(before.total_tests, 2);
+        assert_eq!(before.worst_download, 90.0);
+        assert_eq!(history.get_trends("upload").await.unwrap(), trends_before);
+        std::fs::remove_file(history.sibling("lock")).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn concurrent_saves_keep_every_entry() {
+        let path = std::env::temp_dir().join(format!("pingtest-{}.json", uuid::Uuid::new_v4()));
+        let saves: Vec<_> = (0..16)
+            .map(|i| {
+                let path = path.clone();
+                tokio::spawn(async move {
+                    // A manager each, like the daemon, the API and the CLI.
+                    let history = HistoryManager::with_path(path).await.unwrap();
+                    let timestamp = format!("2026-03-01T09:{:02}:00Z", i);
+                    history.add_entry(entry(&timestamp, 50.0)).await.unwrap();
+                })
+            })
+            .collect();
+        for save in saves {
+            save.await.unwrap();
+        }
+
+        let history = HistoryManager::with_path(&path).await.unwrap();
+        assert_eq!(history.get_history().await.unwrap().len(), 16);
+        assert!(!history.sibling("tmp").exists());
+        std::fs::remove_file(history.sibling("lock")).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
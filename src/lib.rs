@@ -0,0 +1,22 @@
+//! PingTest library: speed and ping testing, history, configuration,
+//! themes and network diagnostic tools. The `pingtest` binary is a thin
+//! command-line client of this crate.
+
+pub mod alerts;
+pub mod api;
+pub mod batch;
+pub mod config;
+pub mod daemon;
+pub mod dashboard;
+pub mod export;
+pub mod exporter;
+pub mod history;
+pub mod metrics;
+pub mod network;
+pub mod ping;
+pub mod report;
+pub mod run;
+pub mod thresholds;
+pub mod tools;
+pub mod ui;
+pub mod utils;
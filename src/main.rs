@@ -1,25 +1,72 @@
+use pingtest::{
+    alerts, api, config, daemon, dashboard, export, exporter, history, metrics, network, report,
+    run, thresholds, ui, utils,
+};
+
 use anyhow::Result;
-use clap::Parser;
-use std::time::{Duration, Instant};
-use tokio::time::sleep;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use config::Config;
+use daemon::cron::Schedule;
+use export::ExportFormat;
+use history::anomaly::{self, AnomalySettings, Metric};
+use history::HistoryManager;
+use metrics::{mqtt, otlp};
+use network::speedtest::TestMethod;
+use run::{defaults, RunOptions};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use thresholds::{Check, Threshold};
+use ui::output;
 
 #[derive(Parser)]
 #[command(name = "pingtest")]
-#[command(about = "A beautiful, fast, and feature-rich terminal-based internet speed test application")]
+#[command(
+    about = "A beautiful, fast, and feature-rich terminal-based internet speed test application"
+)]
 #[command(version)]
 struct Cli {
-    /// Run quick test with minimal output
-    #[arg(short, long)]
+    /// Run a short test and print one line: download, upload and ping
+    #[arg(short, long, conflicts_with_all = ["stream_json", "compare"])]
     quick: bool,
 
+    /// Config file [default: ~/.config/pingtest/config.toml, or $PINGTEST_CONFIG]
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Use the option values of a `[profiles.<name>]` section of the config file
+    #[arg(long, global = true, env = "PINGTEST_PROFILE", value_name = "NAME")]
+    profile: Option<String>,
+
     /// Test duration in seconds
-    #[arg(short, long, default_value = "15")]
+    #[arg(short, long, env = "PINGTEST_DURATION", default_value = "15")]
     duration: u64,
 
     /// Number of parallel connections
-    #[arg(short, long, default_value = "4")]
+    #[arg(short, long, env = "PINGTEST_CONNECTIONS", default_value = "4")]
     connections: u32,
 
+    /// End each throughput phase once the throughput settles; --duration becomes the maximum
+    #[arg(long)]
+    adaptive: bool,
+
+    /// How far the --adaptive throughput estimate may move over 3 seconds, in percent
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        default_value = "5",
+        requires = "adaptive"
+    )]
+    tolerance: f64,
+
+    /// End each throughput phase once it has moved this much data (e.g. 200MB)
+    #[arg(long, value_name = "SIZE", value_parser = utils::parse_bytes)]
+    max_bytes: Option<u64>,
+
+    /// Cap sending and receiving at this rate (e.g. 10Mbps)
+    #[arg(long, value_name = "RATE", value_parser = utils::parse_rate)]
+    rate_limit: Option<f64>,
+
     /// Skip download test
     #[arg(long)]
     no_download: bool,
@@ -29,155 +76,540 @@ struct Cli {
     no_upload: bool,
 
     /// Color theme
-    #[arg(short, long, default_value = "auto")]
+    #[arg(short, long, env = "PINGTEST_THEME", default_value = "auto", value_parser = clap::builder::PossibleValuesParser::new(config::THEMES))]
     theme: String,
 
-    /// Export results to JSON
+    /// Export results to a file (format inferred from .json, .csv, .ndjson or .md)
     #[arg(short, long)]
     export: Option<String>,
 
+    /// Export format, overriding the file extension; `markdown` without --export prints to stdout
+    #[arg(long, value_enum, global = true)]
+    format: Option<ExportFormat>,
+
+    /// Stream progress and the final result as NDJSON events on stdout
+    #[arg(long)]
+    stream_json: bool,
+
+    /// Write InfluxDB line protocol to a file, `-` for stdout, or an InfluxDB v2 URL
+    #[arg(long, value_name = "DEST")]
+    influx: Option<String>,
+
+    /// Include per-interval throughput samples in the InfluxDB output
+    #[arg(long, requires = "influx")]
+    influx_samples: bool,
+
+    /// InfluxDB measurement name
+    #[arg(long, default_value = "pingtest")]
+    influx_measurement: String,
+
+    /// InfluxDB organization (for URL destinations)
+    #[arg(long, env = "INFLUX_ORG")]
+    influx_org: Option<String>,
+
+    /// InfluxDB bucket (for URL destinations)
+    #[arg(long, env = "INFLUX_BUCKET")]
+    influx_bucket: Option<String>,
+
+    /// InfluxDB API token (for URL destinations)
+    #[arg(long, env = "INFLUX_TOKEN", hide_env_values = true)]
+    influx_token: Option<String>,
+
+    /// Network interface to label InfluxDB points and reports with [default: the default-route interface]
+    #[arg(long)]
+    interface: Option<String>,
+
+    /// Export results as OTLP/HTTP metrics to this collector (e.g. http://localhost:4318)
+    #[arg(long, value_name = "ENDPOINT")]
+    otlp: Option<String>,
+
+    /// Extra header for OTLP requests, as KEY=VALUE (repeatable)
+    #[arg(long, value_name = "KEY=VALUE", requires = "otlp", value_parser = otlp::parse_header)]
+    otlp_header: Vec<(String, String)>,
+
+    /// Also export each test as a trace with a span per phase
+    #[arg(long, requires = "otlp")]
+    otlp_traces: bool,
+
+    /// Publish results to this MQTT broker (mqtt://host[:port] or mqtts://host[:port])
+    #[arg(long, value_name = "URL")]
+    mqtt: Option<String>,
+
+    /// Prefix of the retained state topics
+    #[arg(long, default_value = "pingtest", requires = "mqtt")]
+    mqtt_topic: String,
+
+    /// Home Assistant discovery prefix
+    #[arg(long, default_value = "homeassistant", requires = "mqtt")]
+    mqtt_discovery_prefix: String,
+
+    /// MQTT user name
+    #[arg(long, env = "MQTT_USERNAME")]
+    mqtt_username: Option<String>,
+
+    /// MQTT password
+    #[arg(long, env = "MQTT_PASSWORD", hide_env_values = true)]
+    mqtt_password: Option<String>,
+
+    /// PEM file of CA certificates to trust for mqtts:// [default: the built-in web roots]
+    #[arg(long, value_name = "FILE", requires = "mqtt")]
+    mqtt_ca: Option<PathBuf>,
+
+    /// Minimum download speed in Mbps
+    #[arg(long, value_name = "MBPS")]
+    min_download: Option<f64>,
+
+    /// Minimum upload speed in Mbps
+    #[arg(long, value_name = "MBPS")]
+    min_upload: Option<f64>,
+
+    /// Maximum latency in milliseconds
+    #[arg(long, value_name = "MS")]
+    max_latency: Option<f64>,
+
+    /// Maximum jitter in milliseconds
+    #[arg(long, value_name = "MS")]
+    max_jitter: Option<f64>,
+
+    /// Maximum packet loss in percent
+    #[arg(long, value_name = "PERCENT")]
+    max_loss: Option<f64>,
+
+    /// Minimum quality score (0-100)
+    #[arg(long, value_name = "SCORE")]
+    min_score: Option<f64>,
+
+    /// Write a JUnit XML report with one testcase per threshold
+    #[arg(long, value_name = "FILE")]
+    junit: Option<PathBuf>,
+
+    /// Write a self-contained HTML report with charts
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Include saved history from the last N days in the HTML report
+    #[arg(long, value_name = "DAYS", requires = "report")]
+    report_days: Option<u32>,
+
     /// Save results to history
     #[arg(long)]
     save: bool,
 
+    /// Do not save results, even with `auto_save` in the config file
+    #[arg(long, conflicts_with = "save")]
+    no_save: bool,
+
     /// Add tag to saved result
     #[arg(long)]
     tag: Option<String>,
+
+    /// Repeat the whole test N times and summarise the spread
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["junit", "report"])]
+    runs: u32,
+
+    /// Time between the start of one run and the next (e.g. 30s, 5m)
+    #[arg(long, default_value = "0s", value_parser = utils::parse_duration, requires = "runs")]
+    interval: Duration,
+
+    /// Compare the result with the average of saved results
+    #[arg(long, conflicts_with = "stream_json")]
+    compare: bool,
+
+    /// Compare with results saved in the last N days
+    #[arg(long, default_value = "7", requires = "compare")]
+    days: u32,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-
-    println!("🚀 PingTest - Internet Speed Test");
-    println!("==================================");
-    println!();
-
-    // Run speed test
-    let start_time = Instant::now();
-    
-    println!("🌐 Running speed test...");
-    println!("Duration: {} seconds", cli.duration);
-    println!("Connections: {}", cli.connections);
-    println!("Theme: {}", cli.theme);
-    println!();
-
-    // Simulate speed test
-    let mut download_speed = 0.0;
-    let mut upload_speed = 0.0;
-    let mut ping = 0.0;
-
-    if !cli.no_download {
-        println!("📥 Testing download speed...");
-        for i in 0..cli.duration {
-            sleep(Duration::from_secs(1)).await;
-            download_speed = 50.0 + (i as f64 * 2.0).sin() * 10.0;
-            print!("\rDownload: {:.1} Mbps", download_speed);
-            std::io::Write::flush(&mut std::io::stdout()).unwrap();
-        }
-        println!();
+impl Cli {
+    /// `--save`, or `auto_save` from the config file unless `--no-save`.
+    fn saves_history(&self) -> bool {
+        self.save && !self.no_save
     }
 
-    if !cli.no_upload {
-        println!("📤 Testing upload speed...");
-        for i in 0..cli.duration {
-            sleep(Duration::from_secs(1)).await;
-            upload_speed = 20.0 + (i as f64 * 1.5).sin() * 5.0;
-            print!("\rUpload: {:.1} Mbps", upload_speed);
-            std::io::Write::flush(&mut std::io::stdout()).unwrap();
+    fn test_method(&self) -> TestMethod {
+        TestMethod {
+            duration: self.duration,
+            connections: self.connections,
+            download: !self.no_download,
+            upload: !self.no_upload,
         }
-        println!();
     }
 
-    // Simulate ping test
-    println!("🏓 Testing ping...");
-    sleep(Duration::from_secs(2)).await;
-    ping = 25.0 + (rand::random::<f64>() - 0.5) * 10.0;
-
-    let total_duration = start_time.elapsed();
-
-    // Display results
-    println!();
-    println!("📊 Test Results:");
-    println!("================");
-    println!("Download Speed: {:.1} Mbps", download_speed);
-    println!("Upload Speed: {:.1} Mbps", upload_speed);
-    println!("Ping: {:.1} ms", ping);
-    println!("Test Duration: {:.1} seconds", total_duration.as_secs_f64());
-    println!();
-
-    // Calculate quality score
-    let quality_score = calculate_quality_score(download_speed, upload_speed, ping);
-    let quality_desc = get_quality_description(quality_score);
-    
-    println!("🎯 Network Quality: {}/100 ({})", quality_score, quality_desc);
-
-    // Export results if requested
-    if let Some(export_path) = cli.export {
-        let export_data = serde_json::json!({
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-            "download_speed": download_speed,
-            "upload_speed": upload_speed,
-            "ping": ping,
-            "test_duration": total_duration.as_secs_f64(),
-            "connections": cli.connections,
-            "quality_score": quality_score,
-            "quality_description": quality_desc
-        });
-
-        std::fs::write(&export_path, serde_json::to_string_pretty(&export_data)?)?;
-        println!("📁 Results exported to: {}", export_path);
+    fn mqtt_settings(&self) -> Option<mqtt::MqttSettings> {
+        self.mqtt.as_ref().map(|url| mqtt::MqttSettings {
+            url: url.clone(),
+            topic: self.mqtt_topic.clone(),
+            discovery_prefix: self.mqtt_discovery_prefix.clone(),
+            username: self.mqtt_username.clone(),
+            password: self.mqtt_password.clone(),
+            ca: self.mqtt_ca.clone(),
+        })
     }
 
-    // Save to history if requested
-    if cli.save {
-        println!("💾 Results saved to history");
-        if let Some(tag) = cli.tag {
-            println!("🏷️  Tag: {}", tag);
+    fn thresholds(&self) -> Vec<Threshold> {
+        [
+            (Check::Download, self.min_download),
+            (Check::Upload, self.min_upload),
+            (Check::Latency, self.max_latency),
+            (Check::Jitter, self.max_jitter),
+            (Check::Loss, self.max_loss),
+            (Check::Score, self.min_score),
+        ]
+        .into_iter()
+        .filter_map(|(check, limit)| limit.map(|limit| Threshold { check, limit }))
+        .collect()
+    }
+
+    fn run_options(&self, config: &Config) -> RunOptions {
+        RunOptions {
+            method: self.test_method(),
+            settings: config.test_settings(),
+            quick: self.quick,
+            adaptive: self.adaptive.then_some(self.tolerance),
+            max_bytes: self.max_bytes,
+            rate_limit: self.rate_limit,
+            theme: self.theme.clone(),
+            export: self.export.as_ref().map(PathBuf::from),
+            format: self.format,
+            default_format: config.export.default_format,
+            stream_json: self.stream_json,
+            influx: self.influx.as_ref().map(|destination| run::InfluxOptions {
+                destination: destination.clone(),
+                samples: self.influx_samples,
+                measurement: self.influx_measurement.clone(),
+                org: self.influx_org.clone(),
+                bucket: self.influx_bucket.clone(),
+                token: self.influx_token.clone(),
+            }),
+            interface: self.interface.clone(),
+            otlp: self.otlp.as_ref().map(|endpoint| run::OtlpOptions {
+                endpoint: endpoint.clone(),
+                headers: self.otlp_header.clone(),
+                traces: self.otlp_traces,
+            }),
+            mqtt: self.mqtt_settings(),
+            thresholds: self.thresholds(),
+            junit: self.junit.clone(),
+            report: self.report.clone(),
+            report_days: self.report_days,
+            save: self.saves_history(),
+            tag: self.tag.clone(),
+            runs: self.runs,
+            interval: self.interval,
+            compare: self.compare.then_some(self.days),
         }
     }
+}
 
-    println!();
-    println!("✅ Speed test completed successfully!");
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect saved test history
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
+    },
+    /// Print the JSON Schema for exported results
+    Schema,
+    /// Run tests on a schedule and serve them as Prometheus metrics
+    Exporter {
+        /// Address to serve /metrics on
+        #[arg(long, default_value = ":9516")]
+        listen: String,
 
-    Ok(())
+        /// Time between scheduled tests (e.g. 30s, 15m, 1h)
+        #[arg(long, default_value = "15m", value_parser = utils::parse_duration)]
+        interval: Duration,
+
+        /// Run a test when scraped instead of on a schedule
+        #[arg(long)]
+        on_scrape: bool,
+
+        /// How long a result is reused before a scrape runs a new test
+        #[arg(long, default_value = "5m", value_parser = utils::parse_duration)]
+        cache_ttl: Duration,
+
+        /// Also write node_exporter textfile-collector metrics to this .prom file
+        #[arg(long)]
+        textfile: Option<PathBuf>,
+
+        /// Run one test, write --textfile and exit without serving
+        #[arg(long, requires = "textfile")]
+        once: bool,
+    },
+    /// Serve an HTTP/JSON API for starting tests and reading results and history
+    Api {
+        /// Address to serve the API on
+        #[arg(long, default_value = "127.0.0.1:9517")]
+        listen: String,
+
+        /// Bearer token clients must send [default: a random token, printed at startup]
+        #[arg(long, env = "PINGTEST_API_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+    },
+    /// Serve a local web dashboard with live charts, history trends and a run button
+    Dashboard {
+        /// Address to serve the dashboard on
+        #[arg(long, default_value = "127.0.0.1:9518")]
+        listen: String,
+    },
+    /// Run tests unattended on cron schedules and save them to history
+    Daemon {
+        /// Cron expression, e.g. "*/15 * * * 1-5" for every 15 minutes on weekdays (repeatable) [default: daemon.schedules from the config file]
+        #[arg(long = "schedule", value_name = "CRON")]
+        schedules: Vec<Schedule>,
+
+        /// Maximum random delay added to each run so hosts do not test in lockstep [default: daemon.random_delay from the config file, or 60s]
+        #[arg(long, value_parser = utils::parse_duration)]
+        random_delay: Option<Duration>,
+    },
+    /// Show or create the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
 }
 
-fn calculate_quality_score(download: f64, upload: f64, ping: f64) -> u8 {
-    let speed_score = if download >= 100.0 {
-        100.0
-    } else if download >= 50.0 {
-        80.0
-    } else if download >= 25.0 {
-        60.0
-    } else if download >= 10.0 {
-        40.0
-    } else {
-        20.0
-    };
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the config file location
+    Path,
+    /// Print the config file's settings, with defaults filled in
+    Show,
+    /// List profiles with the option values each one runs with
+    Profiles,
+    /// Write a config file with the default settings
+    Init {
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// Show averages, best and worst results
+    Stats {
+        /// Only consider results from the last N days
+        #[arg(long, default_value = "30")]
+        days: u32,
+    },
+    /// Flag outlier runs and sustained level shifts
+    Anomalies {
+        /// Only consider results from the last N days
+        #[arg(long, default_value = "90")]
+        days: u32,
+
+        /// Only check this metric
+        #[arg(long, value_enum)]
+        metric: Option<Metric>,
+
+        /// Robust z-score above which a run is an outlier
+        #[arg(long, default_value = "3.5")]
+        threshold: f64,
 
-    let ping_score = if ping <= 20.0 {
-        100.0
-    } else if ping <= 50.0 {
-        80.0
-    } else if ping <= 100.0 {
-        60.0
-    } else if ping <= 200.0 {
-        40.0
-    } else {
-        20.0
+        /// Minimum number of runs on each side of a level shift
+        #[arg(long, default_value = "5")]
+        min_segment: usize,
+
+        /// Minimum change between levels, in percent
+        #[arg(long, default_value = "15")]
+        min_shift: f64,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
+    // Parse once to find the config file and profile, then again with their
+    // values as the option defaults so flags and environment variables still
+    // take precedence.
+    let Cli {
+        config: config_file,
+        profile,
+        ..
+    } = Cli::parse();
+    let config = match &config_file {
+        Some(path) => Config::load_from(path).await?,
+        None => Config::load().await?,
     };
+    let config_path = config_file.clone().unwrap_or_else(Config::default_path);
+    let defaults = defaults::resolve(&Cli::command(), &config, profile.as_deref(), &config_path)?;
+    let matches = defaults::with_defaults(Cli::command(), &defaults).get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    match cli.command {
+        Some(Command::History { ref command }) => run_history_command(command, cli.format).await?,
+        Some(Command::Schema) => print!("{}", export::schema::json_schema()),
+        Some(Command::Exporter {
+            ref listen,
+            interval,
+            on_scrape,
+            cache_ttl,
+            ref textfile,
+            once,
+        }) => {
+            let options = exporter::ExporterOptions {
+                listen: utils::parse_listen_addr(listen)?,
+                interval,
+                on_scrape,
+                cache_ttl,
+                textfile: textfile.clone(),
+                method: cli.test_method(),
+                settings: config.test_settings(),
+                tag: cli.tag.clone(),
+            };
+            if once {
+                exporter::write_once(options).await?
+            } else {
+                exporter::serve(options).await?
+            }
+        }
+        Some(Command::Api {
+            ref listen,
+            ref token,
+        }) => {
+            let token = match token {
+                Some(token) if token.is_empty() => anyhow::bail!("The API token is empty"),
+                Some(token) => token.clone(),
+                None => {
+                    let token = uuid::Uuid::new_v4().simple().to_string();
+                    println!("🔑 API token: {}", token);
+                    token
+                }
+            };
+            api::serve(api::ApiOptions {
+                listen: utils::parse_listen_addr(listen)?,
+                token,
+                method: cli.test_method(),
+                settings: config.test_settings(),
+                tag: cli.tag.clone(),
+            })
+            .await?
+        }
+        Some(Command::Dashboard { ref listen }) => {
+            dashboard::serve(dashboard::DashboardOptions {
+                listen: utils::parse_listen_addr(listen)?,
+                method: cli.test_method(),
+                settings: config.test_settings(),
+                tag: cli.tag.clone(),
+            })
+            .await?
+        }
+        Some(Command::Daemon {
+            ref schedules,
+            random_delay,
+        }) => {
+            let schedules = if schedules.is_empty() {
+                config
+                    .daemon
+                    .schedules
+                    .iter()
+                    .map(|s| s.parse())
+                    .collect::<Result<Vec<Schedule>>>()?
+            } else {
+                schedules.clone()
+            };
+            if schedules.is_empty() {
+                anyhow::bail!(
+                    "No schedule: pass --schedule or set daemon.schedules in {}",
+                    config_path.display()
+                );
+            }
+            let random_delay = match (random_delay, &config.daemon.random_delay) {
+                (Some(delay), _) => delay,
+                (None, Some(delay)) => utils::parse_duration(delay)?,
+                (None, None) => Duration::from_secs(60),
+            };
+            let webhooks = config.alerts.webhooks.iter().cloned();
+            daemon::serve(daemon::DaemonOptions {
+                schedules,
+                random_delay,
+                method: cli.test_method(),
+                settings: config.test_settings(),
+                tag: cli.tag.clone(),
+                mqtt: cli.mqtt_settings().map(mqtt::Publisher::new).transpose()?,
+                alerter: alerts::Alerter::new(
+                    config.alerts.rules.clone(),
+                    webhooks.map(alerts::webhook::Webhook::new).collect(),
+                    config
+                        .alerts
+                        .email
+                        .clone()
+                        .map(alerts::email::Email::new)
+                        .transpose()?,
+                ),
+            })
+            .await?
+        }
+        Some(Command::Config { ref command }) => {
+            run_config_command(command, &config, config_file.as_deref()).await?
+        }
+        None => return Ok(ExitCode::from(run::run(cli.run_options(&config)).await?)),
+    }
+    Ok(ExitCode::SUCCESS)
+}
 
-    ((speed_score * 0.7 + ping_score * 0.3) as u8).min(100)
+/// `file` is the `--config` path, if one was given.
+async fn run_config_command(
+    command: &ConfigCommand,
+    config: &Config,
+    file: Option<&Path>,
+) -> Result<()> {
+    let path = file.map_or_else(Config::default_path, Path::to_path_buf);
+    match *command {
+        ConfigCommand::Path => println!("{}", path.display()),
+        ConfigCommand::Show => print!("{}", config.to_toml()?),
+        ConfigCommand::Profiles => defaults::print_profiles(&Cli::command(), config, &path)?,
+        ConfigCommand::Init { force } => {
+            if path.exists() && !force {
+                anyhow::bail!(
+                    "{} already exists; pass --force to overwrite it",
+                    path.display()
+                );
+            }
+            match file {
+                Some(file) => Config::default().save_to(file).await?,
+                None => Config::default().save().await?,
+            }
+            println!("📝 Config written to: {}", path.display());
+        }
+    }
+    Ok(())
 }
 
-fn get_quality_description(score: u8) -> &'static str {
-    match score {
-        90..=100 => "Excellent",
-        80..=89 => "Very Good",
-        70..=79 => "Good",
-        60..=69 => "Fair",
-        50..=59 => "Poor",
-        _ => "Very Poor",
+async fn run_history_command(command: &HistoryCommand, format: Option<ExportFormat>) -> Result<()> {
+    match *command {
+        HistoryCommand::Stats { days } => {
+            let stats = HistoryManager::new().await?.get_statistics(days).await?;
+            match format {
+                Some(ExportFormat::Markdown) => {
+                    print!("{}", report::markdown::history_statistics(&stats, days));
+                }
+                Some(_) => anyhow::bail!("history stats only supports --format markdown"),
+                None => output::print_history_statistics(&stats, days),
+            }
+        }
+        HistoryCommand::Anomalies {
+            days,
+            metric,
+            threshold,
+            min_segment,
+            min_shift,
+        } => {
+            let entries = HistoryManager::new().await?.get_entries_since(days).await?;
+            let settings = AnomalySettings {
+                outlier_threshold: threshold,
+                min_segment,
+                min_shift_percent: min_shift,
+                ..AnomalySettings::default()
+            };
+            let metrics = metric.map_or(Metric::ALL.to_vec(), |m| vec![m]);
+            let anomalies = anomaly::detect_anomalies(&entries, &metrics, &settings);
+            output::print_anomalies(&anomalies, days, entries.len());
+        }
     }
-}
\ No newline at end of file
+
+    Ok(())
+}
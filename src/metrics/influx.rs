@@ -0,0 +1,413 @@
+//! InfluxDB line protocol output.
+//!
+//! Each result becomes one point in the configured measurement; per-interval
+//! throughput samples optionally go to `<measurement>_sample`. Lines can be
+//! written to stdout, appended to a file, or sent to an InfluxDB v2
+//! `/api/v2/write` endpoint.
+
+use crate::network::{Sample, TestResult};
+use crate::utils::get_quality_score;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::time::Duration;
+
+enum FieldValue {
+    Float(f64),
+    Integer(i64),
+}
+
+/// Formats results as line protocol with `server`, `tag` and `interface` tags.
+pub struct LineEncoder {
+    pub measurement: String,
+    pub tag: Option<String>,
+    pub interface: Option<String>,
+}
+
+impl LineEncoder {
+    /// One line for the result, followed by one per sample.
+    pub fn encode(&self, result: &TestResult, samples: &[Sample]) -> String {
+        let mut tags = Vec::new();
+        tags.extend(self.interface.as_deref().map(|i| ("interface", i)));
+        tags.push(("server", result.server_name.as_str()));
+        tags.extend(self.tag.as_deref().map(|t| ("tag", t)));
+
+        let mut fields = Vec::new();
+        if result.method.download {
+            fields.push((
+                "download_bps",
+                FieldValue::Float(result.download_speed * 1e6),
+            ));
+        }
+        if result.method.upload {
+            fields.push(("upload_bps", FieldValue::Float(result.upload_speed * 1e6)));
+        }
+        fields.push(("ping_ms", FieldValue::Float(result.ping)));
+        fields.push(("jitter_ms", FieldValue::Float(result.jitter)));
+        fields.push(("packet_loss_percent", FieldValue::Float(result.packet_loss)));
+        let score = get_quality_score(result.download_speed, result.upload_speed, result.ping);
+        fields.push(("quality_score", FieldValue::Integer(score.into())));
+        fields.push((
+            "elapsed_ms",
+            FieldValue::Float(result.test_duration * 1000.0),
+        ));
+
+        let mut out = String::new();
+        write_line(
+            &mut out,
+            &self.measurement,
+            &tags,
+            &fields,
+            result.timestamp,
+        );
+
+        let sample_measurement = format!("{}_sample", self.measurement);
+        for sample in samples {
+            let mut sample_tags = tags.clone();
+            let at = sample_tags.partition_point(|(key, _)| *key < "phase");
+            sample_tags.insert(at, ("phase", sample.phase.as_str()));
+            let fields = [
+                ("interval", FieldValue::Integer(sample.interval as i64)),
+                ("bytes", FieldValue::Integer(sample.bytes as i64)),
+                ("mbps", FieldValue::Float(sample.mbps)),
+            ];
+            write_line(
+                &mut out,
+                &sample_measurement,
+                &sample_tags,
+                &fields,
+                sample.timestamp,
+            );
+        }
+        out
+    }
+}
+
+/// Writes one point. Tags must already be sorted by key; empty tag values
+/// are dropped because line protocol does not allow them.
+fn write_line(
+    out: &mut String,
+    measurement: &str,
+    tags: &[(&str, &str)],
+    fields: &[(&str, FieldValue)],
+    timestamp: DateTime<Utc>,
+) {
+    out.push_str(&escape(measurement, &[',', ' ']));
+    for (key, value) in tags.iter().filter(|(_, value)| !value.is_empty()) {
+        let _ = write!(
+            out,
+            ",{}={}",
+            escape(key, &[',', '=', ' ']),
+            escape(value, &[',', '=', ' '])
+        );
+    }
+    for (i, (key, value)) in fields.iter().enumerate() {
+        out.push(if i == 0 { ' ' } else { ',' });
+        out.push_str(&escape(key, &[',', '=', ' ']));
+        let _ = match value {
+            FieldValue::Float(v) => write!(out, "={}", v),
+            FieldValue::Integer(v) => write!(out, "={}i", v),
+        };
+    }
+    let _ = writeln!(
+        out,
+        " {}",
+        timestamp.timestamp_nanos_opt().unwrap_or_default()
+    );
+}
+
+fn escape(value: &str, special: &[char]) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '\\' || special.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// How long one write request may take; a timeout is retried like a
+/// connection failure.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where line protocol goes.
+pub enum Destination {
+    Stdout,
+    File(PathBuf),
+    Http(HttpWriter),
+}
+
+impl Destination {
+    /// `-` is stdout, an `http://` or `https://` URL is an InfluxDB v2 server,
+    /// anything else is a file to append to.
+    pub fn parse(
+        dest: &str,
+        org: Option<String>,
+        bucket: Option<String>,
+        token: Option<String>,
+    ) -> Result<Self> {
+        if dest == "-" {
+            return Ok(Self::Stdout);
+        }
+        if !(dest.starts_with("http://") || dest.starts_with("https://")) {
+            return Ok(Self::File(PathBuf::from(dest)));
+        }
+        let (Some(org), Some(bucket)) = (org, bucket) else {
+            bail!(
+                "--influx-org and --influx-bucket are required when writing to {}",
+                dest
+            );
+        };
+        Ok(Self::Http(HttpWriter {
+            client: reqwest::Client::builder()
+                .timeout(TIMEOUT)
+                .build()
+                .expect("the HTTP client builds"),
+            url: format!("{}/api/v2/write", dest.trim_end_matches('/')),
+            org,
+            bucket,
+            token,
+            max_retries: 3,
+            backoff: Duration::from_secs(1),
+        }))
+    }
+
+    pub fn is_stdout(&self) -> bool {
+        matches!(self, Self::Stdout)
+    }
+
+    pub async fn write(&self, lines: &str) -> Result<()> {
+        match self {
+            Self::Stdout => {
+                print!("{}", lines);
+                Ok(())
+            }
+            Self::File(path) => std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(lines.as_bytes()))
+                .with_context(|| format!("Failed to write {}", path.display())),
+            Self::Http(writer) => writer.write(lines).await,
+        }
+    }
+}
+
+/// Sends line protocol to InfluxDB v2, retrying throttled requests, server
+/// errors and connection failures with exponential backoff.
+pub struct HttpWriter {
+    client: reqwest::Client,
+    url: String,
+    org: String,
+    bucket: String,
+    token: Option<String>,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl HttpWriter {
+    async fn write(&self, lines: &str) -> Result<()> {
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match self.send(lines).await {
+                Ok(()) => return Ok(()),
+                Err(Attempt::Fatal(e)) => return Err(e),
+                Err(Attempt::Retryable(e)) => e,
+            };
+            if attempt == self.max_retries {
+                return Err(error.context(format!("Gave up after {} attempts", attempt + 1)));
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    async fn send(&self, lines: &str) -> Result<(), Attempt> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .query(&[
+                ("org", self.org.as_str()),
+                ("bucket", self.bucket.as_str()),
+                ("precision", "ns"),
+            ])
+            .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(lines.to_string());
+        if let Some(token) = &self.token {
+            request = request.header(reqwest::header::AUTHORIZATION, format!("Token {}", token));
+        }
+
+        let response = request.send().await.map_err(|e| {
+            Attempt::Retryable(anyhow!(e).context(format!("Failed to reach {}", self.url)))
+        })?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        let error = anyhow!("InfluxDB write failed with {}: {}", status, body.trim());
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            Err(Attempt::Retryable(error))
+        } else {
+            Err(Attempt::Fatal(error))
+        }
+    }
+}
+
+enum Attempt {
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::speedtest::TestMethod;
+    use crate::network::Phase;
+    use axum::extract::{Query, State};
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn result() -> TestResult {
+        TestResult {
+            server_name: "PingTest Frankfurt, DE".to_string(),
+            method: TestMethod {
+                upload: false,
+                ..TestResult::sample().method
+            },
+            ..TestResult::sample()
+        }
+    }
+
+    fn encoder() -> LineEncoder {
+        LineEncoder {
+            measurement: "pingtest".to_string(),
+            tag: Some("home office".to_string()),
+            interface: Some("eth0".to_string()),
+        }
+    }
+
+    #[test]
+    fn encodes_result_and_samples() {
+        let sample = Sample {
+            timestamp: "2026-01-05T13:59:40Z".parse().unwrap(),
+            phase: Phase::Download,
+            interval: 1,
+            bytes: 6_250_000,
+            mbps: 50.0,
+        };
+        let lines = encoder().encode(&result(), &[sample]);
+        let lines: Vec<&str> = lines.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "pingtest,interface=eth0,server=PingTest\\ Frankfurt\\,\\ DE,tag=home\\ office \
+                 download_bps=94200000,ping_ms=12,jitter_ms=1.5,packet_loss_percent=0,\
+                 quality_score=86i,elapsed_ms=32100 1767621600000000000",
+                "pingtest_sample,interface=eth0,phase=download,server=PingTest\\ Frankfurt\\,\\ DE,\
+                 tag=home\\ office interval=1i,bytes=6250000i,mbps=50 1767621580000000000",
+            ]
+        );
+    }
+
+    /// Query parameters, `Authorization` header and body of one request.
+    type Request = (HashMap<String, String>, Option<String>, String);
+
+    #[derive(Default)]
+    struct StandIn {
+        failures_left: Mutex<u32>,
+        status: Mutex<Option<StatusCode>>,
+        requests: Mutex<Vec<Request>>,
+    }
+
+    async fn write_handler(
+        State(stand_in): State<Arc<StandIn>>,
+        Query(query): Query<HashMap<String, String>>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        let auth = headers
+            .get("authorization")
+            .map(|v| v.to_str().unwrap().to_string());
+        stand_in.requests.lock().unwrap().push((query, auth, body));
+        if let Some(status) = *stand_in.status.lock().unwrap() {
+            return status;
+        }
+        let mut failures = stand_in.failures_left.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::NO_CONTENT
+        }
+    }
+
+    async fn start(stand_in: Arc<StandIn>) -> String {
+        let app = Router::new()
+            .route("/api/v2/write", post(write_handler))
+            .with_state(stand_in);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn destination(url: &str) -> Destination {
+        let mut dest = Destination::parse(
+            url,
+            Some("acme".to_string()),
+            Some("network".to_string()),
+            Some("secret".to_string()),
+        )
+        .unwrap();
+        if let Destination::Http(writer) = &mut dest {
+            writer.backoff = Duration::from_millis(1);
+        }
+        dest
+    }
+
+    #[tokio::test]
+    async fn http_write_retries_server_errors() {
+        let stand_in = Arc::new(StandIn {
+            failures_left: Mutex::new(2),
+            ..StandIn::default()
+        });
+        let url = start(Arc::clone(&stand_in)).await;
+
+        destination(&url)
+            .write("pingtest ping_ms=12 1\n")
+            .await
+            .unwrap();
+
+        let requests = stand_in.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        let (query, auth, body) = &requests[2];
+        assert_eq!(query["org"], "acme");
+        assert_eq!(query["bucket"], "network");
+        assert_eq!(query["precision"], "ns");
+        assert_eq!(auth.as_deref(), Some("Token secret"));
+        assert_eq!(body, "pingtest ping_ms=12 1\n");
+    }
+
+    #[tokio::test]
+    async fn http_write_does_not_retry_client_errors() {
+        let stand_in = Arc::new(StandIn {
+            status: Mutex::new(Some(StatusCode::UNAUTHORIZED)),
+            ..StandIn::default()
+        });
+        let url = start(Arc::clone(&stand_in)).await;
+
+        let error = destination(&url).write("pingtest ping_ms=12 1\n").await;
+
+        assert!(error.unwrap_err().to_string().contains("401"));
+        assert_eq!(stand_in.requests.lock().unwrap().len(), 1);
+    }
+}
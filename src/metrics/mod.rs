@@ -0,0 +1,6 @@
+//! Metric output for monitoring systems.
+
+pub mod influx;
+pub mod mqtt;
+pub mod otlp;
+pub mod prometheus;
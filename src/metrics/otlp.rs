@@ -0,0 +1,518 @@
+//! OpenTelemetry export over OTLP/HTTP with protobuf encoding.
+//!
+//! Each result is sent as gauges to `<endpoint>/v1/metrics`. With traces
+//! enabled the test is also sent to `<endpoint>/v1/traces` as one trace with
+//! a span per phase.
+
+use crate::network::{PhaseSpan, TestResult};
+use crate::utils::{get_quality_score, hostname};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, InstrumentationScope, KeyValue};
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric, number_data_point, Gauge, Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::{span, ResourceSpans, ScopeSpans, Span};
+use prost::Message;
+use std::time::Duration;
+
+/// How long one export request may take; a timeout is retried like a
+/// connection failure.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Parses a `KEY=VALUE` request header, e.g. for collector authentication.
+pub fn parse_header(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => bail!("Invalid header '{}': expected KEY=VALUE", s),
+    }
+}
+
+/// Sends results to a collector, retrying throttled requests, server errors
+/// and connection failures with exponential backoff.
+pub struct OtlpExporter {
+    client: reqwest::Client,
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    traces: bool,
+    resource: Resource,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl OtlpExporter {
+    /// `endpoint` is the collector's base URL, e.g. `http://localhost:4318`.
+    pub fn new(
+        endpoint: &str,
+        headers: Vec<(String, String)>,
+        traces: bool,
+        tag: Option<&str>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(TIMEOUT)
+                .build()
+                .expect("the HTTP client builds"),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            headers,
+            traces,
+            resource: resource(hostname().as_deref(), tag),
+            max_retries: 3,
+            backoff: Duration::from_secs(1),
+        }
+    }
+
+    pub async fn export(&self, result: &TestResult, phases: &[PhaseSpan]) -> Result<()> {
+        let metrics = metrics_request(&self.resource, result, phases);
+        self.post("/v1/metrics", metrics.encode_to_vec()).await?;
+        if self.traces {
+            let traces = trace_request(&self.resource, result, phases, rand::random());
+            self.post("/v1/traces", traces.encode_to_vec()).await?;
+        }
+        Ok(())
+    }
+
+    async fn post(&self, path: &str, body: Vec<u8>) -> Result<()> {
+        let url = format!("{}{}", self.endpoint, path);
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        loop {
+            let error = match self.send(&url, &body).await {
+                Ok(()) => return Ok(()),
+                Err(Attempt::Fatal(e)) => return Err(e),
+                Err(Attempt::Retryable(e)) => e,
+            };
+            if attempt == self.max_retries {
+                return Err(error.context(format!("Gave up after {} attempts", attempt + 1)));
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    async fn send(&self, url: &str, body: &[u8]) -> Result<(), Attempt> {
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+            .body(body.to_vec());
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = request.send().await.map_err(|e| {
+            Attempt::Retryable(anyhow!(e).context(format!("Failed to reach {}", url)))
+        })?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.unwrap_or_default();
+        let error = anyhow!(
+            "OTLP export to {} failed with {}: {}",
+            url,
+            status,
+            body.trim()
+        );
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            Err(Attempt::Retryable(error))
+        } else {
+            Err(Attempt::Fatal(error))
+        }
+    }
+}
+
+enum Attempt {
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+fn resource(host: Option<&str>, tag: Option<&str>) -> Resource {
+    let mut attributes = vec![
+        string_attribute("service.name", "pingtest"),
+        string_attribute("service.version", env!("CARGO_PKG_VERSION")),
+        string_attribute("os.type", std::env::consts::OS),
+        string_attribute("host.arch", std::env::consts::ARCH),
+    ];
+    attributes.extend(host.map(|host| string_attribute("host.name", host)));
+    attributes.extend(tag.map(|tag| string_attribute("pingtest.tag", tag)));
+    Resource {
+        attributes,
+        ..Resource::default()
+    }
+}
+
+fn scope() -> InstrumentationScope {
+    InstrumentationScope {
+        name: "pingtest".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        ..InstrumentationScope::default()
+    }
+}
+
+fn server_attributes(result: &TestResult) -> Vec<KeyValue> {
+    vec![
+        string_attribute("server.address", &result.server_host),
+        string_attribute("pingtest.server.name", &result.server_name),
+        int_attribute("pingtest.server.id", result.server_id.into()),
+    ]
+}
+
+/// Gauges for the result and one `pingtest.phase.duration` point per phase.
+pub fn metrics_request(
+    resource: &Resource,
+    result: &TestResult,
+    phases: &[PhaseSpan],
+) -> ExportMetricsServiceRequest {
+    let time = unix_nanos(result.timestamp);
+    let point = |value: f64, mut attributes: Vec<KeyValue>| {
+        attributes.extend(server_attributes(result));
+        NumberDataPoint {
+            attributes,
+            time_unix_nano: time,
+            value: Some(number_data_point::Value::AsDouble(value)),
+            ..NumberDataPoint::default()
+        }
+    };
+    let gauge = |name: &str, description: &str, unit: &str, data_points| Metric {
+        name: name.to_string(),
+        description: description.to_string(),
+        unit: unit.to_string(),
+        data: Some(metric::Data::Gauge(Gauge { data_points })),
+        ..Metric::default()
+    };
+
+    let mut metrics = Vec::new();
+    if result.method.download {
+        metrics.push(gauge(
+            "pingtest.download.throughput",
+            "Download throughput",
+            "bit/s",
+            vec![point(result.download_speed * 1e6, Vec::new())],
+        ));
+    }
+    if result.method.upload {
+        metrics.push(gauge(
+            "pingtest.upload.throughput",
+            "Upload throughput",
+            "bit/s",
+            vec![point(result.upload_speed * 1e6, Vec::new())],
+        ));
+    }
+    let score = get_quality_score(result.download_speed, result.upload_speed, result.ping);
+    metrics.extend([
+        gauge(
+            "pingtest.latency",
+            "Mean round-trip latency",
+            "s",
+            vec![point(result.ping / 1000.0, Vec::new())],
+        ),
+        gauge(
+            "pingtest.jitter",
+            "Mean difference between consecutive round-trip times",
+            "s",
+            vec![point(result.jitter / 1000.0, Vec::new())],
+        ),
+        gauge(
+            "pingtest.packet_loss",
+            "Share of latency probes lost",
+            "1",
+            vec![point(result.packet_loss / 100.0, Vec::new())],
+        ),
+        gauge(
+            "pingtest.quality_score",
+            "Network quality score from 0 to 100",
+            "1",
+            vec![point(f64::from(score), Vec::new())],
+        ),
+        gauge(
+            "pingtest.duration",
+            "Wall-clock time of the whole test",
+            "s",
+            vec![point(result.test_duration, Vec::new())],
+        ),
+    ]);
+    if !phases.is_empty() {
+        let points = phases
+            .iter()
+            .map(|span| {
+                let seconds = (span.end - span.start).as_seconds_f64();
+                point(
+                    seconds,
+                    vec![string_attribute("pingtest.phase", span.phase.as_str())],
+                )
+            })
+            .collect();
+        metrics.push(gauge(
+            "pingtest.phase.duration",
+            "Time spent in each test phase",
+            "s",
+            points,
+        ));
+    }
+
+    ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            resource: Some(resource.clone()),
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(scope()),
+                metrics,
+                ..ScopeMetrics::default()
+            }],
+            ..ResourceMetrics::default()
+        }],
+    }
+}
+
+/// A `speedtest` root span covering the test with one child span per phase.
+pub fn trace_request(
+    resource: &Resource,
+    result: &TestResult,
+    phases: &[PhaseSpan],
+    trace_id: [u8; 16],
+) -> ExportTraceServiceRequest {
+    let root_id: [u8; 8] = rand::random();
+    let (start, end) = match (phases.first(), phases.last()) {
+        (Some(first), Some(last)) => (first.start, last.end),
+        _ => {
+            let elapsed = chrono::Duration::milliseconds((result.test_duration * 1000.0) as i64);
+            (result.timestamp - elapsed, result.timestamp)
+        }
+    };
+
+    let mut attributes = server_attributes(result);
+    if result.method.download {
+        attributes.push(double_attribute(
+            "pingtest.download.throughput",
+            result.download_speed * 1e6,
+        ));
+    }
+    if result.method.upload {
+        attributes.push(double_attribute(
+            "pingtest.upload.throughput",
+            result.upload_speed * 1e6,
+        ));
+    }
+    attributes.push(double_attribute("pingtest.latency", result.ping / 1000.0));
+
+    let mut spans = vec![Span {
+        trace_id: trace_id.to_vec(),
+        span_id: root_id.to_vec(),
+        name: "speedtest".to_string(),
+        kind: span::SpanKind::Internal as i32,
+        start_time_unix_nano: unix_nanos(start),
+        end_time_unix_nano: unix_nanos(end),
+        attributes,
+        ..Span::default()
+    }];
+    spans.extend(phases.iter().map(|phase| Span {
+        trace_id: trace_id.to_vec(),
+        span_id: rand::random::<[u8; 8]>().to_vec(),
+        parent_span_id: root_id.to_vec(),
+        name: phase.phase.as_str().to_string(),
+        kind: span::SpanKind::Client as i32,
+        start_time_unix_nano: unix_nanos(phase.start),
+        end_time_unix_nano: unix_nanos(phase.end),
+        attributes: vec![string_attribute("server.address", &result.server_host)],
+        ..Span::default()
+    }));
+
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            resource: Some(resource.clone()),
+            scope_spans: vec![ScopeSpans {
+                scope: Some(scope()),
+                spans,
+                ..ScopeSpans::default()
+            }],
+            ..ResourceSpans::default()
+        }],
+    }
+}
+
+fn unix_nanos(time: DateTime<Utc>) -> u64 {
+    time.timestamp_nanos_opt().unwrap_or_default().max(0) as u64
+}
+
+fn attribute(key: &str, value: any_value::Value) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue { value: Some(value) }),
+    }
+}
+
+fn string_attribute(key: &str, value: &str) -> KeyValue {
+    attribute(key, any_value::Value::StringValue(value.to_string()))
+}
+
+fn int_attribute(key: &str, value: i64) -> KeyValue {
+    attribute(key, any_value::Value::IntValue(value))
+}
+
+fn double_attribute(key: &str, value: f64) -> KeyValue {
+    attribute(key, any_value::Value::DoubleValue(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Phase, Progress, Recording};
+    use axum::body::Bytes;
+    use axum::extract::{Path, State};
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::{Arc, Mutex};
+
+    fn result() -> TestResult {
+        TestResult {
+            timestamp: "2026-01-05T14:00:32Z".parse().unwrap(),
+            test_duration: 32.0,
+            ..TestResult::sample()
+        }
+    }
+
+    fn phases() -> Vec<PhaseSpan> {
+        let mut recording = Recording::new();
+        let at = |s: &str| format!("2026-01-05T14:00:{}Z", s).parse().unwrap();
+        for (progress, time) in [
+            (Progress::PhaseStarted(Phase::Dns), "00.000"),
+            (Progress::PhaseFinished(Phase::Dns), "00.010"),
+            (Progress::PhaseStarted(Phase::Download), "00.040"),
+            (Progress::PhaseFinished(Phase::Download), "15.040"),
+        ] {
+            recording.record(&progress, at(time));
+        }
+        recording.phases
+    }
+
+    fn string_value(attributes: &[KeyValue], key: &str) -> Option<String> {
+        attributes.iter().find(|kv| kv.key == key).and_then(|kv| {
+            match kv.value.as_ref()?.value.as_ref()? {
+                any_value::Value::StringValue(s) => Some(s.clone()),
+                _ => None,
+            }
+        })
+    }
+
+    #[test]
+    fn records_phase_durations_as_gauge_points() {
+        let request = metrics_request(&resource(Some("runner-1"), None), &result(), &phases());
+        let metrics = &request.resource_metrics[0].scope_metrics[0].metrics;
+        let phase_metric = metrics
+            .iter()
+            .find(|m| m.name == "pingtest.phase.duration")
+            .unwrap();
+        let Some(metric::Data::Gauge(gauge)) = &phase_metric.data else {
+            panic!("phase duration is not a gauge");
+        };
+        let durations: Vec<(String, f64)> = gauge
+            .data_points
+            .iter()
+            .map(|p| {
+                let Some(number_data_point::Value::AsDouble(v)) = p.value else {
+                    panic!("not a double");
+                };
+                (string_value(&p.attributes, "pingtest.phase").unwrap(), v)
+            })
+            .collect();
+        assert_eq!(
+            durations,
+            [("dns".to_string(), 0.01), ("download".to_string(), 15.0)]
+        );
+    }
+
+    #[derive(Default)]
+    struct Collector {
+        failures_left: Mutex<u32>,
+        requests: Mutex<Vec<(String, HeaderMap, Bytes)>>,
+    }
+
+    async fn receive(
+        State(collector): State<Arc<Collector>>,
+        Path(signal): Path<String>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        collector
+            .requests
+            .lock()
+            .unwrap()
+            .push((signal, headers, body));
+        let mut failures = collector.failures_left.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    async fn start(collector: Arc<Collector>) -> String {
+        let app = Router::new()
+            .route("/v1/{signal}", post(receive))
+            .with_state(collector);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn exports_metrics_and_traces_over_http() {
+        let collector = Arc::new(Collector::default());
+        let endpoint = start(Arc::clone(&collector)).await;
+
+        let headers = vec![parse_header("x-api-key=secret").unwrap()];
+        OtlpExporter::new(&endpoint, headers, true, Some("ci"))
+            .export(&result(), &phases())
+            .await
+            .unwrap();
+
+        let requests = collector.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let (signal, headers, body) = &requests[0];
+        assert_eq!(signal, "metrics");
+        assert_eq!(headers["content-type"], "application/x-protobuf");
+        assert_eq!(headers["x-api-key"], "secret");
+        let metrics = ExportMetricsServiceRequest::decode(body.clone()).unwrap();
+        let resource = metrics.resource_metrics[0].resource.as_ref().unwrap();
+        assert_eq!(
+            string_value(&resource.attributes, "pingtest.tag").as_deref(),
+            Some("ci")
+        );
+        assert!(string_value(&resource.attributes, "host.name").is_some());
+
+        let (signal, _, body) = &requests[1];
+        assert_eq!(signal, "traces");
+        let traces = ExportTraceServiceRequest::decode(body.clone()).unwrap();
+        let spans = &traces.resource_spans[0].scope_spans[0].spans;
+        let names: Vec<&str> = spans.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["speedtest", "dns", "download"]);
+        assert!(spans[1..]
+            .iter()
+            .all(|s| s.parent_span_id == spans[0].span_id && s.trace_id == spans[0].trace_id));
+    }
+
+    #[tokio::test]
+    async fn export_retries_server_errors() {
+        let collector = Arc::new(Collector {
+            failures_left: Mutex::new(2),
+            ..Collector::default()
+        });
+        let endpoint = start(Arc::clone(&collector)).await;
+
+        let mut exporter = OtlpExporter::new(&endpoint, Vec::new(), false, None);
+        exporter.backoff = Duration::from_millis(1);
+        exporter.export(&result(), &phases()).await.unwrap();
+
+        let requests = collector.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|(signal, _, _)| signal == "metrics"));
+    }
+}
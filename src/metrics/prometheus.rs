@@ -0,0 +1,305 @@
+//! Prometheus text exposition format.
+//!
+//! Gauges hold the latest run for each server/tag pair; histograms
+//! accumulate every run observed since the process started. Values use
+//! Prometheus base units: bits per second, seconds and ratios.
+
+use crate::network::TestResult;
+use crate::thresholds::Check;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const BPS_BUCKETS: &[f64] = &[
+    1e6, 5e6, 10e6, 25e6, 50e6, 100e6, 250e6, 500e6, 1e9, 2.5e9, 10e9,
+];
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.02, 0.03, 0.05, 0.075, 0.1, 0.2, 0.5, 1.0];
+const JITTER_BUCKETS: &[f64] = &[0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1];
+const LOSS_BUCKETS: &[f64] = &[0.0, 0.01, 0.02, 0.05, 0.1, 0.25, 0.5, 1.0];
+const SCORE_BUCKETS: &[f64] = &[20.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 100.0];
+
+/// One metric family: its name, help text, histogram buckets, and how to
+/// read the value from a result, `None` when its phase did not run.
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    buckets: &'static [f64],
+    value: fn(&TestResult) -> Option<f64>,
+}
+
+const FAMILIES: &[Family] = &[
+    Family {
+        name: "pingtest_download_bits_per_second",
+        help: "Download throughput",
+        buckets: BPS_BUCKETS,
+        value: |r| Check::Download.measure(r).map(|mbps| mbps * 1e6),
+    },
+    Family {
+        name: "pingtest_upload_bits_per_second",
+        help: "Upload throughput",
+        buckets: BPS_BUCKETS,
+        value: |r| Check::Upload.measure(r).map(|mbps| mbps * 1e6),
+    },
+    Family {
+        name: "pingtest_latency_seconds",
+        help: "Mean round-trip latency",
+        buckets: LATENCY_BUCKETS,
+        value: |r| Check::Latency.measure(r).map(|ms| ms / 1000.0),
+    },
+    Family {
+        name: "pingtest_jitter_seconds",
+        help: "Mean difference between consecutive round-trip times",
+        buckets: JITTER_BUCKETS,
+        value: |r| Check::Jitter.measure(r).map(|ms| ms / 1000.0),
+    },
+    Family {
+        name: "pingtest_packet_loss_ratio",
+        help: "Share of latency probes lost",
+        buckets: LOSS_BUCKETS,
+        value: |r| Check::Loss.measure(r).map(|percent| percent / 100.0),
+    },
+    Family {
+        name: "pingtest_quality_score",
+        help: "Network quality score from 0 to 100",
+        buckets: SCORE_BUCKETS,
+        value: |r| Check::Score.measure(r),
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Labels {
+    server: String,
+    tag: String,
+}
+
+impl Labels {
+    fn render(&self) -> String {
+        format!(
+            "server=\"{}\",tag=\"{}\"",
+            escape_label(&self.server),
+            escape_label(&self.tag)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(buckets: &[f64]) -> Self {
+        Self {
+            counts: vec![0; buckets.len()],
+            ..Self::default()
+        }
+    }
+
+    fn observe(&mut self, buckets: &[f64], value: f64) {
+        for (count, bound) in self.counts.iter_mut().zip(buckets) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Series {
+    last: TestResult,
+    /// One histogram per entry in [`FAMILIES`].
+    histograms: Vec<Histogram>,
+}
+
+/// Collected results, rendered on demand.
+#[derive(Debug, Clone, Default)]
+pub struct PrometheusMetrics {
+    series: BTreeMap<Labels, Series>,
+    runs_failed: u64,
+    last_run_ok: Option<bool>,
+}
+
+impl PrometheusMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, result: &TestResult, tag: Option<&str>) {
+        let labels = Labels {
+            server: result.server_name.clone(),
+            tag: tag.unwrap_or_default().to_string(),
+        };
+        let series = self.series.entry(labels).or_insert_with(|| Series {
+            last: result.clone(),
+            histograms: FAMILIES.iter().map(|f| Histogram::new(f.buckets)).collect(),
+        });
+        series.last = result.clone();
+        for (family, histogram) in FAMILIES.iter().zip(&mut series.histograms) {
+            if let Some(value) = (family.value)(result) {
+                histogram.observe(family.buckets, value);
+            }
+        }
+        self.last_run_ok = Some(true);
+    }
+
+    pub fn observe_failure(&mut self) {
+        self.runs_failed += 1;
+        self.last_run_ok = Some(false);
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP pingtest_up Whether the most recent test succeeded\n");
+        out.push_str("# TYPE pingtest_up gauge\n");
+        let up = self.last_run_ok.map_or(0, u8::from);
+        let _ = writeln!(out, "pingtest_up {}", up);
+
+        out.push_str("# HELP pingtest_runs_failed_total Tests that ended in an error\n");
+        out.push_str("# TYPE pingtest_runs_failed_total counter\n");
+        let _ = writeln!(out, "pingtest_runs_failed_total {}", self.runs_failed);
+
+        out.push_str("# HELP pingtest_last_run_timestamp_seconds When the latest test finished\n");
+        out.push_str("# TYPE pingtest_last_run_timestamp_seconds gauge\n");
+        for (labels, series) in &self.series {
+            let _ = writeln!(
+                out,
+                "pingtest_last_run_timestamp_seconds{{{}}} {}",
+                labels.render(),
+                series.last.timestamp.timestamp()
+            );
+        }
+
+        for (i, family) in FAMILIES.iter().enumerate() {
+            let _ = writeln!(out, "# HELP {} {} (latest run)", family.name, family.help);
+            let _ = writeln!(out, "# TYPE {} gauge", family.name);
+            // A phase the latest run skipped has no sample.
+            for (labels, series) in &self.series {
+                if let Some(value) = (family.value)(&series.last) {
+                    let _ = writeln!(out, "{}{{{}}} {}", family.name, labels.render(), value);
+                }
+            }
+
+            let histogram_name = family.name.replacen("pingtest_", "pingtest_runs_", 1);
+            let _ = writeln!(out, "# HELP {} {} (all runs)", histogram_name, family.help);
+            let _ = writeln!(out, "# TYPE {} histogram", histogram_name);
+            for (labels, series) in &self.series {
+                let histogram = &series.histograms[i];
+                let labels = labels.render();
+                for (bound, count) in family.buckets.iter().zip(&histogram.counts) {
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{{{},le=\"{}\"}} {}",
+                        histogram_name, labels, bound, count
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{{},le=\"+Inf\"}} {}",
+                    histogram_name, labels, histogram.count
+                );
+                let _ = writeln!(
+                    out,
+                    "{}_sum{{{}}} {}",
+                    histogram_name, labels, histogram.sum
+                );
+                let _ = writeln!(
+                    out,
+                    "{}_count{{{}}} {}",
+                    histogram_name, labels, histogram.count
+                );
+            }
+        }
+
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::speedtest::TestMethod;
+
+    fn result(download: f64) -> TestResult {
+        TestResult {
+            server_name: "PingTest \"FRA\"".to_string(),
+            download_speed: download,
+            packet_loss: 10.0,
+            ..TestResult::sample()
+        }
+    }
+
+    #[test]
+    fn renders_gauges_and_histograms() {
+        let mut metrics = PrometheusMetrics::new();
+        metrics.observe(&result(40.0), Some("office"));
+        metrics.observe(&result(94.2), Some("office"));
+        let text = metrics.render();
+
+        let labels = r#"server="PingTest \"FRA\"",tag="office""#;
+        assert!(text.contains("pingtest_up 1\n"));
+        assert!(text.contains(&format!(
+            "pingtest_download_bits_per_second{{{}}} 94200000\n",
+            labels
+        )));
+        assert!(text.contains(&format!("pingtest_packet_loss_ratio{{{}}} 0.1\n", labels)));
+        assert!(text.contains(&format!(
+            "pingtest_runs_download_bits_per_second_bucket{{{},le=\"50000000\"}} 1\n",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "pingtest_runs_download_bits_per_second_bucket{{{},le=\"+Inf\"}} 2\n",
+            labels
+        )));
+        assert!(text.contains("# TYPE pingtest_runs_latency_seconds histogram\n"));
+    }
+
+    #[test]
+    fn skipped_phases_have_no_samples() {
+        let mut metrics = PrometheusMetrics::new();
+        metrics.observe(
+            &TestResult {
+                upload_speed: 0.0,
+                method: TestMethod {
+                    upload: false,
+                    ..TestResult::sample().method
+                },
+                ..result(94.2)
+            },
+            None,
+        );
+        let text = metrics.render();
+
+        let labels = r#"server="PingTest \"FRA\"",tag="""#;
+        assert!(text.contains("pingtest_download_bits_per_second{"));
+        assert!(!text.contains("pingtest_upload_bits_per_second{"));
+        assert!(text.contains(&format!(
+            "pingtest_runs_upload_bits_per_second_bucket{{{},le=\"1000000\"}} 0\n",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "pingtest_runs_upload_bits_per_second_count{{{}}} 0\n",
+            labels
+        )));
+    }
+
+    #[test]
+    fn failure_marks_down() {
+        let mut metrics = PrometheusMetrics::new();
+        metrics.observe(&result(40.0), None);
+        metrics.observe_failure();
+        let text = metrics.render();
+        assert!(text.contains("pingtest_up 0\n"));
+        assert!(text.contains("pingtest_runs_failed_total 1\n"));
+    }
+}
@@ -0,0 +1,9 @@
+//! Speed test engine and server selection.
+
+pub mod recording;
+pub mod speedtest;
+
+pub use recording::{PhaseSpan, Recording, Sample};
+pub use speedtest::{
+    Convergence, Phase, Progress, Server, SpeedTest, SteadyWindow, TestResult, TestSettings,
+};
@@ -0,0 +1,67 @@
+//! Progress events collected with their arrival times.
+
+use super::{Phase, Progress};
+use chrono::{DateTime, Utc};
+
+/// A throughput sample recorded while the test ran.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub timestamp: DateTime<Utc>,
+    pub phase: Phase,
+    pub interval: u64,
+    pub bytes: u64,
+    pub mbps: f64,
+}
+
+/// When one phase of a test started and finished.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseSpan {
+    pub phase: Phase,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Everything a test reported through its progress callback.
+#[derive(Debug, Default)]
+pub struct Recording {
+    pub samples: Vec<Sample>,
+    /// Round-trip time of each latency probe in milliseconds, `None` if lost.
+    pub probes: Vec<Option<f64>>,
+    pub phases: Vec<PhaseSpan>,
+    started: Vec<(Phase, DateTime<Utc>)>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, progress: &Progress, at: DateTime<Utc>) {
+        match *progress {
+            Progress::PhaseStarted(phase) => self.started.push((phase, at)),
+            Progress::PhaseFinished(phase) => {
+                if let Some(i) = self.started.iter().position(|(p, _)| *p == phase) {
+                    let (_, start) = self.started.remove(i);
+                    self.phases.push(PhaseSpan {
+                        phase,
+                        start,
+                        end: at,
+                    });
+                }
+            }
+            Progress::Sample {
+                phase,
+                interval,
+                bytes,
+                mbps,
+            } => self.samples.push(Sample {
+                timestamp: at,
+                phase,
+                interval,
+                bytes,
+                mbps,
+            }),
+            Progress::LatencyProbe { rtt_ms, .. } => self.probes.push(rtt_ms),
+        }
+    }
+}
@@ -0,0 +1,744 @@
+use crate::ping::PingAnalyzer;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+/// A speed test server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Server {
+    pub id: u32,
+    pub name: String,
+    pub location: String,
+    pub country: String,
+    pub host: String,
+    pub distance_km: f64,
+}
+
+impl Server {
+    fn new(
+        id: u32,
+        name: &str,
+        location: &str,
+        country: &str,
+        host: &str,
+        distance_km: f64,
+    ) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            location: location.to_string(),
+            country: country.to_string(),
+            host: host.to_string(),
+            distance_km,
+        }
+    }
+}
+
+/// Which server a test runs against and how latency is probed, from the
+/// config file's `[servers]` and `[ping]` sections.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestSettings {
+    /// ISO 3166 country codes, e.g. `US`. The nearest server in one of them
+    /// is used when there is one.
+    pub preferred_countries: Vec<String>,
+    /// Servers farther away are never used.
+    pub max_distance_km: Option<u32>,
+    /// Latency probes sent per test.
+    pub ping_count: u32,
+    /// A probe answered later than this counts as lost.
+    pub ping_timeout: Duration,
+}
+
+impl Default for TestSettings {
+    fn default() -> Self {
+        Self {
+            preferred_countries: Vec::new(),
+            max_distance_km: None,
+            ping_count: 10,
+            ping_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+/// The phases of a speed test, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// Resolving the server's host name.
+    Dns,
+    /// Opening the test connections.
+    Connect,
+    Download,
+    Upload,
+    Ping,
+}
+
+impl Phase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Dns => "dns",
+            Phase::Connect => "connect",
+            Phase::Download => "download",
+            Phase::Upload => "upload",
+            Phase::Ping => "ping",
+        }
+    }
+}
+
+/// Progress reported while a test is running.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progress {
+    PhaseStarted(Phase),
+    /// Throughput measured over one interval of a throughput phase.
+    Sample {
+        phase: Phase,
+        /// 1-based interval number within the phase.
+        interval: u64,
+        /// Bytes transferred during the interval.
+        bytes: u64,
+        mbps: f64,
+    },
+    /// A latency probe's round-trip time in milliseconds, or `None` if lost.
+    LatencyProbe {
+        seq: u32,
+        rtt_ms: Option<f64>,
+    },
+    PhaseFinished(Phase),
+}
+
+/// How a test was configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestMethod {
+    /// Length of each throughput phase in seconds.
+    pub duration: u64,
+    pub connections: u32,
+    pub download: bool,
+    pub upload: bool,
+}
+
+/// The outcome of a single speed test run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestResult {
+    pub timestamp: DateTime<Utc>,
+    pub server_id: u32,
+    pub server_name: String,
+    pub server_location: String,
+    pub server_host: String,
+    /// Download throughput in Mbps.
+    pub download_speed: f64,
+    /// Upload throughput in Mbps.
+    pub upload_speed: f64,
+    /// Round-trip latency in milliseconds.
+    pub ping: f64,
+    /// Mean difference between consecutive round-trip times in milliseconds.
+    pub jitter: f64,
+    /// Share of latency probes that were lost, in percent.
+    pub packet_loss: f64,
+    /// Wall-clock duration of the whole test in seconds.
+    pub test_duration: f64,
+    pub method: TestMethod,
+    /// The part of the download phase `download_speed` is averaged over.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_window: Option<SteadyWindow>,
+    /// The part of the upload phase `upload_speed` is averaged over.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_window: Option<SteadyWindow>,
+    /// Bytes received during the download phase.
+    #[serde(default)]
+    pub download_bytes: u64,
+    /// Bytes sent during the upload phase.
+    #[serde(default)]
+    pub upload_bytes: u64,
+    /// The test was stopped early. Phases it never reached count as skipped
+    /// in `method`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+}
+
+impl TestResult {
+    /// A complete run against the Frankfurt server, for tests to override.
+    #[cfg(test)]
+    pub(crate) fn sample() -> Self {
+        Self {
+            timestamp: "2026-01-05T14:00:00Z".parse().unwrap(),
+            server_id: 12345,
+            server_name: "PingTest Frankfurt".to_string(),
+            server_location: "Frankfurt, DE".to_string(),
+            server_host: "fra.pingtest.net".to_string(),
+            download_speed: 94.2,
+            upload_speed: 21.0,
+            ping: 12.0,
+            jitter: 1.5,
+            packet_loss: 0.0,
+            test_duration: 32.1,
+            method: TestMethod {
+                duration: 15,
+                connections: 4,
+                download: true,
+                upload: true,
+            },
+            download_window: None,
+            upload_window: None,
+            download_bytes: 0,
+            upload_bytes: 0,
+            partial: false,
+        }
+    }
+}
+
+/// The seconds of a throughput phase after the TCP slow-start ramp, which
+/// the reported throughput is averaged over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SteadyWindow {
+    /// Offset of the window's start from the start of the phase.
+    pub start_s: u64,
+    /// Offset of the window's end; also the length of the phase.
+    pub end_s: u64,
+    /// Whether the phase ended early because the throughput converged.
+    pub converged: bool,
+}
+
+impl fmt::Display for SteadyWindow {
+    /// E.g. `2–9 s of 9 s, converged`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}–{} s of {} s", self.start_s, self.end_s, self.end_s)?;
+        if self.converged {
+            f.write_str(", converged")?;
+        }
+        Ok(())
+    }
+}
+
+/// A sample more than this much above the one before it is still part of
+/// the slow-start ramp.
+const RAMP_GROWTH: f64 = 0.1;
+
+/// When a throughput phase may end before its full duration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Convergence {
+    /// Number of consecutive one-second estimates compared.
+    pub window: usize,
+    /// Largest deviation of those estimates from the latest one, as a
+    /// fraction of it.
+    pub tolerance: f64,
+}
+
+impl Convergence {
+    /// Whether the last `window` estimates agree with the latest within
+    /// `tolerance`.
+    pub fn is_reached(&self, estimates: &[f64]) -> bool {
+        let Some(&latest) = estimates.last() else {
+            return false;
+        };
+        if self.window == 0 || estimates.len() < self.window || latest <= 0.0 {
+            return false;
+        }
+        estimates[estimates.len() - self.window..]
+            .iter()
+            .all(|e| (e - latest).abs() / latest <= self.tolerance)
+    }
+}
+
+/// Follows the samples of one throughput phase: where slow start ends, and
+/// the running steady-state throughput estimate.
+#[derive(Debug, Default)]
+struct PhaseMeter {
+    samples: Vec<f64>,
+    /// Index of the first steady-state sample, once the ramp has ended.
+    steady_from: Option<usize>,
+    /// The estimate after each sample since the ramp ended.
+    estimates: Vec<f64>,
+}
+
+impl PhaseMeter {
+    fn push(&mut self, mbps: f64) {
+        if self.steady_from.is_none() {
+            if let Some(&previous) = self.samples.last() {
+                if mbps <= previous * (1.0 + RAMP_GROWTH) {
+                    self.steady_from = Some(self.samples.len() - 1);
+                }
+            }
+        }
+        self.samples.push(mbps);
+        if self.steady_from.is_some() {
+            self.estimates.push(self.estimate());
+        }
+    }
+
+    /// The mean of the steady-state samples, or the latest sample while the
+    /// ramp is still going.
+    fn estimate(&self) -> f64 {
+        match self.steady_from {
+            Some(from) => {
+                let steady = &self.samples[from..];
+                steady.iter().sum::<f64>() / steady.len() as f64
+            }
+            None => self.samples.last().copied().unwrap_or_default(),
+        }
+    }
+
+    fn window(&self, converged: bool) -> SteadyWindow {
+        let end_s = self.samples.len() as u64;
+        SteadyWindow {
+            start_s: self
+                .steady_from
+                .map_or(end_s.saturating_sub(1), |from| from as u64),
+            end_s,
+            converged,
+        }
+    }
+}
+
+pub struct SpeedTest {
+    servers: Vec<Server>,
+    settings: TestSettings,
+    convergence: Option<Convergence>,
+    max_bytes: Option<u64>,
+    rate_limit_mbps: Option<f64>,
+    stop: Option<watch::Receiver<bool>>,
+}
+
+/// What one throughput phase measured.
+struct PhaseOutcome {
+    mbps: f64,
+    window: SteadyWindow,
+    bytes: u64,
+    /// A stop was requested during the phase.
+    stopped: bool,
+}
+
+impl SpeedTest {
+    pub fn new() -> Self {
+        Self {
+            servers: vec![
+                Server::new(
+                    12345,
+                    "PingTest Frankfurt",
+                    "Frankfurt, DE",
+                    "DE",
+                    "fra.pingtest.net",
+                    420.0,
+                ),
+                Server::new(
+                    23456,
+                    "PingTest Amsterdam",
+                    "Amsterdam, NL",
+                    "NL",
+                    "ams.pingtest.net",
+                    650.0,
+                ),
+                Server::new(
+                    34567,
+                    "PingTest London",
+                    "London, GB",
+                    "GB",
+                    "lon.pingtest.net",
+                    930.0,
+                ),
+                Server::new(
+                    45678,
+                    "PingTest New York",
+                    "New York, US",
+                    "US",
+                    "nyc.pingtest.net",
+                    6200.0,
+                ),
+                Server::new(
+                    56789,
+                    "PingTest Toronto",
+                    "Toronto, CA",
+                    "CA",
+                    "tor.pingtest.net",
+                    6400.0,
+                ),
+            ],
+            settings: TestSettings::default(),
+            convergence: None,
+            max_bytes: None,
+            rate_limit_mbps: None,
+            stop: None,
+        }
+    }
+
+    /// Picks the server and probes latency as `settings` say.
+    pub fn with_settings(mut self, settings: TestSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Ends each throughput phase as soon as `convergence` is reached; the
+    /// test duration becomes the maximum.
+    pub fn with_convergence(mut self, convergence: Convergence) -> Self {
+        self.convergence = Some(convergence);
+        self
+    }
+
+    /// Ends each throughput phase once it has moved `max_bytes`.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Caps sending and receiving at `mbps`.
+    pub fn with_rate_limit(mut self, mbps: f64) -> Self {
+        self.rate_limit_mbps = Some(mbps);
+        self
+    }
+
+    /// Ends the test early once `stop` turns true: the current phase keeps
+    /// the samples measured so far and later throughput phases are skipped.
+    /// The latency phase still runs, unless the stop came during it.
+    pub fn with_stop(mut self, stop: watch::Receiver<bool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    fn stop_requested(&self) -> bool {
+        self.stop.as_ref().is_some_and(|stop| *stop.borrow())
+    }
+
+    /// Completes when a stop is requested; never without [`SpeedTest::with_stop`].
+    async fn stopped(&self) {
+        if let Some(stop) = &self.stop {
+            if stop.clone().wait_for(|&stop| stop).await.is_ok() {
+                return;
+            }
+        }
+        std::future::pending().await
+    }
+
+    /// Measures one throughput phase of up to `duration` seconds.
+    async fn throughput_phase(
+        &self,
+        phase: Phase,
+        duration: u64,
+        simulate: impl Fn(u64) -> f64,
+        progress: &mut impl FnMut(Progress),
+    ) -> PhaseOutcome {
+        progress(Progress::PhaseStarted(phase));
+        let mut meter = PhaseMeter::default();
+        let mut total_bytes = 0;
+        let mut converged = false;
+        let mut stopped = false;
+        for i in 0..duration {
+            let mbps = self
+                .rate_limit_mbps
+                .map_or(simulate(i), |limit| simulate(i).min(limit));
+            let mut bytes = megabits_to_bytes(mbps);
+            let mut elapsed = Duration::from_secs(1);
+            // The last interval stops as soon as the budget is used up.
+            let budget_left = self.max_bytes.map(|max| max.saturating_sub(total_bytes));
+            let exhausted = budget_left.is_some_and(|left| bytes >= left);
+            if let Some(left) = budget_left.filter(|_| exhausted) {
+                elapsed = elapsed.mul_f64(left as f64 / bytes.max(1) as f64);
+                bytes = left;
+            }
+            // An interval cut short by a stop is not counted.
+            tokio::select! {
+                () = sleep(elapsed) => {}
+                () = self.stopped() => {
+                    stopped = true;
+                    break;
+                }
+            }
+            total_bytes += bytes;
+            progress(Progress::Sample {
+                phase,
+                interval: i + 1,
+                bytes,
+                mbps,
+            });
+            meter.push(mbps);
+            if exhausted {
+                break;
+            }
+            if self
+                .convergence
+                .is_some_and(|c| c.is_reached(&meter.estimates))
+            {
+                converged = true;
+                break;
+            }
+        }
+        progress(Progress::PhaseFinished(phase));
+        PhaseOutcome {
+            mbps: meter.estimate(),
+            window: meter.window(converged),
+            bytes: total_bytes,
+            stopped,
+        }
+    }
+
+    /// Every known server, in no particular order.
+    pub async fn get_servers(&self) -> Result<Vec<Server>> {
+        Ok(self.servers.clone())
+    }
+
+    /// Returns up to `count` servers ordered by distance.
+    pub async fn get_nearest_servers(&self, count: usize) -> Result<Vec<Server>> {
+        let mut servers = self.servers.clone();
+        servers.sort_by(|a, b| a.distance_km.total_cmp(&b.distance_km));
+        servers.truncate(count);
+        Ok(servers)
+    }
+
+    /// The nearest server within the maximum distance, preferring one in a
+    /// preferred country.
+    pub async fn select_server(&self) -> Result<Server> {
+        let settings = &self.settings;
+        let in_range: Vec<Server> = self
+            .get_nearest_servers(self.servers.len())
+            .await?
+            .into_iter()
+            .filter(|server| {
+                settings
+                    .max_distance_km
+                    .is_none_or(|max| server.distance_km <= f64::from(max))
+            })
+            .collect();
+        let preferred = in_range.iter().find(|server| {
+            settings
+                .preferred_countries
+                .iter()
+                .any(|code| code.eq_ignore_ascii_case(&server.country))
+        });
+        preferred
+            .or(in_range.first())
+            .cloned()
+            .ok_or_else(|| match settings.max_distance_km {
+                Some(max) => anyhow::anyhow!(
+                    "No speed test server within {} km (servers.max_distance_km)",
+                    max
+                ),
+                None => anyhow::anyhow!("No speed test servers available"),
+            })
+    }
+
+    /// Runs a test against the selected server without reporting progress.
+    pub async fn run_test(
+        &self,
+        duration: u64,
+        connections: u32,
+        no_download: bool,
+        no_upload: bool,
+    ) -> Result<TestResult> {
+        self.run_test_with_progress(duration, connections, no_download, no_upload, |_| {})
+            .await
+    }
+
+    /// Runs a test against [`SpeedTest::select_server`], reporting each phase and
+    /// per-second sample to `progress`. Throughput phases last `duration`
+    /// seconds, or less with [`SpeedTest::with_convergence`] or
+    /// [`SpeedTest::with_max_bytes`], and report the mean throughput after
+    /// slow start.
+    pub async fn run_test_with_progress(
+        &self,
+        duration: u64,
+        connections: u32,
+        no_download: bool,
+        no_upload: bool,
+        mut progress: impl FnMut(Progress),
+    ) -> Result<TestResult> {
+        let server = self.select_server().await?;
+
+        let start_time = Instant::now();
+
+        progress(Progress::PhaseStarted(Phase::Dns));
+        sleep(Duration::from_millis(5 + rand::random::<u64>() % 15)).await;
+        progress(Progress::PhaseFinished(Phase::Dns));
+
+        progress(Progress::PhaseStarted(Phase::Connect));
+        sleep(Duration::from_millis(20 + rand::random::<u64>() % 10)).await;
+        progress(Progress::PhaseFinished(Phase::Connect));
+
+        let mut download = None;
+        if !no_download {
+            download = Some(
+                self.throughput_phase(
+                    Phase::Download,
+                    duration,
+                    |i| (50.0 + (i as f64 * 2.0).sin() * 10.0) * slow_start(i),
+                    &mut progress,
+                )
+                .await,
+            );
+        }
+
+        let mut upload = None;
+        if !no_upload && !self.stop_requested() {
+            upload = Some(
+                self.throughput_phase(
+                    Phase::Upload,
+                    duration,
+                    |i| (20.0 + (i as f64 * 1.5).sin() * 5.0) * slow_start(i),
+                    &mut progress,
+                )
+                .await,
+            );
+        }
+        let mut partial = [&download, &upload]
+            .into_iter()
+            .flatten()
+            .any(|phase| phase.stopped);
+        // A phase stopped before its first sample has nothing to report.
+        let download = download.filter(|phase| phase.window.end_s > 0);
+        let upload = upload.filter(|phase| phase.window.end_s > 0);
+
+        progress(Progress::PhaseStarted(Phase::Ping));
+        let analyzer =
+            PingAnalyzer::new().with_probes(self.settings.ping_count, self.settings.ping_timeout);
+        let mut sent = 0;
+        let mut answered = Vec::new();
+        let probes = analyzer.run_probes(&server.host, |seq, rtt_ms| {
+            sent = seq;
+            answered.extend(rtt_ms);
+            progress(Progress::LatencyProbe { seq, rtt_ms })
+        });
+        let finished = tokio::select! {
+            result = probes => Some(result?),
+            () = self.stopped(), if !partial => None,
+        };
+        let ping = match finished {
+            Some(ping) => ping,
+            None => {
+                partial = true;
+                analyzer.summarize(&server.host, sent, answered)
+            }
+        };
+        progress(Progress::PhaseFinished(Phase::Ping));
+
+        Ok(TestResult {
+            timestamp: Utc::now(),
+            server_id: server.id,
+            server_name: server.name,
+            server_location: server.location,
+            server_host: server.host,
+            download_speed: download.as_ref().map_or(0.0, |phase| phase.mbps),
+            upload_speed: upload.as_ref().map_or(0.0, |phase| phase.mbps),
+            ping: ping.avg_ping,
+            jitter: ping.jitter,
+            packet_loss: ping.packet_loss,
+            test_duration: start_time.elapsed().as_secs_f64(),
+            method: TestMethod {
+                duration,
+                connections,
+                download: download.is_some(),
+                upload: upload.is_some(),
+            },
+            download_window: download.as_ref().map(|phase| phase.window),
+            upload_window: upload.as_ref().map(|phase| phase.window),
+            download_bytes: download.as_ref().map_or(0, |phase| phase.bytes),
+            upload_bytes: upload.as_ref().map_or(0, |phase| phase.bytes),
+            partial,
+        })
+    }
+}
+
+/// Share of the steady-state rate reached in second `i` of a phase, as
+/// the congestion window grows.
+fn slow_start(i: u64) -> f64 {
+    1.0 - 0.4f64.powi(i as i32 + 1)
+}
+
+/// Bytes moved in one second at `mbps`.
+fn megabits_to_bytes(mbps: f64) -> u64 {
+    (mbps * 1_000_000.0 / 8.0).round() as u64
+}
+
+impl Default for SpeedTest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excludes_slow_start_from_the_estimate() {
+        let mut meter = PhaseMeter::default();
+        for mbps in [10.0, 40.0, 80.0, 95.0] {
+            meter.push(mbps);
+        }
+        // Still ramping: the latest sample is the best estimate.
+        assert_eq!(meter.estimate(), 95.0);
+        assert!(meter.estimates.is_empty());
+
+        for mbps in [90.0, 92.0, 93.0] {
+            meter.push(mbps);
+        }
+        assert_eq!(meter.estimate(), 92.5);
+        assert_eq!(
+            meter.window(false),
+            SteadyWindow {
+                start_s: 3,
+                end_s: 7,
+                converged: false
+            }
+        );
+        assert_eq!(meter.window(true).to_string(), "3–7 s of 7 s, converged");
+    }
+
+    #[test]
+    fn converges_once_recent_estimates_agree() {
+        let convergence = Convergence {
+            window: 3,
+            tolerance: 0.05,
+        };
+
+        assert!(!convergence.is_reached(&[90.0, 91.0]));
+        assert!(!convergence.is_reached(&[60.0, 80.0, 90.0, 95.0]));
+        assert!(convergence.is_reached(&[60.0, 80.0, 90.0, 95.0, 93.0]));
+        assert!(!convergence.is_reached(&[0.0, 0.0, 0.0]));
+    }
+
+    #[tokio::test]
+    async fn a_stop_keeps_the_samples_so_far() {
+        let (stop, stopped) = watch::channel(false);
+        let speed_test = SpeedTest::new().with_stop(stopped);
+
+        let result = speed_test
+            .run_test_with_progress(10, 1, false, false, |progress| {
+                if let Progress::Sample { interval: 2, .. } = progress {
+                    let _ = stop.send(true);
+                }
+            })
+            .await
+            .unwrap();
+
+        assert!(result.partial);
+        assert!(result.method.download);
+        assert!(!result.method.upload);
+        assert_eq!(result.download_window.map(|w| w.end_s), Some(2));
+        assert!(result.download_speed > 0.0);
+    }
+
+    #[tokio::test]
+    async fn selects_the_nearest_preferred_server_in_range() {
+        let select = |countries: &[&str], max_distance_km| {
+            let speed_test = SpeedTest::new().with_settings(TestSettings {
+                preferred_countries: countries.iter().map(|c| c.to_string()).collect(),
+                max_distance_km,
+                ..TestSettings::default()
+            });
+            async move {
+                let server = speed_test.select_server().await?;
+                Ok::<_, anyhow::Error>(server.country)
+            }
+        };
+
+        assert_eq!(select(&[], None).await.unwrap(), "DE");
+        assert_eq!(select(&["us", "CA"], None).await.unwrap(), "US");
+        assert_eq!(select(&["US"], Some(1000)).await.unwrap(), "DE");
+        assert_eq!(select(&["GB", "NL"], Some(1000)).await.unwrap(), "NL");
+        assert_eq!(
+            select(&[], Some(100)).await.unwrap_err().to_string(),
+            "No speed test server within 100 km (servers.max_distance_km)"
+        );
+    }
+}
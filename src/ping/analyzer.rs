@@ -0,0 +1,161 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// Host probed by [`PingAnalyzer::run_ping_test`].
+pub const DEFAULT_TARGET: &str = "8.8.8.8";
+
+/// Summary of a series of latency probes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PingResult {
+    pub target: String,
+    /// Probes sent, including lost ones.
+    pub packet_count: u32,
+    /// Round-trip time of each answered probe in milliseconds.
+    pub ping_times: Vec<f64>,
+    pub avg_ping: f64,
+    pub min_ping: f64,
+    pub max_ping: f64,
+    /// Mean difference between consecutive round-trip times in milliseconds.
+    pub jitter: f64,
+    /// Share of probes that were lost, in percent.
+    pub packet_loss: f64,
+}
+
+/// Descriptive statistics over round-trip times.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PingStatistics {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Population standard deviation.
+    pub std_dev: f64,
+    pub jitter: f64,
+}
+
+pub struct PingAnalyzer {
+    packet_count: u32,
+    interval: Duration,
+    timeout: Duration,
+}
+
+impl PingAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            packet_count: 10,
+            interval: Duration::from_millis(200),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// Sends `count` probes per test, counting any answer slower than
+    /// `timeout` as lost.
+    pub fn with_probes(mut self, count: u32, timeout: Duration) -> Self {
+        self.packet_count = count;
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends the configured number of probes to [`DEFAULT_TARGET`].
+    pub async fn run_ping_test(&self) -> Result<PingResult> {
+        self.run_probes(DEFAULT_TARGET, |_, _| {}).await
+    }
+
+    /// Sends `count` probes to `target`, counting any answer slower than
+    /// `timeout` as lost.
+    pub async fn run_ping_test_with_params(
+        &self,
+        target: &str,
+        count: u32,
+        timeout: Duration,
+    ) -> Result<PingResult> {
+        let analyzer = Self {
+            packet_count: count,
+            timeout,
+            ..*self
+        };
+        analyzer.run_probes(target, |_, _| {}).await
+    }
+
+    /// Sends the configured number of probes to `target`, reporting each
+    /// probe's sequence number and round-trip time (`None` if lost).
+    pub async fn run_probes(
+        &self,
+        target: &str,
+        mut on_probe: impl FnMut(u32, Option<f64>),
+    ) -> Result<PingResult> {
+        let mut ping_times = Vec::with_capacity(self.packet_count as usize);
+        for seq in 1..=self.packet_count {
+            sleep(self.interval).await;
+            let rtt = (rand::random::<f64>() >= 0.01)
+                .then(|| 25.0 + (rand::random::<f64>() - 0.5) * 10.0)
+                .filter(|rtt| *rtt <= self.timeout.as_secs_f64() * 1000.0);
+            on_probe(seq, rtt);
+            ping_times.extend(rtt);
+        }
+
+        Ok(self.summarize(target, self.packet_count, ping_times))
+    }
+
+    /// Summarizes `sent` probes to `target`, of which `ping_times` were
+    /// answered. Also used for a run that was stopped early.
+    pub fn summarize(&self, target: &str, sent: u32, ping_times: Vec<f64>) -> PingResult {
+        let stats = self.calculate_statistics(&ping_times);
+        let lost = (sent as usize).saturating_sub(ping_times.len());
+        PingResult {
+            target: target.to_string(),
+            packet_count: sent,
+            avg_ping: stats.mean,
+            min_ping: stats.min,
+            max_ping: stats.max,
+            jitter: stats.jitter,
+            packet_loss: lost as f64 / sent.max(1) as f64 * 100.0,
+            ping_times,
+        }
+    }
+
+    pub fn calculate_statistics(&self, ping_times: &[f64]) -> PingStatistics {
+        if ping_times.is_empty() {
+            return PingStatistics::default();
+        }
+
+        let count = ping_times.len();
+        let mean = ping_times.iter().sum::<f64>() / count as f64;
+        let mut sorted = ping_times.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let median = if count.is_multiple_of(2) {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+        } else {
+            sorted[count / 2]
+        };
+        let variance = ping_times.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / count as f64;
+        let jitter = if count > 1 {
+            ping_times
+                .windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .sum::<f64>()
+                / (count - 1) as f64
+        } else {
+            0.0
+        };
+
+        PingStatistics {
+            count,
+            mean,
+            median,
+            min: sorted[0],
+            max: sorted[count - 1],
+            std_dev: variance.sqrt(),
+            jitter,
+        }
+    }
+}
+
+impl Default for PingAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,5 @@
+//! Latency probing and statistics.
+
+pub mod analyzer;
+
+pub use analyzer::{PingAnalyzer, PingResult, PingStatistics};
@@ -0,0 +1,378 @@
+//! A single-file HTML report with inline SVG charts.
+//!
+//! The page uses no scripts, fonts or external assets, so it can be mailed or
+//! attached to a ticket and opened offline.
+
+use super::escape;
+use super::svg::{bar_chart, line_chart, Series};
+use crate::export::schema::ClientSection;
+use crate::history::anomaly::Metric;
+use crate::history::{HistoryEntry, HistoryStatistics};
+use crate::network::{Phase, Recording, TestResult};
+use crate::utils::{
+    format_bytes, get_quality_breakdown, get_quality_description, get_quality_score, hostname,
+    QualityBreakdown,
+};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use std::fmt::Write;
+
+const DOWNLOAD_COLOR: &str = "#2563eb";
+const UPLOAD_COLOR: &str = "#16a34a";
+const LATENCY_COLOR: &str = "#d97706";
+
+const STYLE: &str = "body{font-family:system-ui,-apple-system,Segoe UI,sans-serif;margin:0;background:#f9fafb;color:#111827}\
+main{max-width:760px;margin:0 auto;padding:24px}\
+h1{margin:0 0 4px}h2{margin:32px 0 8px;font-size:18px}\
+.muted{color:#6b7280}\
+.cards{display:grid;grid-template-columns:repeat(3,1fr);gap:12px;margin-top:16px}\
+.card{background:#fff;border:1px solid #e5e7eb;border-radius:8px;padding:12px}\
+.card b{display:block;font-size:22px}\
+table{border-collapse:collapse;width:100%;background:#fff}\
+td,th{border:1px solid #e5e7eb;padding:6px 10px;text-align:left}\
+svg{width:100%;height:auto;background:#fff;border:1px solid #e5e7eb;border-radius:8px}";
+
+/// What goes into a report.
+pub struct ReportInput<'a> {
+    pub result: &'a TestResult,
+    pub recording: &'a Recording,
+    pub tag: Option<&'a str>,
+    pub interface: Option<&'a str>,
+    /// Saved runs to chart, and how many days back they were selected from.
+    pub history: Option<(u32, &'a [HistoryEntry])>,
+}
+
+pub fn render(input: &ReportInput) -> String {
+    let result = input.result;
+    let score = get_quality_score(result.download_speed, result.upload_speed, result.ping);
+    let description = get_quality_description(score);
+    let local_time = result.timestamp.with_timezone(&Local);
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>PingTest Report {}</title>\n<style>{}</style>\n</head>\n<body>\n<main>\n",
+        local_time.format("%Y-%m-%d %H:%M"),
+        STYLE
+    );
+    let _ = writeln!(out, "<h1>🚀 PingTest Report</h1>");
+    if result.partial {
+        out.push_str("<p><b>Partial result:</b> the test was stopped early.</p>\n");
+    }
+    let _ = writeln!(
+        out,
+        "<div class=\"muted\">{} · {}{}</div>",
+        local_time.format("%Y-%m-%d %H:%M:%S %Z"),
+        escape(&result.server_name),
+        input
+            .tag
+            .map(|tag| format!(" · tag <b>{}</b>", escape(tag)))
+            .unwrap_or_default()
+    );
+
+    out.push_str("<div class=\"cards\">\n");
+    let mut card = |label: &str, value: String| {
+        let _ = writeln!(
+            out,
+            "<div class=\"card\"><span class=\"muted\">{}</span><b>{}</b></div>",
+            label, value
+        );
+    };
+    card(
+        "Download",
+        phase_value(result.method.download, result.download_speed, "Mbps"),
+    );
+    card(
+        "Upload",
+        phase_value(result.method.upload, result.upload_speed, "Mbps"),
+    );
+    card("Ping", format!("{:.1} ms", result.ping));
+    card("Jitter", format!("{:.1} ms", result.jitter));
+    card("Packet Loss", format!("{:.1}%", result.packet_loss));
+    card("Quality", format!("{}/100 {}", score, description));
+    let used: Vec<String> = [
+        (result.method.download, result.download_bytes, "download"),
+        (result.method.upload, result.upload_bytes, "upload"),
+    ]
+    .into_iter()
+    .filter(|(enabled, _, _)| *enabled)
+    .map(|(_, bytes, phase)| format!("{} {}", format_bytes(bytes), phase))
+    .collect();
+    if !used.is_empty() {
+        card("Data Used", used.join("<br>"));
+    }
+    out.push_str("</div>\n");
+
+    out.push_str("<h2>Throughput</h2>\n");
+    let windows: Vec<String> = [
+        ("download", result.download_window),
+        ("upload", result.upload_window),
+    ]
+    .into_iter()
+    .filter_map(|(phase, window)| Some(format!("{} {}", phase, window?)))
+    .collect();
+    if !windows.is_empty() {
+        let _ = writeln!(
+            out,
+            "<div class=\"muted\">Steady state: {}</div>",
+            windows.join(" · ")
+        );
+    }
+    out.push_str(&throughput_chart(input.recording));
+    out.push('\n');
+
+    let answered: Vec<f64> = input.recording.probes.iter().flatten().copied().collect();
+    let lost = input.recording.probes.len() - answered.len();
+    let _ = writeln!(
+        out,
+        "<h2>Latency</h2>\n<div class=\"muted\">{} probes, {} lost</div>",
+        input.recording.probes.len(),
+        lost
+    );
+    out.push_str(&bar_chart(&latency_histogram(&answered), LATENCY_COLOR));
+    out.push('\n');
+
+    let parts = get_quality_breakdown(result.download_speed, result.ping);
+    out.push_str("<h2>Quality Breakdown</h2>\n<table>\n");
+    out.push_str("<tr><th>Component</th><th>Measured</th><th>Score</th><th>Weight</th></tr>\n");
+    let _ = writeln!(
+        out,
+        "<tr><td>Download</td><td>{:.1} Mbps</td><td>{}/100</td><td>{}%</td></tr>",
+        result.download_speed,
+        parts.download_score,
+        QualityBreakdown::DOWNLOAD_WEIGHT * 100.0
+    );
+    let _ = writeln!(
+        out,
+        "<tr><td>Latency</td><td>{:.1} ms</td><td>{}/100</td><td>{}%</td></tr>",
+        result.ping,
+        parts.latency_score,
+        QualityBreakdown::LATENCY_WEIGHT * 100.0
+    );
+    let _ = writeln!(
+        out,
+        "<tr><th>Overall</th><td></td><th>{}/100</th><td>{}</td></tr>\n</table>",
+        score, description
+    );
+
+    out.push_str("<h2>Server</h2>\n");
+    table(
+        &mut out,
+        &[
+            ("Name", result.server_name.clone()),
+            ("Location", result.server_location.clone()),
+            ("Host", result.server_host.clone()),
+            ("ID", result.server_id.to_string()),
+        ],
+    );
+
+    let client = ClientSection::current();
+    let mut environment = vec![
+        ("pingtest", client.version),
+        ("OS", format!("{} ({})", client.os, client.arch)),
+    ];
+    environment.extend(hostname().map(|host| ("Host", host)));
+    environment.extend(input.interface.map(|i| ("Interface", i.to_string())));
+    environment.extend([
+        ("Connections", result.method.connections.to_string()),
+        ("Phase duration", format!("{} s", result.method.duration)),
+        ("Test duration", format!("{:.1} s", result.test_duration)),
+    ]);
+    out.push_str("<h2>Environment</h2>\n");
+    table(&mut out, &environment);
+
+    if let Some((days, entries)) = input.history {
+        history_section(&mut out, days, entries);
+    }
+
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+fn phase_value(enabled: bool, mbps: f64, unit: &str) -> String {
+    if enabled {
+        format!("{:.1} {}", mbps, unit)
+    } else {
+        "skipped".to_string()
+    }
+}
+
+fn table(out: &mut String, rows: &[(&str, String)]) {
+    out.push_str("<table>\n");
+    for (key, value) in rows {
+        let _ = writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", key, escape(value));
+    }
+    out.push_str("</table>\n");
+}
+
+fn throughput_chart(recording: &Recording) -> String {
+    let series_for = |phase: Phase, label, color| Series {
+        label,
+        color,
+        points: recording
+            .samples
+            .iter()
+            .filter(|s| s.phase == phase)
+            .map(|s| (s.interval as f64, s.mbps))
+            .collect(),
+    };
+    let series: Vec<Series> = [
+        series_for(Phase::Download, "Download", DOWNLOAD_COLOR),
+        series_for(Phase::Upload, "Upload", UPLOAD_COLOR),
+    ]
+    .into_iter()
+    .filter(|s| !s.points.is_empty())
+    .collect();
+    line_chart(&series, "Mbps", |x| {
+        format!("{} s", (x * 10.0).round() / 10.0)
+    })
+}
+
+/// Buckets round-trip times into about eight whole-millisecond bins.
+fn latency_histogram(rtts: &[f64]) -> Vec<(String, u32)> {
+    if rtts.is_empty() {
+        return Vec::new();
+    }
+    let low = rtts.iter().copied().fold(f64::INFINITY, f64::min).floor();
+    let high = rtts.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let width = ((high - low) / 8.0).ceil().max(1.0);
+    let bins = ((high - low) / width).floor() as usize + 1;
+
+    let mut counts = vec![0u32; bins];
+    for rtt in rtts {
+        let bin = (((rtt - low) / width).floor() as usize).min(bins - 1);
+        counts[bin] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let from = low + i as f64 * width;
+            (format!("{}–{} ms", from, from + width), count)
+        })
+        .collect()
+}
+
+fn history_section(out: &mut String, days: u32, entries: &[HistoryEntry]) {
+    let _ = writeln!(
+        out,
+        "<h2>History</h2>\n<div class=\"muted\">{} saved runs in the last {} days</div>",
+        entries.len(),
+        days
+    );
+    if entries.is_empty() {
+        return;
+    }
+
+    // Runs stopped early or that skipped a phase are left out, not drawn as zeros.
+    let points = |metric: Metric| {
+        entries
+            .iter()
+            .filter_map(|e| Some((e.timestamp.timestamp() as f64, metric.value(e)?)))
+            .collect()
+    };
+    let date = |x: f64| {
+        Utc.timestamp_opt(x as i64, 0)
+            .single()
+            .map(|t: DateTime<Utc>| t.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    };
+    out.push_str(&line_chart(
+        &[
+            Series {
+                label: "Download",
+                color: DOWNLOAD_COLOR,
+                points: points(Metric::Download),
+            },
+            Series {
+                label: "Upload",
+                color: UPLOAD_COLOR,
+                points: points(Metric::Upload),
+            },
+        ],
+        "Mbps",
+        date,
+    ));
+    out.push('\n');
+    out.push_str(&line_chart(
+        &[Series {
+            label: "Ping",
+            color: LATENCY_COLOR,
+            points: points(Metric::Latency),
+        }],
+        "ms",
+        date,
+    ));
+    out.push('\n');
+
+    out.push_str("<table>\n<tr><th></th><th>Average</th><th>Best</th><th>Worst</th></tr>\n");
+    let stats = HistoryStatistics::from_entries(entries);
+    for metric in Metric::ALL {
+        let (mean, best, worst) = stats.summary(metric);
+        let _ = writeln!(
+            out,
+            "<tr><th>{}</th><td>{:.1} {u}</td><td>{:.1} {u}</td><td>{:.1} {u}</td></tr>",
+            metric.name(),
+            mean,
+            best,
+            worst,
+            u = metric.unit()
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Progress;
+
+    #[test]
+    fn renders_a_self_contained_page() {
+        let result = TestResult {
+            server_name: "PingTest <Frankfurt>".to_string(),
+            packet_loss: 10.0,
+            ..TestResult::sample()
+        };
+        let mut recording = Recording::new();
+        let now = result.timestamp;
+        for (interval, mbps) in [(1, 90.0), (2, 94.2)] {
+            let sample = Progress::Sample {
+                phase: Phase::Download,
+                interval,
+                bytes: 0,
+                mbps,
+            };
+            recording.record(&sample, now);
+        }
+        for rtt_ms in [Some(11.0), Some(12.5), None, Some(19.0)] {
+            recording.record(&Progress::LatencyProbe { seq: 0, rtt_ms }, now);
+        }
+
+        let html = render(&ReportInput {
+            result: &result,
+            recording: &recording,
+            tag: Some("isp-ticket"),
+            interface: Some("eth0"),
+            history: Some((30, &[])),
+        });
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("PingTest &lt;Frankfurt&gt;"));
+        assert!(html.contains("<polyline"));
+        assert!(html.contains("4 probes, 1 lost"));
+        assert!(html.contains("<td>Download</td><td>94.2 Mbps</td><td>80/100</td><td>70%</td>"));
+        assert!(html.contains("0 saved runs in the last 30 days"));
+        assert!(!html.contains("<script") && !html.contains("src=") && !html.contains("href="));
+    }
+
+    #[test]
+    fn buckets_latency_into_whole_millisecond_bins() {
+        let bins = latency_histogram(&[11.0, 12.5, 19.0]);
+        assert_eq!(bins.len(), 9);
+        assert_eq!(bins[0], ("11–12 ms".to_string(), 1));
+        assert_eq!(bins[1].1, 1);
+        assert_eq!(bins[8], ("19–20 ms".to_string(), 1));
+    }
+}
@@ -0,0 +1,138 @@
+//! JUnit XML with one testcase per threshold.
+//!
+//! CI systems show each threshold as a test; a failed threshold carries the
+//! measured and expected values, and a threshold whose phase was skipped is
+//! reported as skipped.
+
+use super::escape;
+use crate::network::TestResult;
+use crate::thresholds::Outcome;
+use chrono::SecondsFormat;
+use std::fmt::Write;
+
+const SUITE: &str = "pingtest";
+
+pub fn render(result: &TestResult, outcomes: &[Outcome], tag: Option<&str>) -> String {
+    let failures = outcomes.iter().filter(|o| !o.passed()).count();
+    let skipped = outcomes.iter().filter(|o| o.measured.is_none()).count();
+    let timestamp = result.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        SUITE,
+        outcomes.len(),
+        failures,
+        skipped,
+        result.test_duration
+    );
+    let _ = writeln!(
+        out,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" \
+         time=\"{:.3}\" timestamp=\"{}\">",
+        SUITE,
+        outcomes.len(),
+        failures,
+        skipped,
+        result.test_duration,
+        timestamp
+    );
+    out.push_str("    <properties>\n");
+    let mut properties = vec![
+        ("server", result.server_name.clone()),
+        ("server_host", result.server_host.clone()),
+        ("download_mbps", format!("{:.1}", result.download_speed)),
+        ("upload_mbps", format!("{:.1}", result.upload_speed)),
+        ("ping_ms", format!("{:.1}", result.ping)),
+        ("jitter_ms", format!("{:.1}", result.jitter)),
+        ("packet_loss_percent", format!("{:.1}", result.packet_loss)),
+    ];
+    properties.extend(tag.map(|tag| ("tag", tag.to_string())));
+    for (name, value) in properties {
+        let _ = writeln!(
+            out,
+            "      <property name=\"{}\" value=\"{}\"/>",
+            name,
+            escape(&value)
+        );
+    }
+    out.push_str("    </properties>\n");
+
+    for outcome in outcomes {
+        let name = escape(&outcome.threshold.describe());
+        let _ = write!(
+            out,
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"0\"",
+            SUITE, name
+        );
+        match outcome.measured {
+            None => {
+                out.push_str(">\n      <skipped message=\"phase was skipped\"/>\n    </testcase>\n")
+            }
+            Some(_) if outcome.passed() => out.push_str("/>\n"),
+            Some(measured) => {
+                let _ = writeln!(
+                    out,
+                    ">\n      <failure type=\"threshold\" message=\"{}\">measured: {}\n\
+                     expected: {}</failure>\n    </testcase>",
+                    escape(&outcome.describe_failure()),
+                    outcome.threshold.check.format_value(measured),
+                    name
+                );
+            }
+        }
+    }
+
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::speedtest::TestMethod;
+    use crate::thresholds::{evaluate, Check, Threshold};
+
+    #[test]
+    fn renders_passing_failing_and_skipped_cases() {
+        let result = TestResult {
+            download_speed: 42.1,
+            upload_speed: 0.0,
+            test_duration: 20.0,
+            method: TestMethod {
+                upload: false,
+                ..TestResult::sample().method
+            },
+            ..TestResult::sample()
+        };
+        let thresholds = [
+            Threshold {
+                check: Check::Download,
+                limit: 50.0,
+            },
+            Threshold {
+                check: Check::Upload,
+                limit: 10.0,
+            },
+            Threshold {
+                check: Check::Latency,
+                limit: 20.0,
+            },
+        ];
+
+        let xml = render(&result, &evaluate(&thresholds, &result), Some("runner<1>"));
+
+        assert!(xml.contains(
+            "<testsuites name=\"pingtest\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"20.000\">"
+        ));
+        assert!(xml.contains("<property name=\"tag\" value=\"runner&lt;1&gt;\"/>"));
+        assert!(xml.contains(
+            "<testcase classname=\"pingtest\" name=\"download &gt;= 50 Mbps\" time=\"0\">\n      \
+             <failure type=\"threshold\" message=\"download 42.1 Mbps is below the minimum of 50 Mbps\">\
+             measured: 42.1 Mbps\nexpected: download &gt;= 50 Mbps</failure>"
+        ));
+        assert!(xml.contains("<skipped message=\"phase was skipped\"/>"));
+        assert!(xml.contains("name=\"latency &lt;= 20 ms\" time=\"0\"/>"));
+    }
+}
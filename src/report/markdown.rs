@@ -0,0 +1,388 @@
+//! GitHub-flavoured Markdown for results, comparisons and history statistics.
+//!
+//! Tables are padded so they also read well as plain text.
+
+use crate::batch::Summary;
+use crate::export::schema::ThroughputSection;
+use crate::export::ResultDocument;
+use crate::history::anomaly::{self, Metric};
+use crate::history::HistoryStatistics;
+use crate::network::SteadyWindow;
+use crate::thresholds::Check;
+use crate::utils::{format_bytes, get_quality_description, get_quality_score};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+}
+
+/// A single test result.
+pub fn result(document: &ResultDocument) -> String {
+    let mut out = String::from(if document.partial {
+        "## PingTest Result (partial)\n\n"
+    } else {
+        "## PingTest Result\n\n"
+    });
+    let rows = vec![
+        row("Download", throughput(document, Metric::Download)),
+        row("Upload", throughput(document, Metric::Upload)),
+        row("Ping", format!("{:.1} ms", document.latency.ping_ms)),
+        row("Jitter", format!("{:.1} ms", document.latency.jitter_ms)),
+        row(
+            "Packet loss",
+            format!("{:.1}%", document.latency.packet_loss_percent),
+        ),
+        row(
+            "Quality",
+            quality(document.quality.score, &document.quality.description),
+        ),
+        row("Data used", data_used(document)),
+        row(
+            "Test duration",
+            format!("{:.1} s", document.elapsed_ms / 1000.0),
+        ),
+    ];
+    out.push_str(&table(
+        &[("Metric", Align::Left), ("Value", Align::Right)],
+        &rows,
+    ));
+
+    let windows: Vec<String> = [
+        ("download", &document.download),
+        ("upload", &document.upload),
+    ]
+    .into_iter()
+    .filter_map(|(phase, section)| {
+        let steady = section.as_ref()?.steady_state.as_ref()?;
+        let window = SteadyWindow {
+            start_s: steady.start_s,
+            end_s: steady.end_s,
+            converged: steady.converged,
+        };
+        Some(format!("{} {}", phase, window))
+    })
+    .collect();
+    if !windows.is_empty() {
+        let _ = writeln!(out, "\nSteady state: {}", windows.join(" · "));
+    }
+
+    let _ = write!(
+        out,
+        "\nServer: **{}** ({}) · {}",
+        document.server.name,
+        document.server.location,
+        document.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    if let Some(tag) = &document.tag {
+        let _ = write!(out, " · tag `{}`", tag);
+    }
+    out.push('\n');
+    out
+}
+
+/// A result next to the averages of saved runs from the last `days` days.
+pub fn comparison(document: &ResultDocument, baseline: &HistoryStatistics, days: u32) -> String {
+    let mut out = format!(
+        "## Compared with the last {} days ({} runs)\n\n",
+        days, baseline.total_tests
+    );
+    if baseline.total_tests == 0 {
+        let _ = writeln!(out, "_No saved results in the last {} days._", days);
+        return out;
+    }
+
+    let mut rows: Vec<Vec<String>> = Metric::ALL
+        .into_iter()
+        .map(|metric| {
+            let (average, _, _) = baseline.summary(metric);
+            let current = current_value(document, metric);
+            vec![
+                metric_label(metric).to_string(),
+                current.map_or_else(|| "skipped".to_string(), |v| value(v, metric)),
+                value(average, metric),
+                current.map_or_else(|| "–".to_string(), |v| anomaly::format_change(average, v)),
+            ]
+        })
+        .collect();
+    let average_score = average_quality(baseline);
+    rows.push(vec![
+        "Quality".to_string(),
+        quality(document.quality.score, &document.quality.description),
+        quality(average_score, get_quality_description(average_score)),
+        format!(
+            "{:+}",
+            i16::from(document.quality.score) - i16::from(average_score)
+        ),
+    ]);
+    out.push_str(&table(
+        &[
+            ("Metric", Align::Left),
+            ("This run", Align::Right),
+            ("Average", Align::Right),
+            ("Change", Align::Right),
+        ],
+        &rows,
+    ));
+    out
+}
+
+/// Statistics over saved runs from the last `days` days.
+pub fn history_statistics(stats: &HistoryStatistics, days: u32) -> String {
+    let mut out = format!(
+        "## History Statistics (last {} days, {} runs)\n\n",
+        days, stats.total_tests
+    );
+    if stats.total_tests == 0 {
+        let _ = writeln!(out, "_No saved results in the last {} days._", days);
+        return out;
+    }
+
+    let rows: Vec<Vec<String>> = Metric::ALL
+        .into_iter()
+        .map(|metric| {
+            let (average, best, worst) = stats.summary(metric);
+            vec![
+                metric_label(metric).to_string(),
+                value(average, metric),
+                value(best, metric),
+                value(worst, metric),
+            ]
+        })
+        .collect();
+    out.push_str(&table(
+        &[
+            ("Metric", Align::Left),
+            ("Average", Align::Right),
+            ("Best", Align::Right),
+            ("Worst", Align::Right),
+        ],
+        &rows,
+    ));
+    let score = average_quality(stats);
+    let _ = writeln!(
+        out,
+        "\nAverage quality: {}",
+        quality(score, get_quality_description(score))
+    );
+    out
+}
+
+/// Statistics over the runs of one `--runs` batch.
+pub fn batch(summaries: &[(Check, Summary)], runs: u32) -> String {
+    let mut out = format!("## Summary of {} runs\n\n", runs);
+    let rows: Vec<Vec<String>> = summaries
+        .iter()
+        .map(|(check, summary)| {
+            vec![
+                check.label().to_string(),
+                check.format_value(summary.mean),
+                check.format_value(summary.median),
+                check.format_value(summary.min),
+                check.format_value(summary.max),
+                summary.ci95.map_or_else(
+                    || "–".to_string(),
+                    |ci| format!("±{}", check.format_value(ci)),
+                ),
+            ]
+        })
+        .collect();
+    out.push_str(&table(
+        &[
+            ("Metric", Align::Left),
+            ("Mean", Align::Right),
+            ("Median", Align::Right),
+            ("Min", Align::Right),
+            ("Max", Align::Right),
+            ("95% CI", Align::Right),
+        ],
+        &rows,
+    ));
+    out
+}
+
+/// The quality score of the average download, upload and ping.
+pub fn average_quality(stats: &HistoryStatistics) -> u8 {
+    get_quality_score(stats.avg_download, stats.avg_upload, stats.avg_ping)
+}
+
+fn row(label: &str, value: String) -> Vec<String> {
+    vec![label.to_string(), value]
+}
+
+fn metric_label(metric: Metric) -> &'static str {
+    // History calls it latency; results and the terminal output call it ping.
+    match metric {
+        Metric::Latency => "Ping",
+        _ => metric.name(),
+    }
+}
+
+fn value(value: f64, metric: Metric) -> String {
+    format!("{:.1} {}", value, metric.unit())
+}
+
+fn quality(score: u8, description: &str) -> String {
+    format!("{}/100 ({})", score, description)
+}
+
+fn current_value(document: &ResultDocument, metric: Metric) -> Option<f64> {
+    let mbps = |section: &Option<ThroughputSection>| {
+        section.as_ref().map(|t| t.bits_per_second / 1_000_000.0)
+    };
+    match metric {
+        Metric::Download => mbps(&document.download),
+        Metric::Upload => mbps(&document.upload),
+        Metric::Latency => Some(document.latency.ping_ms),
+    }
+}
+
+/// Bytes moved per phase, e.g. `176.6 MB download`.
+fn data_used(document: &ResultDocument) -> String {
+    let used: Vec<String> = [
+        ("download", &document.download),
+        ("upload", &document.upload),
+    ]
+    .into_iter()
+    .filter_map(|(phase, section)| {
+        let bytes = section.as_ref()?.bytes?;
+        Some(format!("{} {}", format_bytes(bytes), phase))
+    })
+    .collect();
+    if used.is_empty() {
+        "–".to_string()
+    } else {
+        used.join(", ")
+    }
+}
+
+fn throughput(document: &ResultDocument, metric: Metric) -> String {
+    current_value(document, metric).map_or_else(|| "skipped".to_string(), |v| value(v, metric))
+}
+
+/// Renders a table with every column padded to its widest cell.
+fn table(columns: &[(&str, Align)], rows: &[Vec<String>]) -> String {
+    let escape = |cell: &str| cell.replace('|', "\\|");
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (header, _))| {
+            rows.iter()
+                .map(|r| escape(&r[i]).chars().count())
+                .chain([header.chars().count(), 3])
+                .max()
+                .unwrap_or(3)
+        })
+        .collect();
+
+    let line = |cells: Vec<String>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(columns)
+            .zip(&widths)
+            .map(|((cell, (_, align)), &width)| {
+                let pad = " ".repeat(width - cell.chars().count());
+                match align {
+                    Align::Left => format!("{}{}", cell, pad),
+                    Align::Right => format!("{}{}", pad, cell),
+                }
+            })
+            .collect();
+        format!("| {} |\n", padded.join(" | "))
+    };
+
+    let mut out = line(columns.iter().map(|(h, _)| h.to_string()).collect());
+    let rule: Vec<String> = columns
+        .iter()
+        .zip(&widths)
+        .map(|((_, align), &width)| match align {
+            Align::Left => format!(":{}", "-".repeat(width - 1)),
+            Align::Right => format!("{}:", "-".repeat(width - 1)),
+        })
+        .collect();
+    let _ = writeln!(out, "| {} |", rule.join(" | "));
+    for r in rows {
+        out.push_str(&line(r.iter().map(|c| escape(c)).collect()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::speedtest::TestMethod;
+    use crate::network::TestResult;
+
+    fn document() -> ResultDocument {
+        let result = TestResult {
+            test_duration: 20.0,
+            method: TestMethod {
+                upload: false,
+                ..TestResult::sample().method
+            },
+            download_bytes: 176_625_000,
+            ..TestResult::sample()
+        };
+        ResultDocument::new(&result, 86, "Very Good", Some("office".to_string()))
+    }
+
+    #[test]
+    fn renders_an_aligned_result_table() {
+        let markdown = result(&document());
+
+        let table: Vec<&str> = markdown.lines().skip(2).take(10).collect();
+        assert_eq!(
+            table,
+            [
+                "| Metric        |              Value |",
+                "| :------------ | -----------------: |",
+                "| Download      |          94.2 Mbps |",
+                "| Upload        |            skipped |",
+                "| Ping          |            12.0 ms |",
+                "| Jitter        |             1.5 ms |",
+                "| Packet loss   |               0.0% |",
+                "| Quality       | 86/100 (Very Good) |",
+                "| Data used     |  176.6 MB download |",
+                "| Test duration |             20.0 s |",
+            ]
+        );
+        assert!(markdown
+            .contains("Server: **PingTest Frankfurt** (Frankfurt, DE) · 2026-01-05 14:00:00 UTC"));
+        assert!(markdown.ends_with("· tag `office`\n"));
+    }
+
+    #[test]
+    fn compares_with_history_averages() {
+        let baseline = HistoryStatistics {
+            total_tests: 4,
+            avg_download: 80.0,
+            avg_upload: 20.0,
+            avg_ping: 15.0,
+            ..HistoryStatistics::default()
+        };
+
+        let markdown = comparison(&document(), &baseline, 7);
+
+        assert_eq!(
+            markdown,
+            "## Compared with the last 7 days (4 runs)\n\n\
+             | Metric   |           This run |            Average | Change |\n\
+             | :------- | -----------------: | -----------------: | -----: |\n\
+             | Download |          94.2 Mbps |          80.0 Mbps | +17.8% |\n\
+             | Upload   |            skipped |          20.0 Mbps |      – |\n\
+             | Ping     |            12.0 ms |            15.0 ms | -20.0% |\n\
+             | Quality  | 86/100 (Very Good) | 86/100 (Very Good) |     +0 |\n"
+        );
+    }
+
+    #[test]
+    fn notes_empty_history() {
+        let markdown = history_statistics(&HistoryStatistics::default(), 30);
+
+        assert_eq!(
+            markdown,
+            "## History Statistics (last 30 days, 0 runs)\n\n_No saved results in the last 30 days._\n"
+        );
+    }
+}
@@ -0,0 +1,15 @@
+//! Human- and CI-facing reports built from test results.
+
+pub mod html;
+pub mod junit;
+pub mod markdown;
+pub mod svg;
+
+/// Escapes text for XML and HTML content and attribute values.
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
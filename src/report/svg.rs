@@ -0,0 +1,190 @@
+//! Small inline SVG charts with no external assets.
+
+use super::escape;
+use std::fmt::Write;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 240.0;
+const LEFT: f64 = 56.0;
+const RIGHT: f64 = 16.0;
+const TOP: f64 = 16.0;
+const BOTTOM: f64 = 36.0;
+
+/// One line of a line chart.
+pub struct Series<'a> {
+    pub label: &'a str,
+    pub color: &'a str,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// A line chart with a y axis starting at zero. `x_label` formats x-axis
+/// tick values.
+pub fn line_chart(series: &[Series], y_unit: &str, x_label: impl Fn(f64) -> String) -> String {
+    let points = series.iter().flat_map(|s| s.points.iter());
+    let (mut x_min, mut x_max, mut y_max) = (f64::INFINITY, f64::NEG_INFINITY, 0.0_f64);
+    for &(x, y) in points {
+        x_min = x_min.min(x);
+        x_max = x_max.max(x);
+        y_max = y_max.max(y);
+    }
+    if !x_min.is_finite() {
+        return empty_chart();
+    }
+    if x_max <= x_min {
+        x_max = x_min + 1.0;
+    }
+    let (y_step, y_top) = nice_axis(y_max);
+
+    let plot_w = WIDTH - LEFT - RIGHT;
+    let plot_h = HEIGHT - TOP - BOTTOM;
+    let sx = |x: f64| LEFT + (x - x_min) / (x_max - x_min) * plot_w;
+    let sy = |y: f64| TOP + plot_h - y / y_top * plot_h;
+
+    let mut svg = open_svg();
+    let gridlines = (y_top / y_step).round() as u32;
+    for i in 0..=gridlines {
+        let y = f64::from(i) * y_step;
+        let _ = write!(
+            svg,
+            r##"<line x1="{l}" x2="{r}" y1="{py:.1}" y2="{py:.1}" stroke="#e5e7eb"/><text x="{tx}" y="{ty:.1}" text-anchor="end" font-size="11" fill="#6b7280">{y} {unit}</text>"##,
+            l = LEFT,
+            r = WIDTH - RIGHT,
+            py = sy(y),
+            tx = LEFT - 6.0,
+            ty = sy(y) + 4.0,
+            y = format_tick(y),
+            unit = escape(y_unit),
+        );
+    }
+    let mut previous_label = String::new();
+    for i in 0..=4 {
+        let x = x_min + (x_max - x_min) * f64::from(i) / 4.0;
+        let label = x_label(x);
+        // Short ranges round several ticks to the same label; keep the first.
+        if label == previous_label {
+            continue;
+        }
+        let _ = write!(
+            svg,
+            r##"<text x="{:.1}" y="{}" text-anchor="middle" font-size="11" fill="#6b7280">{}</text>"##,
+            sx(x),
+            HEIGHT - BOTTOM + 16.0,
+            escape(&label)
+        );
+        previous_label = label;
+    }
+    for (i, s) in series.iter().enumerate() {
+        let path: Vec<String> = s
+            .points
+            .iter()
+            .map(|&(x, y)| format!("{:.1},{:.1}", sx(x), sy(y)))
+            .collect();
+        let _ = write!(
+            svg,
+            r#"<polyline fill="none" stroke="{c}" stroke-width="2" points="{p}"/>"#,
+            c = s.color,
+            p = path.join(" ")
+        );
+        for &(x, y) in &s.points {
+            let _ = write!(
+                svg,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="2.5" fill="{}"/>"#,
+                sx(x),
+                sy(y),
+                s.color
+            );
+        }
+        let lx = LEFT + 8.0 + i as f64 * 120.0;
+        let _ = write!(
+            svg,
+            r##"<rect x="{lx}" y="{ly}" width="10" height="10" fill="{c}"/><text x="{tx}" y="{ty}" font-size="12" fill="#374151">{label}</text>"##,
+            lx = lx,
+            ly = HEIGHT - 14.0,
+            c = s.color,
+            tx = lx + 14.0,
+            ty = HEIGHT - 5.0,
+            label = escape(s.label),
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// A bar chart with one labelled bar per bucket.
+pub fn bar_chart(bars: &[(String, u32)], color: &str) -> String {
+    if bars.is_empty() {
+        return empty_chart();
+    }
+    let max = bars.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+    let plot_w = WIDTH - LEFT - RIGHT;
+    let plot_h = HEIGHT - TOP - BOTTOM;
+    let slot = plot_w / bars.len() as f64;
+
+    let mut svg = open_svg();
+    let _ = write!(
+        svg,
+        r##"<line x1="{l}" x2="{r}" y1="{y}" y2="{y}" stroke="#9ca3af"/>"##,
+        l = LEFT,
+        r = WIDTH - RIGHT,
+        y = TOP + plot_h
+    );
+    for (i, (label, count)) in bars.iter().enumerate() {
+        let h = f64::from(*count) / f64::from(max) * plot_h;
+        let x = LEFT + i as f64 * slot;
+        let _ = write!(
+            svg,
+            r##"<rect x="{x:.1}" y="{y:.1}" width="{w:.1}" height="{h:.1}" fill="{c}"/><text x="{cx:.1}" y="{ny:.1}" text-anchor="middle" font-size="11" fill="#374151">{n}</text><text x="{cx:.1}" y="{ly}" text-anchor="middle" font-size="10" fill="#6b7280">{label}</text>"##,
+            x = x + slot * 0.1,
+            y = TOP + plot_h - h,
+            w = slot * 0.8,
+            h = h,
+            c = color,
+            cx = x + slot / 2.0,
+            ny = TOP + plot_h - h - 4.0,
+            n = count,
+            ly = HEIGHT - BOTTOM + 16.0,
+            label = escape(label),
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn open_svg() -> String {
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}" role="img">"#,
+        WIDTH, HEIGHT
+    )
+}
+
+fn empty_chart() -> String {
+    let mut svg = open_svg();
+    let _ = write!(
+        svg,
+        r##"<text x="{}" y="{}" text-anchor="middle" fill="#9ca3af">No data</text></svg>"##,
+        WIDTH / 2.0,
+        HEIGHT / 2.0
+    );
+    svg
+}
+
+/// A step of 1, 2 or 5 times a power of ten giving about four gridlines,
+/// and the axis top as a multiple of it.
+fn nice_axis(max: f64) -> (f64, f64) {
+    if max <= 0.0 {
+        return (1.0, 1.0);
+    }
+    let raw = max / 4.0;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(10.0 * magnitude);
+    (step, (max / step).ceil() * step)
+}
+
+fn format_tick(value: f64) -> String {
+    let rounded = (value * 1000.0).round() / 1000.0;
+    format!("{}", rounded)
+}
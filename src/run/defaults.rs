@@ -0,0 +1,147 @@
+//! Option defaults from the config file and its profiles, applied to a
+//! `clap` command so flags and environment variables still take precedence.
+
+use crate::config::{self, Config, Profile};
+use anyhow::{anyhow, Context, Result};
+use clap::builder::Resettable;
+use clap::parser::ValueSource;
+use clap::ArgAction;
+use std::path::Path;
+
+/// An option id and the values to use as its default.
+pub type OptionDefault = (String, Vec<String>);
+
+/// Options that select what the defaults are, so a profile cannot set them.
+const NOT_IN_PROFILES: [&str; 4] = ["config", "profile", "help", "version"];
+
+/// The config file's settings plus the options of `profile`, if one is
+/// selected. Every profile is checked, so a typo in one that is not selected
+/// still fails. `path` is where `config` was read from, for errors.
+pub fn resolve(
+    command: &clap::Command,
+    config: &Config,
+    profile: Option<&str>,
+    path: &Path,
+) -> Result<Vec<OptionDefault>> {
+    let mut defaults = file_defaults(config);
+    for (name, options) in &config.profiles {
+        let options = profile_defaults(command, name, options)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        if profile == Some(name.as_str()) {
+            defaults.extend(options);
+        }
+    }
+    if let Some(name) = profile {
+        config
+            .profile(name)
+            .with_context(|| format!("Failed to select a profile from {}", path.display()))?;
+    }
+    Ok(defaults)
+}
+
+fn file_defaults(config: &Config) -> Vec<OptionDefault> {
+    config
+        .cli_defaults()
+        .into_iter()
+        .map(|(id, value)| (id.to_string(), vec![value]))
+        .collect()
+}
+
+/// Checks a profile's values against the options of `command` they set and
+/// returns them as option defaults. Errors name the key.
+pub fn profile_defaults(
+    command: &clap::Command,
+    name: &str,
+    profile: &Profile,
+) -> Result<Vec<OptionDefault>> {
+    profile
+        .iter()
+        .map(|(key, value)| {
+            let invalid = |message: String| anyhow!("profiles.{}.{}: {}", name, key, message);
+            let id = key.replace('-', "_");
+            let arg = command
+                .get_arguments()
+                .find(|arg| {
+                    arg.get_id() == id.as_str()
+                        && arg.get_long().is_some()
+                        && !NOT_IN_PROFILES.contains(&id.as_str())
+                })
+                .ok_or_else(|| invalid("unknown option".to_string()))?;
+            let values = config::option_values(value).map_err(|e| invalid(e.to_string()))?;
+            if values.len() != 1 && !matches!(arg.get_action(), ArgAction::Append) {
+                return Err(invalid("expected a single value".to_string()));
+            }
+            // Parse the values as the option's default on its own, so its
+            // value parser checks them without flags or variables interfering.
+            clap::Command::new("pingtest")
+                .arg(
+                    arg.clone()
+                        .env(None)
+                        .requires(Resettable::Reset)
+                        .conflicts_with(Resettable::Reset)
+                        .default_values(values.clone()),
+                )
+                .try_get_matches_from(["pingtest"])
+                .map_err(|e| invalid(clap_message(&e)))?;
+            Ok((id, values))
+        })
+        .collect()
+}
+
+/// The first line of a clap error, without its `error: ` prefix.
+fn clap_message(error: &clap::Error) -> String {
+    let rendered = error.to_string();
+    let line = rendered.lines().next().unwrap_or_default();
+    line.strip_prefix("error: ").unwrap_or(line).to_string()
+}
+
+/// `command` with `defaults` replacing its built-in defaults.
+pub fn with_defaults(mut command: clap::Command, defaults: &[OptionDefault]) -> clap::Command {
+    for (id, values) in defaults {
+        command = command.mut_arg(id.as_str(), |arg| arg.default_values(values.clone()));
+    }
+    command
+}
+
+/// Prints every profile's options as they would resolve without flags:
+/// the profile's own values plus the settings it inherits.
+pub fn print_profiles(command: &clap::Command, config: &Config, path: &Path) -> Result<()> {
+    if config.profiles.is_empty() {
+        println!("No profiles in {}", path.display());
+        return Ok(());
+    }
+    let inherited = file_defaults(config);
+    for (i, (name, profile)) in config.profiles.iter().enumerate() {
+        let own = profile_defaults(command, name, profile)?;
+        let mut defaults = inherited.clone();
+        defaults.extend(own.iter().cloned());
+        let matches =
+            with_defaults(command.clone(), &defaults).try_get_matches_from(["pingtest"])?;
+
+        if i > 0 {
+            println!();
+        }
+        println!("{}", name);
+        let mut ids: Vec<&str> = inherited.iter().map(|(id, _)| id.as_str()).collect();
+        ids.extend(own.iter().map(|(id, _)| id.as_str()));
+        ids.sort_unstable();
+        ids.dedup();
+        for id in ids {
+            let values: Vec<String> = matches
+                .get_raw(id)
+                .into_iter()
+                .flatten()
+                .map(|value| value.to_string_lossy().into_owned())
+                .collect();
+            let source = if matches.value_source(id) == Some(ValueSource::EnvVariable) {
+                "environment"
+            } else if own.iter().any(|(own_id, _)| own_id == id) {
+                "profile"
+            } else {
+                "config"
+            };
+            println!("  {:<14} {:<24} ({})", id, values.join(", "), source);
+        }
+    }
+    Ok(())
+}
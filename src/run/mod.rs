@@ -0,0 +1,566 @@
+//! A speed test run from the command line: one test or a batch of them,
+//! with every requested output written for each.
+
+pub mod defaults;
+
+use crate::batch;
+use crate::export::stream::{self, StreamEvent};
+use crate::export::{self, ExportFormat, ResultDocument};
+use crate::history::{HistoryEntry, HistoryManager, HistoryStatistics};
+use crate::metrics::{influx, mqtt, otlp};
+use crate::network::speedtest::TestMethod;
+use crate::network::{Convergence, Recording, SpeedTest, TestResult, TestSettings};
+use crate::report;
+use crate::thresholds::{self, Outcome, Threshold};
+use crate::ui::{output, UiManager};
+use crate::utils::{self, get_quality_description, get_quality_score};
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Exit code after a Ctrl-C, whether or not partial results were kept.
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+/// Exit code when every run finished but an output, such as an InfluxDB
+/// server or an export file, could not be written.
+pub const EXIT_OUTPUT_FAILED: u8 = 1;
+
+/// Longest throughput phase in `quick` mode, in seconds.
+const QUICK_DURATION: u64 = 5;
+
+/// Seconds the `adaptive` estimate must hold within its tolerance.
+const ADAPTIVE_WINDOW: usize = 3;
+
+/// `quick` ends a phase once the estimate holds within 10% for 3 seconds.
+const QUICK_CONVERGENCE: Convergence = Convergence {
+    window: 3,
+    tolerance: 0.1,
+};
+
+pub struct RunOptions {
+    pub method: TestMethod,
+    pub settings: TestSettings,
+    /// Run a short test and print one line.
+    pub quick: bool,
+    /// End each throughput phase once the estimate holds within this many
+    /// percent; `method.duration` becomes the maximum.
+    pub adaptive: Option<f64>,
+    /// Bytes each throughput phase may move.
+    pub max_bytes: Option<u64>,
+    /// Cap on sending and receiving, in Mbps.
+    pub rate_limit: Option<f64>,
+    pub theme: String,
+    pub export: Option<PathBuf>,
+    /// Overrides the format of `export`; Markdown without `export` prints to
+    /// stdout.
+    pub format: Option<ExportFormat>,
+    /// Used when neither `format` nor the extension of `export` names one.
+    pub default_format: Option<ExportFormat>,
+    /// Stream progress and the result as NDJSON events on stdout.
+    pub stream_json: bool,
+    pub influx: Option<InfluxOptions>,
+    /// Labels InfluxDB points and reports [default: the default-route interface].
+    pub interface: Option<String>,
+    pub otlp: Option<OtlpOptions>,
+    pub mqtt: Option<mqtt::MqttSettings>,
+    /// Checked after every run; a failure makes the exit code nonzero.
+    pub thresholds: Vec<Threshold>,
+    /// JUnit XML report with one testcase per threshold.
+    pub junit: Option<PathBuf>,
+    /// Self-contained HTML report.
+    pub report: Option<PathBuf>,
+    /// Include saved history from the last N days in `report`.
+    pub report_days: Option<u32>,
+    /// Save every run to history.
+    pub save: bool,
+    pub tag: Option<String>,
+    /// At least 1.
+    pub runs: u32,
+    /// Time between the start of one run and the next.
+    pub interval: Duration,
+    /// Compare each result with the results saved in the last N days.
+    pub compare: Option<u32>,
+}
+
+pub struct InfluxOptions {
+    /// A file, `-` for stdout, or an InfluxDB v2 URL.
+    pub destination: String,
+    /// Include per-interval throughput samples.
+    pub samples: bool,
+    pub measurement: String,
+    pub org: Option<String>,
+    pub bucket: Option<String>,
+    pub token: Option<String>,
+}
+
+pub struct OtlpOptions {
+    /// OTLP/HTTP collector, e.g. `http://localhost:4318`.
+    pub endpoint: String,
+    pub headers: Vec<(String, String)>,
+    /// Also export each test as a trace with a span per phase.
+    pub traces: bool,
+}
+
+/// Settings resolved once and shared by every run of a batch.
+struct RunContext {
+    options: RunOptions,
+    export_format: Option<ExportFormat>,
+    influx: Option<influx::Destination>,
+    mqtt: Option<mqtt::Publisher>,
+    markdown_stdout: bool,
+    /// With `stream_json`, InfluxDB or Markdown on stdout, stdout carries
+    /// only that output; notices go to stderr.
+    quiet: bool,
+    baseline: Option<HistoryStatistics>,
+    batch_id: Option<String>,
+    /// Longest throughput phase in seconds.
+    duration: u64,
+    convergence: Option<Convergence>,
+    /// Turns true on the first Ctrl-C.
+    stop: watch::Receiver<bool>,
+    ui: UiManager,
+}
+
+impl RunContext {
+    fn speed_test(&self) -> SpeedTest {
+        let mut speed_test = SpeedTest::new().with_settings(self.options.settings.clone());
+        if let Some(convergence) = self.convergence {
+            speed_test = speed_test.with_convergence(convergence);
+        }
+        if let Some(max_bytes) = self.options.max_bytes {
+            speed_test = speed_test.with_max_bytes(max_bytes);
+        }
+        if let Some(mbps) = self.options.rate_limit {
+            speed_test = speed_test.with_rate_limit(mbps);
+        }
+        speed_test.with_stop(self.stop.clone())
+    }
+
+    /// `quick` prints only the one-line result.
+    fn notice(&self, message: String) {
+        if self.options.quick {
+            return;
+        }
+        if self.quiet {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+}
+
+/// Runs one test, or `runs` tests with a summary, and returns the exit code:
+/// nonzero when a threshold failed in any run (see [`thresholds::exit_code`]),
+/// [`EXIT_INTERRUPTED`] after a Ctrl-C, or [`EXIT_OUTPUT_FAILED`].
+pub async fn run(mut options: RunOptions) -> Result<u8> {
+    let export_format = options
+        .export
+        .as_deref()
+        .map(|path| export::resolve_format(path, options.format, options.default_format))
+        .transpose()?;
+    let influx = options
+        .influx
+        .as_ref()
+        .map(|influx| {
+            influx::Destination::parse(
+                &influx.destination,
+                influx.org.clone(),
+                influx.bucket.clone(),
+                influx.token.clone(),
+            )
+        })
+        .transpose()?;
+    if options.junit.is_some() && options.thresholds.is_empty() {
+        bail!(
+            "--junit needs at least one threshold (--min-download, --min-upload, --max-latency, --max-jitter, --max-loss or --min-score)"
+        );
+    }
+    let influx_stdout = influx.as_ref().is_some_and(|dest| dest.is_stdout());
+    let markdown_stdout =
+        options.export.is_none() && options.format == Some(ExportFormat::Markdown);
+    if options.stream_json && influx_stdout {
+        bail!("--influx - cannot be combined with --stream-json; both write to stdout");
+    }
+    if options.quick && (markdown_stdout || influx_stdout) {
+        bail!(
+            "--quick cannot be combined with --format markdown without --export or --influx -; they all write to stdout"
+        );
+    }
+    if markdown_stdout && (options.stream_json || influx_stdout) {
+        bail!(
+            "--format markdown without --export cannot be combined with --stream-json or --influx -; they all write to stdout"
+        );
+    }
+    // Read before any run is saved so runs are not compared with themselves.
+    let baseline = match options.compare {
+        Some(days) => Some(HistoryManager::new().await?.get_statistics(days).await?),
+        None => None,
+    };
+
+    let ctx = RunContext {
+        export_format,
+        influx,
+        mqtt: options.mqtt.take().map(mqtt::Publisher::new).transpose()?,
+        markdown_stdout,
+        quiet: options.stream_json || influx_stdout || markdown_stdout || options.quick,
+        baseline,
+        batch_id: (options.runs > 1).then(|| uuid::Uuid::new_v4().to_string()),
+        duration: if options.quick {
+            options.method.duration.min(QUICK_DURATION)
+        } else {
+            options.method.duration
+        },
+        convergence: if options.quick {
+            Some(QUICK_CONVERGENCE)
+        } else {
+            options.adaptive.map(|tolerance| Convergence {
+                window: ADAPTIVE_WINDOW,
+                tolerance: tolerance / 100.0,
+            })
+        },
+        stop: stop_on_ctrl_c(),
+        ui: UiManager::with_theme(&options.theme)?,
+        options,
+    };
+    let options = &ctx.options;
+
+    if !ctx.quiet {
+        println!("🚀 PingTest - Internet Speed Test");
+        println!("==================================");
+        println!();
+
+        println!("🌐 Running speed test...");
+        if let Some(tolerance) = options.adaptive {
+            println!(
+                "Duration: up to {} seconds (adaptive, {}% tolerance)",
+                ctx.duration, tolerance
+            );
+        } else {
+            println!("Duration: {} seconds", ctx.duration);
+        }
+        println!("Connections: {}", options.method.connections);
+        if let Some(max_bytes) = options.max_bytes {
+            println!("Data budget: {} per phase", utils::format_bytes(max_bytes));
+        }
+        if let Some(mbps) = options.rate_limit {
+            println!("Rate limit: {} Mbps", mbps);
+        }
+        println!("Theme: {}", options.theme);
+        if options.runs > 1 {
+            println!(
+                "Runs: {}, {} seconds apart",
+                options.runs,
+                options.interval.as_secs()
+            );
+        }
+        println!();
+    }
+
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+    let mut failed_outputs = 0;
+    let mut next_start = tokio::time::Instant::now();
+    let mut stop = ctx.stop.clone();
+    for run in 1..=options.runs {
+        tokio::select! {
+            () = tokio::time::sleep_until(next_start) => {}
+            _ = stop.wait_for(|&stop| stop) => break,
+        }
+        next_start = tokio::time::Instant::now() + options.interval;
+        if options.runs > 1 {
+            ctx.notice(format!("🔁 Run {} of {}", run, options.runs));
+        }
+        let (result, outcomes, failed) = run_once(&ctx).await?;
+        failed_outputs += failed;
+        failures.extend(
+            outcomes
+                .into_iter()
+                .filter(|o| !o.passed())
+                .map(|o| (run, o)),
+        );
+        results.push(result);
+        if *ctx.stop.borrow() {
+            break;
+        }
+        if run < options.runs && !ctx.quiet {
+            println!();
+        }
+    }
+    let runs = results.len() as u32;
+    let stopped = *ctx.stop.borrow();
+    if stopped && !options.thresholds.is_empty() {
+        eprintln!("⚠️  Thresholds were not checked for the stopped run");
+    }
+
+    if let Some(batch_id) = ctx.batch_id.as_ref().filter(|_| runs > 0) {
+        let summaries = batch::summarize(&results);
+        if ctx.markdown_stdout {
+            print!("{}", report::markdown::batch(&summaries, runs));
+        } else if !ctx.quiet {
+            println!();
+            output::print_batch_summary(&summaries, runs);
+        }
+        if options.save {
+            ctx.notice(format!("💾 All runs saved under batch {}", batch_id));
+        }
+    }
+
+    if !failures.is_empty() {
+        if !ctx.quiet {
+            eprintln!();
+        }
+        eprintln!(
+            "❌ {} of {} thresholds failed:",
+            failures.len(),
+            options.thresholds.len() * runs as usize
+        );
+        for (run, failure) in &failures {
+            if options.runs > 1 {
+                eprintln!("   - run {}: {}", run, failure.describe_failure());
+            } else {
+                eprintln!("   - {}", failure.describe_failure());
+            }
+        }
+        let outcomes: Vec<Outcome> = failures.into_iter().map(|(_, o)| o).collect();
+        return Ok(thresholds::exit_code(&outcomes));
+    }
+
+    if stopped {
+        // Like a process killed by SIGINT, so scripts can tell.
+        return Ok(EXIT_INTERRUPTED);
+    }
+
+    if failed_outputs > 0 {
+        eprintln!(
+            "❌ {} output(s) could not be written; see the errors above",
+            failed_outputs
+        );
+        return Ok(EXIT_OUTPUT_FAILED);
+    }
+
+    if !ctx.quiet {
+        println!();
+        if !options.thresholds.is_empty() {
+            println!("✅ All thresholds passed");
+        }
+        println!("✅ Speed test completed successfully!");
+    }
+
+    Ok(0)
+}
+
+/// The first Ctrl-C turns the returned flag true so the running test stops
+/// and keeps what it has measured; the second exits at once.
+fn stop_on_ctrl_c() -> watch::Receiver<bool> {
+    let (stop, stopped) = watch::channel(false);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        eprintln!("\n⏹️  Stopping with the results so far (Ctrl-C again to abort)");
+        let _ = stop.send(true);
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("\n❌ Aborted");
+            std::process::exit(EXIT_INTERRUPTED.into());
+        }
+    });
+    stopped
+}
+
+/// Runs a single test and writes every requested output for it. An output
+/// that fails is reported and the others are still written; the count of
+/// failed outputs is returned with the result.
+async fn run_once(ctx: &RunContext) -> Result<(TestResult, Vec<Outcome>, usize)> {
+    let options = &ctx.options;
+    let speed_test = ctx.speed_test();
+    let mut recording = Recording::new();
+    let result = speed_test
+        .run_test_with_progress(
+            ctx.duration,
+            options.method.connections,
+            !options.method.download,
+            !options.method.upload,
+            |progress| {
+                recording.record(&progress, chrono::Utc::now());
+                if options.stream_json {
+                    stream::emit(&StreamEvent::from_progress(progress));
+                } else if !ctx.quiet {
+                    ctx.ui.print_progress(progress);
+                }
+            },
+        )
+        .await?;
+
+    let quality_score = get_quality_score(result.download_speed, result.upload_speed, result.ping);
+    let quality_desc = get_quality_description(quality_score);
+    let document = ResultDocument::new(&result, quality_score, quality_desc, options.tag.clone());
+    let outcomes = thresholds::evaluate(&options.thresholds, &result);
+
+    if options.quick {
+        println!("{}", output::quick_line(&result));
+    } else if ctx.markdown_stdout {
+        // A batch prints one summary instead of every result.
+        if ctx.batch_id.is_none() {
+            print!("{}", report::markdown::result(&document));
+            if let (Some(baseline), Some(days)) = (&ctx.baseline, options.compare) {
+                println!();
+                print!(
+                    "{}",
+                    report::markdown::comparison(&document, baseline, days)
+                );
+            }
+        }
+    } else if options.stream_json {
+        stream::emit(&StreamEvent::Result {
+            timestamp: chrono::Utc::now(),
+            result: &document,
+        });
+    } else if !ctx.quiet {
+        println!();
+        output::print_result(&result, quality_score, options.max_bytes);
+        if let (Some(baseline), Some(days)) = (&ctx.baseline, options.compare) {
+            println!();
+            output::print_comparison(&document, baseline, days);
+        }
+    }
+
+    // History first, so the run is kept whichever output fails.
+    let mut failed_outputs = 0;
+    let mut check = |written: Result<()>| {
+        if let Err(e) = written {
+            eprintln!("❌ {:#}", e);
+            failed_outputs += 1;
+        }
+    };
+    check(save(ctx, &result).await);
+    check(export_file(ctx, &document));
+    check(write_influx(ctx, &result, &recording).await);
+    check(export_otlp(ctx, &result, &recording).await);
+    check(publish_mqtt(ctx, &result).await);
+    check(write_junit(ctx, &result, &outcomes));
+    check(write_report(ctx, &result, &recording).await);
+
+    Ok((result, outcomes, failed_outputs))
+}
+
+async fn save(ctx: &RunContext, result: &TestResult) -> Result<()> {
+    let options = &ctx.options;
+    if !options.save {
+        return Ok(());
+    }
+    HistoryManager::new()
+        .await?
+        .add_entry(HistoryEntry::from_result(
+            result,
+            options.tag.clone(),
+            ctx.batch_id.clone(),
+        ))
+        .await?;
+    ctx.notice("💾 Results saved to history".to_string());
+    if let Some(tag) = &options.tag {
+        ctx.notice(format!("🏷️  Tag: {}", tag));
+    }
+    Ok(())
+}
+
+fn export_file(ctx: &RunContext, document: &ResultDocument) -> Result<()> {
+    let (Some(path), Some(format)) = (&ctx.options.export, ctx.export_format) else {
+        return Ok(());
+    };
+    export::export(document, path, format)?;
+    ctx.notice(format!("📁 Results exported to: {}", path.display()));
+    Ok(())
+}
+
+async fn write_influx(ctx: &RunContext, result: &TestResult, recording: &Recording) -> Result<()> {
+    let options = &ctx.options;
+    let (Some(dest), Some(influx)) = (&ctx.influx, &options.influx) else {
+        return Ok(());
+    };
+    let encoder = influx::LineEncoder {
+        measurement: influx.measurement.clone(),
+        tag: options.tag.clone(),
+        interface: options.interface.clone().or_else(utils::default_interface),
+    };
+    let samples: &[_] = if influx.samples {
+        &recording.samples
+    } else {
+        &[]
+    };
+    dest.write(&encoder.encode(result, samples)).await?;
+    if !dest.is_stdout() {
+        ctx.notice(format!(
+            "📈 Results written to InfluxDB: {}",
+            influx.destination
+        ));
+    }
+    Ok(())
+}
+
+async fn export_otlp(ctx: &RunContext, result: &TestResult, recording: &Recording) -> Result<()> {
+    let options = &ctx.options;
+    let Some(otlp) = &options.otlp else {
+        return Ok(());
+    };
+    otlp::OtlpExporter::new(
+        &otlp.endpoint,
+        otlp.headers.clone(),
+        otlp.traces,
+        options.tag.as_deref(),
+    )
+    .export(result, &recording.phases)
+    .await?;
+    ctx.notice(format!(
+        "🔭 Results exported over OTLP to: {}",
+        otlp.endpoint
+    ));
+    Ok(())
+}
+
+async fn publish_mqtt(ctx: &RunContext, result: &TestResult) -> Result<()> {
+    let Some(publisher) = &ctx.mqtt else {
+        return Ok(());
+    };
+    publisher.publish(result).await?;
+    ctx.notice(format!("🏠 Results published to MQTT: {}", publisher.url()));
+    Ok(())
+}
+
+fn write_junit(ctx: &RunContext, result: &TestResult, outcomes: &[Outcome]) -> Result<()> {
+    let options = &ctx.options;
+    let Some(path) = &options.junit else {
+        return Ok(());
+    };
+    let xml = report::junit::render(result, outcomes, options.tag.as_deref());
+    std::fs::write(path, xml).with_context(|| format!("Failed to write {}", path.display()))?;
+    ctx.notice(format!("🧪 JUnit report written to: {}", path.display()));
+    Ok(())
+}
+
+async fn write_report(ctx: &RunContext, result: &TestResult, recording: &Recording) -> Result<()> {
+    let options = &ctx.options;
+    let Some(path) = &options.report else {
+        return Ok(());
+    };
+    let history = match options.report_days {
+        Some(days) => Some((
+            days,
+            HistoryManager::new().await?.get_entries_since(days).await?,
+        )),
+        None => None,
+    };
+    let interface = options.interface.clone().or_else(utils::default_interface);
+    let html = report::html::render(&report::html::ReportInput {
+        result,
+        recording,
+        tag: options.tag.as_deref(),
+        interface: interface.as_deref(),
+        history: history
+            .as_ref()
+            .map(|(days, entries)| (*days, entries.as_slice())),
+    });
+    std::fs::write(path, html).with_context(|| format!("Failed to write {}", path.display()))?;
+    ctx.notice(format!("📄 Report written to: {}", path.display()));
+    Ok(())
+}
@@ -0,0 +1,262 @@
+//! Pass/fail limits checked against a finished test.
+//!
+//! Each check has its own process exit code so scripts can tell which limit
+//! failed. When several fail, the code of the first in [`Check`] order wins.
+
+use crate::network::TestResult;
+use crate::utils::get_quality_score;
+use serde::{Deserialize, Serialize};
+
+/// A measured quantity that can be given a limit. Config files name it in
+/// lowercase, with `loss` and `score` for the last two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Check {
+    Download,
+    Upload,
+    Latency,
+    Jitter,
+    Loss,
+    Score,
+}
+
+impl Check {
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::Download => "download",
+            Check::Upload => "upload",
+            Check::Latency => "latency",
+            Check::Jitter => "jitter",
+            Check::Loss => "packet loss",
+            Check::Score => "quality score",
+        }
+    }
+
+    /// The name as a heading, e.g. `Packet loss`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Check::Download => "Download",
+            Check::Upload => "Upload",
+            Check::Latency => "Latency",
+            Check::Jitter => "Jitter",
+            Check::Loss => "Packet loss",
+            Check::Score => "Quality score",
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Check::Download | Check::Upload => " Mbps",
+            Check::Latency | Check::Jitter => " ms",
+            Check::Loss => "%",
+            Check::Score => "",
+        }
+    }
+
+    /// Whether the limit is a minimum (higher is better) or a maximum.
+    pub fn is_minimum(self) -> bool {
+        matches!(self, Check::Download | Check::Upload | Check::Score)
+    }
+
+    /// A measured value with this check's precision and unit.
+    pub fn format_value(self, value: f64) -> String {
+        match self {
+            Check::Score => format!("{:.0}", value),
+            _ => format!("{:.1}{}", value, self.unit()),
+        }
+    }
+
+    /// The process exit code used when this check fails.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Check::Download => 10,
+            Check::Upload => 11,
+            Check::Latency => 12,
+            Check::Jitter => 13,
+            Check::Loss => 14,
+            Check::Score => 15,
+        }
+    }
+
+    /// The measured value, or `None` when its phase was skipped.
+    pub fn measure(self, result: &TestResult) -> Option<f64> {
+        match self {
+            Check::Download => result.method.download.then_some(result.download_speed),
+            Check::Upload => result.method.upload.then_some(result.upload_speed),
+            Check::Latency => Some(result.ping),
+            Check::Jitter => Some(result.jitter),
+            Check::Loss => Some(result.packet_loss),
+            Check::Score => Some(f64::from(get_quality_score(
+                result.download_speed,
+                result.upload_speed,
+                result.ping,
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Threshold {
+    pub check: Check,
+    pub limit: f64,
+}
+
+impl Threshold {
+    /// E.g. `download >= 50 Mbps`.
+    pub fn describe(&self) -> String {
+        let op = if self.check.is_minimum() { ">=" } else { "<=" };
+        format!(
+            "{} {} {}{}",
+            self.check.name(),
+            op,
+            self.limit,
+            self.check.unit()
+        )
+    }
+}
+
+/// The result of checking one threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub threshold: Threshold,
+    /// `None` when the phase was skipped and nothing was checked.
+    pub measured: Option<f64>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.measured.is_none_or(|value| {
+            if self.threshold.check.is_minimum() {
+                value >= self.threshold.limit
+            } else {
+                value <= self.threshold.limit
+            }
+        })
+    }
+
+    /// E.g. `download 42.1 Mbps is below the minimum of 50 Mbps`.
+    pub fn describe_failure(&self) -> String {
+        let check = self.threshold.check;
+        let direction = if check.is_minimum() {
+            "below the minimum"
+        } else {
+            "above the maximum"
+        };
+        format!(
+            "{} {} is {} of {}{}",
+            check.name(),
+            check.format_value(self.measured.unwrap_or_default()),
+            direction,
+            self.threshold.limit,
+            check.unit()
+        )
+    }
+}
+
+/// The exit code for a set of outcomes: 0 when all passed, otherwise the
+/// code of the first failing check in [`Check`] order.
+pub fn exit_code(outcomes: &[Outcome]) -> u8 {
+    outcomes
+        .iter()
+        .filter(|o| !o.passed())
+        .map(|o| o.threshold.check)
+        .min_by_key(|check| check.exit_code())
+        .map_or(0, Check::exit_code)
+}
+
+/// Checks `thresholds` against `result`. A partial result is not checked at
+/// all: phases it never reached would pass as skipped and the ones it cut
+/// short would fail on a fraction of the test.
+pub fn evaluate(thresholds: &[Threshold], result: &TestResult) -> Vec<Outcome> {
+    if result.partial {
+        return Vec::new();
+    }
+    thresholds
+        .iter()
+        .map(|&threshold| Outcome {
+            threshold,
+            measured: threshold.check.measure(result),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::speedtest::TestMethod;
+
+    #[test]
+    fn checks_minimums_maximums_and_skipped_phases() {
+        let result = TestResult {
+            download_speed: 42.1,
+            upload_speed: 0.0,
+            test_duration: 20.0,
+            method: TestMethod {
+                upload: false,
+                ..TestResult::sample().method
+            },
+            ..TestResult::sample()
+        };
+        let thresholds = [
+            Threshold {
+                check: Check::Download,
+                limit: 50.0,
+            },
+            Threshold {
+                check: Check::Upload,
+                limit: 10.0,
+            },
+            Threshold {
+                check: Check::Score,
+                limit: 80.0,
+            },
+            Threshold {
+                check: Check::Latency,
+                limit: 20.0,
+            },
+        ];
+
+        let outcomes = evaluate(&thresholds, &result);
+
+        let passed: Vec<bool> = outcomes.iter().map(Outcome::passed).collect();
+        assert_eq!(passed, [false, true, false, true]);
+        assert_eq!(outcomes[1].measured, None);
+        assert_eq!(
+            outcomes[0].describe_failure(),
+            "download 42.1 Mbps is below the minimum of 50 Mbps"
+        );
+        assert_eq!(
+            outcomes[2].describe_failure(),
+            "quality score 72 is below the minimum of 80"
+        );
+        assert_eq!(thresholds[3].describe(), "latency <= 20 ms");
+        assert_eq!(exit_code(&outcomes), 10);
+        assert_eq!(exit_code(&outcomes[1..]), 15);
+        assert_eq!(exit_code(&outcomes[3..]), 0);
+    }
+
+    #[test]
+    fn partial_results_are_not_checked() {
+        let result = TestResult {
+            partial: true,
+            method: TestMethod {
+                upload: false,
+                ..TestResult::sample().method
+            },
+            ..TestResult::sample()
+        };
+        let thresholds = [
+            Threshold {
+                check: Check::Upload,
+                limit: 10.0,
+            },
+            Threshold {
+                check: Check::Download,
+                limit: 1000.0,
+            },
+        ];
+
+        assert!(evaluate(&thresholds, &result).is_empty());
+        assert!(!evaluate(&thresholds, &TestResult::sample()).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use super::{interface_counters, InterfaceCounters};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// Throughput of an interface over one interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandwidthSample {
+    pub timestamp: DateTime<Utc>,
+    pub download_mbps: f64,
+    pub upload_mbps: f64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// Summary of one monitoring run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandwidthHistory {
+    pub interface: String,
+    pub samples: Vec<BandwidthSample>,
+    pub avg_download_mbps: f64,
+    pub avg_upload_mbps: f64,
+    pub peak_download_mbps: f64,
+    pub peak_upload_mbps: f64,
+    /// Bytes received and sent during the run.
+    pub total_bytes_transferred: u64,
+}
+
+impl BandwidthHistory {
+    fn from_samples(interface: &str, samples: Vec<BandwidthSample>) -> Self {
+        let count = samples.len().max(1) as f64;
+        let sum = |f: fn(&BandwidthSample) -> f64| samples.iter().map(f).sum::<f64>();
+        let peak = |f: fn(&BandwidthSample) -> f64| samples.iter().map(f).fold(0.0, f64::max);
+        Self {
+            interface: interface.to_string(),
+            avg_download_mbps: sum(|s| s.download_mbps) / count,
+            avg_upload_mbps: sum(|s| s.upload_mbps) / count,
+            peak_download_mbps: peak(|s| s.download_mbps),
+            peak_upload_mbps: peak(|s| s.upload_mbps),
+            total_bytes_transferred: samples.iter().map(|s| s.rx_bytes + s.tx_bytes).sum(),
+            samples,
+        }
+    }
+}
+
+/// Samples the traffic of one interface from its kernel counters. This
+/// measures everything the machine sends and receives, not a test.
+pub struct BandwidthMonitor {
+    interface: String,
+    interval: Duration,
+    history_size: usize,
+    history: VecDeque<BandwidthSample>,
+}
+
+impl BandwidthMonitor {
+    pub fn new(interface: impl Into<String>) -> Self {
+        Self {
+            interface: interface.into(),
+            interval: Duration::from_secs(1),
+            history_size: 60,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Number of recent samples kept across runs.
+    pub fn with_history_size(mut self, history_size: usize) -> Self {
+        self.history_size = history_size;
+        self
+    }
+
+    /// Recent samples, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &BandwidthSample> {
+        self.history.iter()
+    }
+
+    /// Samples the interface every interval until `duration` has passed.
+    pub async fn monitor_for_duration(&mut self, duration: Duration) -> Result<BandwidthHistory> {
+        let started = Instant::now();
+        let mut previous = interface_counters(&self.interface)?;
+        let mut previous_at = Instant::now();
+        let mut samples = Vec::new();
+        while started.elapsed() < duration {
+            sleep(
+                self.interval
+                    .min(duration.saturating_sub(started.elapsed())),
+            )
+            .await;
+            let current = interface_counters(&self.interface)?;
+            let sample = sample(&previous, &current, previous_at.elapsed());
+            previous = current;
+            previous_at = Instant::now();
+
+            self.history.push_back(sample);
+            while self.history.len() > self.history_size {
+                self.history.pop_front();
+            }
+            samples.push(sample);
+        }
+        Ok(BandwidthHistory::from_samples(&self.interface, samples))
+    }
+}
+
+fn sample(
+    previous: &InterfaceCounters,
+    current: &InterfaceCounters,
+    elapsed: Duration,
+) -> BandwidthSample {
+    // Counters restart from zero when an interface is reset.
+    let rx_bytes = current.rx_bytes.saturating_sub(previous.rx_bytes);
+    let tx_bytes = current.tx_bytes.saturating_sub(previous.tx_bytes);
+    let mbps = |bytes: u64| bytes as f64 * 8.0 / 1e6 / elapsed.as_secs_f64().max(1e-3);
+    BandwidthSample {
+        timestamp: Utc::now(),
+        download_mbps: mbps(rx_bytes),
+        upload_mbps: mbps(tx_bytes),
+        rx_bytes,
+        tx_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_counter_deltas_into_rates() {
+        let counters = |rx_bytes, tx_bytes| InterfaceCounters {
+            rx_bytes,
+            tx_bytes,
+            ..Default::default()
+        };
+        let samples = vec![
+            sample(
+                &counters(0, 0),
+                &counters(12_500_000, 2_500_000),
+                Duration::from_secs(2),
+            ),
+            sample(
+                &counters(0, 0),
+                &counters(2_500_000, 500_000),
+                Duration::from_secs(2),
+            ),
+        ];
+        assert_eq!(samples[0].download_mbps, 50.0);
+        assert_eq!(samples[0].upload_mbps, 10.0);
+
+        let history = BandwidthHistory::from_samples("eth0", samples);
+        assert_eq!(history.avg_download_mbps, 30.0);
+        assert_eq!(history.peak_upload_mbps, 10.0);
+        assert_eq!(history.total_bytes_transferred, 18_000_000);
+
+        // A reset counter is not a negative rate.
+        assert_eq!(
+            sample(&counters(500, 0), &counters(100, 0), Duration::from_secs(1)).rx_bytes,
+            0
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use crate::utils;
+use anyhow::Result;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+use tokio::time::timeout;
+
+/// Public anycast resolvers reached over HTTPS to test connectivity without
+/// depending on DNS.
+const CONNECTIVITY_TARGETS: [&str; 2] = ["1.1.1.1:443", "8.8.8.8:443"];
+const DNS_TEST_HOST: &str = "example.com";
+const PROXY_VARS: [&str; 6] = [
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticStatus {
+    Pass,
+    Warning,
+    Fail,
+    Info,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticResult {
+    pub test_name: String,
+    pub status: DiagnosticStatus,
+    pub details: String,
+    pub recommendations: Vec<String>,
+}
+
+impl DiagnosticResult {
+    fn new(test_name: &str, status: DiagnosticStatus, details: String) -> Self {
+        Self {
+            test_name: test_name.to_string(),
+            status,
+            details,
+            recommendations: Vec::new(),
+        }
+    }
+
+    fn recommend(mut self, recommendation: &str) -> Self {
+        self.recommendations.push(recommendation.to_string());
+        self
+    }
+}
+
+/// Checks the pieces a speed test depends on: a default route, the MTU,
+/// DNS, direct connectivity and proxies.
+pub struct NetworkDiagnostics {
+    timeout: Duration,
+}
+
+impl NetworkDiagnostics {
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(3),
+        }
+    }
+
+    /// How long each network check may take.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs every check; a failing check is a result, not an error.
+    pub async fn run_full_diagnostics(&self) -> Result<Vec<DiagnosticResult>> {
+        let interface = utils::default_interface();
+        Ok(vec![
+            default_route(interface.as_deref()),
+            mtu(interface.as_deref()),
+            self.dns().await,
+            self.connectivity().await,
+            proxy(),
+        ])
+    }
+
+    async fn dns(&self) -> DiagnosticResult {
+        const NAME: &str = "DNS resolution";
+        let started = Instant::now();
+        let answer = timeout(self.timeout, lookup_host((DNS_TEST_HOST, 443))).await;
+        match answer.map(|lookup| lookup.map(|mut addrs| addrs.next().is_some())) {
+            Ok(Ok(false)) => DiagnosticResult::new(
+                NAME,
+                DiagnosticStatus::Fail,
+                format!("{} has no addresses", DNS_TEST_HOST),
+            ),
+            Ok(Ok(true)) => {
+                let ms = started.elapsed().as_secs_f64() * 1000.0;
+                let details = format!("Resolved {} in {:.1} ms", DNS_TEST_HOST, ms);
+                if ms > 200.0 {
+                    DiagnosticResult::new(NAME, DiagnosticStatus::Warning, details)
+                        .recommend("DNS is slow; try a nearby or public resolver such as 1.1.1.1")
+                } else {
+                    DiagnosticResult::new(NAME, DiagnosticStatus::Pass, details)
+                }
+            }
+            Ok(Err(e)) => DiagnosticResult::new(
+                NAME,
+                DiagnosticStatus::Fail,
+                format!("Could not resolve {}: {}", DNS_TEST_HOST, e),
+            )
+            .recommend("Check the resolvers in /etc/resolv.conf"),
+            Err(_) => DiagnosticResult::new(
+                NAME,
+                DiagnosticStatus::Fail,
+                format!("No answer for {} within {:?}", DNS_TEST_HOST, self.timeout),
+            )
+            .recommend("Check the resolvers in /etc/resolv.conf"),
+        }
+    }
+
+    async fn connectivity(&self) -> DiagnosticResult {
+        const NAME: &str = "Internet connectivity";
+        let mut failures = Vec::new();
+        for target in CONNECTIVITY_TARGETS {
+            let addr: SocketAddr = target.parse().expect("valid target address");
+            let started = Instant::now();
+            match timeout(self.timeout, TcpStream::connect(addr)).await {
+                Ok(Ok(_)) => {
+                    let ms = started.elapsed().as_secs_f64() * 1000.0;
+                    let details = format!("Connected to {} in {:.1} ms", target, ms);
+                    return if ms > 150.0 {
+                        DiagnosticResult::new(NAME, DiagnosticStatus::Warning, details).recommend(
+                            "Connection setup is slow; check for congestion or a distant gateway",
+                        )
+                    } else {
+                        DiagnosticResult::new(NAME, DiagnosticStatus::Pass, details)
+                    };
+                }
+                Ok(Err(e)) => failures.push(format!("{}: {}", target, e)),
+                Err(_) => failures.push(format!("{}: timed out", target)),
+            }
+        }
+        DiagnosticResult::new(NAME, DiagnosticStatus::Fail, failures.join(", "))
+            .recommend("Check the cable or Wi-Fi link and that a firewall allows outgoing HTTPS")
+    }
+}
+
+impl Default for NetworkDiagnostics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_route(interface: Option<&str>) -> DiagnosticResult {
+    const NAME: &str = "Default route";
+    match interface {
+        Some(interface) => DiagnosticResult::new(
+            NAME,
+            DiagnosticStatus::Pass,
+            format!("Default route via {}", interface),
+        ),
+        None if std::path::Path::new("/proc/net/route").exists() => {
+            DiagnosticResult::new(NAME, DiagnosticStatus::Fail, "No default route".to_string())
+                .recommend("Bring a network interface up and check that it has a gateway")
+        }
+        None => DiagnosticResult::new(
+            NAME,
+            DiagnosticStatus::Info,
+            "Routes can only be read on Linux".to_string(),
+        ),
+    }
+}
+
+fn mtu(interface: Option<&str>) -> DiagnosticResult {
+    const NAME: &str = "MTU";
+    let mtu = interface.and_then(|interface| {
+        std::fs::read_to_string(format!("/sys/class/net/{}/mtu", interface))
+            .ok()?
+            .trim()
+            .parse::<u32>()
+            .ok()
+    });
+    match mtu {
+        Some(mtu) if mtu < 1500 => DiagnosticResult::new(
+            NAME,
+            DiagnosticStatus::Warning,
+            format!("MTU is {}, below the usual 1500", mtu),
+        )
+        .recommend("A VPN or PPPoE link lowers the MTU; expect slightly lower throughput"),
+        Some(mtu) => DiagnosticResult::new(NAME, DiagnosticStatus::Pass, format!("MTU is {}", mtu)),
+        None => DiagnosticResult::new(
+            NAME,
+            DiagnosticStatus::Info,
+            "MTU of the default interface is unknown".to_string(),
+        ),
+    }
+}
+
+fn proxy() -> DiagnosticResult {
+    const NAME: &str = "Proxy";
+    let set: Vec<_> = PROXY_VARS
+        .iter()
+        .filter(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+        .copied()
+        .collect();
+    if set.is_empty() {
+        DiagnosticResult::new(
+            NAME,
+            DiagnosticStatus::Info,
+            "No proxy configured".to_string(),
+        )
+    } else {
+        DiagnosticResult::new(
+            NAME,
+            DiagnosticStatus::Info,
+            format!("Proxy set in {}", set.join(", ")),
+        )
+        .recommend("Results through a proxy measure the proxy's connection too")
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tokio::net::lookup_host;
+use tokio::time::timeout;
+
+/// Address records the system resolver can answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsRecordType {
+    A,
+    Aaaa,
+}
+
+impl DnsRecordType {
+    pub fn name(self) -> &'static str {
+        match self {
+            DnsRecordType::A => "A",
+            DnsRecordType::Aaaa => "AAAA",
+        }
+    }
+
+    fn matches(self, ip: &IpAddr) -> bool {
+        match self {
+            DnsRecordType::A => ip.is_ipv4(),
+            DnsRecordType::Aaaa => ip.is_ipv6(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsRecord {
+    pub record_type: DnsRecordType,
+    pub value: String,
+}
+
+/// The answer for one name. A lookup that failed has an `error` and no
+/// records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsResult {
+    pub query: String,
+    pub record_type: DnsRecordType,
+    pub records: Vec<DnsRecord>,
+    pub response_time_ms: f64,
+    pub error: Option<String>,
+}
+
+/// Resolves names through the system resolver, as the speed test does.
+pub struct DnsResolver {
+    timeout: Duration,
+}
+
+impl DnsResolver {
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn resolve(&self, name: &str, record_type: DnsRecordType) -> DnsResult {
+        resolve(name, record_type, self.timeout).await
+    }
+
+    /// Resolves all `names` concurrently; results keep the order of `names`.
+    pub async fn bulk_resolve<S: AsRef<str>>(
+        &self,
+        names: Vec<S>,
+        record_type: DnsRecordType,
+    ) -> Result<Vec<DnsResult>> {
+        let lookups: Vec<_> = names
+            .iter()
+            .map(|name| {
+                let name = name.as_ref().to_string();
+                let limit = self.timeout;
+                tokio::spawn(async move { resolve(&name, record_type, limit).await })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(lookups.len());
+        for lookup in lookups {
+            results.push(lookup.await.context("DNS lookup task failed")?);
+        }
+        Ok(results)
+    }
+}
+
+impl Default for DnsResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn resolve(name: &str, record_type: DnsRecordType, limit: Duration) -> DnsResult {
+    let started = Instant::now();
+    let answer = timeout(limit, lookup_host((name, 0))).await;
+    let response_time_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let (records, error) = match answer {
+        Ok(Ok(addrs)) => {
+            let mut ips: Vec<IpAddr> = Vec::new();
+            for ip in addrs.map(|addr| addr.ip()) {
+                if record_type.matches(&ip) && !ips.contains(&ip) {
+                    ips.push(ip);
+                }
+            }
+            let records: Vec<_> = ips
+                .into_iter()
+                .map(|ip| DnsRecord {
+                    record_type,
+                    value: ip.to_string(),
+                })
+                .collect();
+            let error = records
+                .is_empty()
+                .then(|| format!("No {} records", record_type.name()));
+            (records, error)
+        }
+        Ok(Err(e)) => (Vec::new(), Some(e.to_string())),
+        Err(_) => (Vec::new(), Some(format!("No answer within {:?}", limit))),
+    };
+    DnsResult {
+        query: name.to_string(),
+        record_type,
+        records,
+        response_time_ms,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn resolves_addresses_of_the_requested_family() {
+        let resolver = DnsResolver::new();
+        let results = resolver
+            .bulk_resolve(vec!["127.0.0.1", "::1"], DnsRecordType::A)
+            .await
+            .unwrap();
+        assert_eq!(results[0].query, "127.0.0.1");
+        assert_eq!(results[0].records[0].value, "127.0.0.1");
+        assert_eq!(results[0].error, None);
+        assert!(results[1].records.is_empty());
+        assert_eq!(results[1].error.as_deref(), Some("No A records"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Network diagnostic tools: interface counters, name resolution, route
+//! tracing, port and host scans, Wi-Fi surveys and quality analysis. Tools
+//! that read `/proc` or run system commands work on Linux only.
+
+pub mod bandwidth_monitor;
+pub mod diagnostics;
+pub mod dns_resolver;
+pub mod network_scanner;
+pub mod port_scanner;
+pub mod quality_analyzer;
+pub mod statistics_collector;
+pub mod traceroute;
+pub mod wifi_analyzer;
+
+use anyhow::{Context, Result};
+
+/// Cumulative counters of one interface from `/proc/net/dev`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InterfaceCounters {
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+}
+
+/// Reads the counters of `interface`.
+pub fn interface_counters(interface: &str) -> Result<InterfaceCounters> {
+    let data = std::fs::read_to_string("/proc/net/dev")
+        .context("Failed to read /proc/net/dev; interface counters need Linux")?;
+    parse_net_dev(&data, interface)
+        .with_context(|| format!("Interface {} not found in /proc/net/dev", interface))
+}
+
+fn parse_net_dev(data: &str, interface: &str) -> Option<InterfaceCounters> {
+    data.lines().skip(2).find_map(|line| {
+        let (name, counters) = line.split_once(':')?;
+        if name.trim() != interface {
+            return None;
+        }
+        let values: Vec<u64> = counters
+            .split_whitespace()
+            .map(|v| v.parse().ok())
+            .collect::<Option<_>>()?;
+        // Receive: bytes packets errs drop fifo frame compressed multicast,
+        // then the same for transmit.
+        (values.len() >= 11).then(|| InterfaceCounters {
+            rx_bytes: values[0],
+            rx_packets: values[1],
+            rx_errors: values[2],
+            tx_bytes: values[8],
+            tx_packets: values[9],
+            tx_errors: values[10],
+        })
+    })
+}
+
+/// Vendor of a MAC address, for a few well-known prefixes: virtual machine
+/// NICs and Raspberry Pi boards.
+pub fn mac_vendor(mac: &str) -> Option<&'static str> {
+    let prefix = mac.get(..8)?.to_ascii_lowercase().replace('-', ":");
+    let vendor = match prefix.as_str() {
+        "00:50:56" | "00:0c:29" | "00:05:69" => "VMware",
+        "08:00:27" => "VirtualBox",
+        "52:54:00" => "QEMU",
+        "00:15:5d" => "Hyper-V",
+        "00:16:3e" => "Xen",
+        "b8:27:eb" | "dc:a6:32" | "e4:5f:01" | "d8:3a:dd" | "2c:cf:67" => "Raspberry Pi",
+        _ => return None,
+    };
+    Some(vendor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_counters_of_one_interface() {
+        let data = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:  104890     1208    0    0    0     0          0         0   104890    1208    0    0    0     0       0          0
+  eth0: 98765432   81234    3    0    0     0          0       112  1234567   40321    1    0    0     0       0          0
+";
+        assert_eq!(
+            parse_net_dev(data, "eth0"),
+            Some(InterfaceCounters {
+                rx_bytes: 98765432,
+                rx_packets: 81234,
+                rx_errors: 3,
+                tx_bytes: 1234567,
+                tx_packets: 40321,
+                tx_errors: 1,
+            })
+        );
+        assert_eq!(parse_net_dev(data, "wlan0"), None);
+        assert_eq!(mac_vendor("B8:27:EB:12:34:56"), Some("Raspberry Pi"));
+        assert_eq!(mac_vendor("aa:bb:cc:dd:ee:ff"), None);
+    }
+}
@@ -0,0 +1,289 @@
+use super::mac_vendor;
+use super::port_scanner::PortScanner;
+use anyhow::{bail, Context, Result};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+/// Ports tried to find out whether a host is up. A refused connection
+/// counts: only a live host can refuse.
+const PING_PORTS: [u16; 4] = [80, 443, 22, 445];
+/// Largest range [`NetworkScanner::scan_network`] accepts (a /16).
+const MAX_HOSTS: u64 = 65_536;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanType {
+    /// Find live hosts only.
+    Ping,
+    /// Also check the common ports of each live host.
+    Full,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostResult {
+    pub target_ip: IpAddr,
+    /// From `/etc/hosts`.
+    pub hostname: Option<String>,
+    /// From the ARP cache, for hosts on the local link.
+    pub mac_address: Option<String>,
+    pub vendor: Option<String>,
+    pub response_time_ms: f64,
+    /// Open common ports, with [`ScanType::Full`].
+    pub open_ports: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkScanResult {
+    pub network_range: String,
+    pub hosts_scanned: usize,
+    /// Live hosts in address order.
+    pub alive_hosts: Vec<HostResult>,
+    pub scan_duration: Duration,
+}
+
+/// Finds live hosts with TCP connection attempts, which need no raw
+/// sockets. Only scan networks you are allowed to probe.
+pub struct NetworkScanner {
+    timeout: Duration,
+    max_threads: usize,
+}
+
+impl NetworkScanner {
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            max_threads: 64,
+        }
+    }
+
+    /// How long to wait for each host.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Most hosts probed at once.
+    pub fn with_max_threads(mut self, max_threads: usize) -> Self {
+        self.max_threads = max_threads.max(1);
+        self
+    }
+
+    /// Scans an IPv4 range given as CIDR (`192.168.1.0/24`), a span
+    /// (`192.168.1.1-192.168.1.10` or `192.168.1.1-10`) or one address.
+    pub async fn scan_network(
+        &self,
+        range: &str,
+        scan_type: ScanType,
+    ) -> Result<NetworkScanResult> {
+        let started = Instant::now();
+        let hosts = parse_range(range)?;
+        let permits = Arc::new(Semaphore::new(self.max_threads));
+        let mut probes = JoinSet::new();
+        for &ip in &hosts {
+            let permits = permits.clone();
+            let limit = self.timeout;
+            probes.spawn(async move {
+                let _permit = permits.acquire_owned().await?;
+                anyhow::Ok(is_alive(ip, limit).await.map(|ms| (ip, ms)))
+            });
+        }
+
+        let mut alive = Vec::new();
+        while let Some(result) = probes.join_next().await {
+            alive.extend(result.context("Host probe task failed")??);
+        }
+        alive.sort_by_key(|(ip, _)| *ip);
+
+        let arp = std::fs::read_to_string("/proc/net/arp").unwrap_or_default();
+        let etc_hosts = std::fs::read_to_string("/etc/hosts").unwrap_or_default();
+        let mut alive_hosts = Vec::with_capacity(alive.len());
+        for (ip, response_time_ms) in alive {
+            let mac_address = arp_entry(&arp, ip);
+            let open_ports = match scan_type {
+                ScanType::Ping => Vec::new(),
+                ScanType::Full => PortScanner::new()
+                    .with_timeout(self.timeout)
+                    .scan_common_ports(IpAddr::V4(ip))
+                    .await?
+                    .open_ports
+                    .iter()
+                    .map(|port| port.port)
+                    .collect(),
+            };
+            alive_hosts.push(HostResult {
+                target_ip: IpAddr::V4(ip),
+                hostname: hosts_entry(&etc_hosts, ip),
+                vendor: mac_address
+                    .as_deref()
+                    .and_then(mac_vendor)
+                    .map(str::to_string),
+                mac_address,
+                response_time_ms,
+                open_ports,
+            });
+        }
+
+        Ok(NetworkScanResult {
+            network_range: range.to_string(),
+            hosts_scanned: hosts.len(),
+            alive_hosts,
+            scan_duration: started.elapsed(),
+        })
+    }
+}
+
+impl Default for NetworkScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Round-trip time in milliseconds of the first answer on any of
+/// [`PING_PORTS`], or `None` if the host did not answer in time.
+async fn is_alive(ip: Ipv4Addr, limit: Duration) -> Option<f64> {
+    let started = Instant::now();
+    let mut attempts = JoinSet::new();
+    for port in PING_PORTS {
+        attempts.spawn(async move {
+            match TcpStream::connect(SocketAddr::from((ip, port))).await {
+                Ok(_) => true,
+                Err(e) => e.kind() == std::io::ErrorKind::ConnectionRefused,
+            }
+        });
+    }
+    let answered = async {
+        while let Some(attempt) = attempts.join_next().await {
+            if attempt.unwrap_or(false) {
+                return true;
+            }
+        }
+        false
+    };
+    // Dropping the set aborts the attempts still in flight.
+    matches!(timeout(limit, answered).await, Ok(true))
+        .then(|| started.elapsed().as_secs_f64() * 1000.0)
+}
+
+fn parse_range(range: &str) -> Result<Vec<Ipv4Addr>> {
+    let invalid = || {
+        format!(
+            "Invalid range '{}': expected e.g. 192.168.1.0/24 or 192.168.1.1-192.168.1.10 (IPv4 only)",
+            range
+        )
+    };
+    let (first, last) = if let Some((network, prefix)) = range.split_once('/') {
+        let network: Ipv4Addr = network.trim().parse().with_context(invalid)?;
+        let prefix: u32 = prefix.trim().parse().with_context(invalid)?;
+        if prefix > 32 {
+            bail!(invalid());
+        }
+        let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+        let first = u32::from(network) & mask;
+        let last = first | !mask;
+        // Leave out the network and broadcast addresses where they exist.
+        if prefix < 31 {
+            (first + 1, last - 1)
+        } else {
+            (first, last)
+        }
+    } else if let Some((start, end)) = range.split_once('-') {
+        let start: Ipv4Addr = start.trim().parse().with_context(invalid)?;
+        let end: Ipv4Addr = match end.trim().parse::<u8>() {
+            Ok(octet) => {
+                let [a, b, c, _] = start.octets();
+                Ipv4Addr::new(a, b, c, octet)
+            }
+            Err(_) => end.trim().parse().with_context(invalid)?,
+        };
+        (u32::from(start), u32::from(end))
+    } else {
+        let ip = u32::from(range.trim().parse::<Ipv4Addr>().with_context(invalid)?);
+        (ip, ip)
+    };
+    if first > last {
+        bail!("Invalid range '{}': the start is after the end", range);
+    }
+    if u64::from(last - first) + 1 > MAX_HOSTS {
+        bail!("Range '{}' is larger than a /16", range);
+    }
+    Ok((first..=last).map(Ipv4Addr::from).collect())
+}
+
+/// MAC address of `ip` in `/proc/net/arp`, if the entry is complete.
+fn arp_entry(arp: &str, ip: Ipv4Addr) -> Option<String> {
+    arp.lines().skip(1).find_map(|line| {
+        let columns: Vec<_> = line.split_whitespace().collect();
+        match columns[..] {
+            [address, _, "0x2", mac, ..] if address.parse() == Ok(ip) => Some(mac.to_string()),
+            _ => None,
+        }
+    })
+}
+
+/// First name of `ip` in an `/etc/hosts` file.
+fn hosts_entry(hosts: &str, ip: Ipv4Addr) -> Option<String> {
+    hosts.lines().find_map(|line| {
+        let mut columns = line.split('#').next()?.split_whitespace();
+        if columns.next()?.parse() != Ok(ip) {
+            return None;
+        }
+        columns.next().map(str::to_string)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(range: &str) -> (String, String, usize) {
+        let hosts = parse_range(range).unwrap();
+        (
+            hosts[0].to_string(),
+            hosts[hosts.len() - 1].to_string(),
+            hosts.len(),
+        )
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(
+            span("192.168.1.0/24"),
+            ("192.168.1.1".into(), "192.168.1.254".into(), 254)
+        );
+        assert_eq!(
+            span("192.168.1.1-192.168.1.10"),
+            ("192.168.1.1".into(), "192.168.1.10".into(), 10)
+        );
+        assert_eq!(
+            span("10.0.0.250-10.0.1.5"),
+            ("10.0.0.250".into(), "10.0.1.5".into(), 12)
+        );
+        assert_eq!(span("10.0.0.5-7").2, 3);
+        assert_eq!(span("10.0.0.5/32").2, 1);
+        assert!(parse_range("10.0.0.9-3").is_err());
+        assert!(parse_range("10.0.0.0/8").is_err());
+        assert!(parse_range("fe80::1/64").is_err());
+    }
+
+    #[test]
+    fn reads_arp_and_hosts_entries() {
+        let arp = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         b8:27:eb:12:34:56     *        eth0
+192.168.1.7      0x1         0x0         00:00:00:00:00:00     *        eth0
+";
+        let gateway = Ipv4Addr::new(192, 168, 1, 1);
+        assert_eq!(
+            arp_entry(arp, gateway).as_deref(),
+            Some("b8:27:eb:12:34:56")
+        );
+        assert_eq!(arp_entry(arp, Ipv4Addr::new(192, 168, 1, 7)), None);
+
+        let hosts = "127.0.0.1 localhost\n# 192.168.1.1 old\n192.168.1.1 router router.lan\n";
+        assert_eq!(hosts_entry(hosts, gateway).as_deref(), Some("router"));
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+/// Ports checked by [`PortScanner::scan_common_ports`], with their usual
+/// service.
+pub const COMMON_PORTS: [(u16, &str); 24] = [
+    (21, "ftp"),
+    (22, "ssh"),
+    (23, "telnet"),
+    (25, "smtp"),
+    (53, "dns"),
+    (80, "http"),
+    (110, "pop3"),
+    (143, "imap"),
+    (443, "https"),
+    (445, "smb"),
+    (465, "smtps"),
+    (587, "submission"),
+    (993, "imaps"),
+    (995, "pop3s"),
+    (1883, "mqtt"),
+    (3306, "mysql"),
+    (3389, "rdp"),
+    (5432, "postgresql"),
+    (6379, "redis"),
+    (8080, "http-alt"),
+    (8443, "https-alt"),
+    (8883, "mqtts"),
+    (9090, "prometheus"),
+    (9516, "pingtest"),
+];
+
+/// Usual service on `port`, if it is one of [`COMMON_PORTS`].
+pub fn service_name(port: u16) -> Option<&'static str> {
+    COMMON_PORTS
+        .iter()
+        .find(|(common, _)| *common == port)
+        .map(|(_, service)| *service)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortState {
+    /// A connection was accepted.
+    Open,
+    /// The host refused the connection.
+    Closed,
+    /// No answer before the timeout, or the connection was dropped.
+    Filtered,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortResult {
+    pub port: u16,
+    pub state: PortState,
+    pub service: Option<String>,
+    pub response_time_ms: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortScanResult {
+    pub target: IpAddr,
+    pub ports_scanned: usize,
+    pub open_ports: Vec<PortResult>,
+    pub closed_ports: Vec<PortResult>,
+    pub filtered_ports: Vec<PortResult>,
+    pub scan_duration: Duration,
+}
+
+/// TCP connect scanner. Only scan hosts you are allowed to probe.
+pub struct PortScanner {
+    timeout: Duration,
+    max_threads: usize,
+}
+
+impl PortScanner {
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            max_threads: 50,
+        }
+    }
+
+    /// How long to wait for each connection.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Most connection attempts in flight at once.
+    pub fn with_max_threads(mut self, max_threads: usize) -> Self {
+        self.max_threads = max_threads.max(1);
+        self
+    }
+
+    pub async fn scan_common_ports(&self, target: IpAddr) -> Result<PortScanResult> {
+        let ports: Vec<u16> = COMMON_PORTS.iter().map(|(port, _)| *port).collect();
+        self.scan_ports(target, &ports).await
+    }
+
+    pub async fn scan_ports(&self, target: IpAddr, ports: &[u16]) -> Result<PortScanResult> {
+        let started = Instant::now();
+        let permits = Arc::new(Semaphore::new(self.max_threads));
+        let mut probes = JoinSet::new();
+        for &port in ports {
+            let permits = permits.clone();
+            let limit = self.timeout;
+            probes.spawn(async move {
+                let _permit = permits.acquire_owned().await?;
+                anyhow::Ok(probe(SocketAddr::new(target, port), limit).await)
+            });
+        }
+
+        let mut results = Vec::with_capacity(ports.len());
+        while let Some(result) = probes.join_next().await {
+            results.push(result.context("Port probe task failed")??);
+        }
+        results.sort_by_key(|result| result.port);
+
+        let mut scan = PortScanResult {
+            target,
+            ports_scanned: results.len(),
+            open_ports: Vec::new(),
+            closed_ports: Vec::new(),
+            filtered_ports: Vec::new(),
+            scan_duration: started.elapsed(),
+        };
+        for result in results {
+            match result.state {
+                PortState::Open => scan.open_ports.push(result),
+                PortState::Closed => scan.closed_ports.push(result),
+                PortState::Filtered => scan.filtered_ports.push(result),
+            }
+        }
+        Ok(scan)
+    }
+}
+
+impl Default for PortScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn probe(addr: SocketAddr, limit: Duration) -> PortResult {
+    let started = Instant::now();
+    let state = match timeout(limit, TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => PortState::Open,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => PortState::Closed,
+        Ok(Err(_)) | Err(_) => PortState::Filtered,
+    };
+    PortResult {
+        port: addr.port(),
+        state,
+        service: service_name(addr.port()).map(str::to_string),
+        response_time_ms: started.elapsed().as_secs_f64() * 1000.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_open_and_closed_ports() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        // Bound and dropped, so nothing listens there any more.
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let scan = PortScanner::new()
+            .with_max_threads(1)
+            .scan_ports("127.0.0.1".parse().unwrap(), &[closed, open])
+            .await
+            .unwrap();
+        assert_eq!(scan.ports_scanned, 2);
+        assert_eq!(scan.open_ports.len(), 1);
+        assert_eq!(scan.open_ports[0].port, open);
+        assert_eq!(scan.closed_ports[0].port, closed);
+        assert_eq!(service_name(443), Some("https"));
+    }
+}
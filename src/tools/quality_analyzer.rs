@@ -0,0 +1,315 @@
+use crate::network::{SpeedTest, TestResult};
+use crate::utils::get_quality_score;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// Length of the download phase of each sample in seconds.
+const SAMPLE_SECONDS: u64 = 3;
+const SAMPLE_CONNECTIONS: u32 = 4;
+
+/// One short download and latency measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityMetrics {
+    pub latency_ms: f64,
+    pub jitter_ms: f64,
+    pub packet_loss_percent: f64,
+    pub bandwidth_download_mbps: f64,
+    pub overall_quality_score: u8,
+}
+
+impl QualityMetrics {
+    fn from_result(result: &TestResult) -> Self {
+        Self {
+            latency_ms: result.ping,
+            jitter_ms: result.jitter,
+            packet_loss_percent: result.packet_loss,
+            bandwidth_download_mbps: result.download_speed,
+            overall_quality_score: get_quality_score(
+                result.download_speed,
+                result.upload_speed,
+                result.ping,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityAssessment {
+    Excellent,
+    Good,
+    Fair,
+    Poor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityTrend {
+    Improving,
+    Stable,
+    Degrading,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueType {
+    HighLatency,
+    HighJitter,
+    PacketLoss,
+    LowBandwidth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkIssue {
+    pub issue_type: IssueType,
+    pub severity: Severity,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityAnalysis {
+    pub test_count: usize,
+    pub samples: Vec<QualityMetrics>,
+    pub average_metrics: QualityMetrics,
+    pub overall_assessment: QualityAssessment,
+    pub quality_trend: QualityTrend,
+    /// Most severe first.
+    pub network_issues: Vec<NetworkIssue>,
+    pub recommendations: Vec<String>,
+}
+
+impl QualityAnalysis {
+    /// Analyzes samples in the order they were taken.
+    pub fn from_samples(samples: Vec<QualityMetrics>) -> Self {
+        let average_metrics = average(&samples);
+        let overall_assessment = match average_metrics.overall_quality_score {
+            80.. => QualityAssessment::Excellent,
+            60..=79 => QualityAssessment::Good,
+            40..=59 => QualityAssessment::Fair,
+            _ => QualityAssessment::Poor,
+        };
+
+        // Compares the mean score of the first and second half.
+        let half = samples.len() / 2;
+        let quality_trend = if half == 0 {
+            QualityTrend::Stable
+        } else {
+            let before = average(&samples[..half]).overall_quality_score;
+            let after = average(&samples[samples.len() - half..]).overall_quality_score;
+            match i16::from(after) - i16::from(before) {
+                6.. => QualityTrend::Improving,
+                ..=-6 => QualityTrend::Degrading,
+                _ => QualityTrend::Stable,
+            }
+        };
+
+        let mut network_issues = issues(&average_metrics);
+        network_issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+        let recommendations = network_issues
+            .iter()
+            .map(|issue| recommendation(issue.issue_type).to_string())
+            .collect();
+        Self {
+            test_count: samples.len(),
+            samples,
+            average_metrics,
+            overall_assessment,
+            quality_trend,
+            network_issues,
+            recommendations,
+        }
+    }
+}
+
+/// Repeats short speed tests over a period to judge how good and how stable
+/// a connection is.
+pub struct QualityAnalyzer {
+    test_duration: Duration,
+    test_interval: Duration,
+}
+
+impl QualityAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            test_duration: Duration::from_secs(60),
+            test_interval: Duration::from_secs(10),
+        }
+    }
+
+    /// How long to keep taking samples. At least one is always taken.
+    pub fn with_test_duration(mut self, test_duration: Duration) -> Self {
+        self.test_duration = test_duration;
+        self
+    }
+
+    /// Pause between samples.
+    pub fn with_test_interval(mut self, test_interval: Duration) -> Self {
+        self.test_interval = test_interval;
+        self
+    }
+
+    pub async fn analyze_network_quality(&self) -> Result<QualityAnalysis> {
+        let started = Instant::now();
+        let speed_test = SpeedTest::new();
+        let mut samples = Vec::new();
+        loop {
+            let result = speed_test
+                .run_test(SAMPLE_SECONDS, SAMPLE_CONNECTIONS, false, true)
+                .await?;
+            samples.push(QualityMetrics::from_result(&result));
+            if started.elapsed() + self.test_interval >= self.test_duration {
+                break;
+            }
+            sleep(self.test_interval).await;
+        }
+        Ok(QualityAnalysis::from_samples(samples))
+    }
+}
+
+impl Default for QualityAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn average(samples: &[QualityMetrics]) -> QualityMetrics {
+    let count = samples.len().max(1) as f64;
+    let mean = |f: fn(&QualityMetrics) -> f64| samples.iter().map(f).sum::<f64>() / count;
+    QualityMetrics {
+        latency_ms: mean(|m| m.latency_ms),
+        jitter_ms: mean(|m| m.jitter_ms),
+        packet_loss_percent: mean(|m| m.packet_loss_percent),
+        bandwidth_download_mbps: mean(|m| m.bandwidth_download_mbps),
+        overall_quality_score: mean(|m| f64::from(m.overall_quality_score)).round() as u8,
+    }
+}
+
+fn issues(metrics: &QualityMetrics) -> Vec<NetworkIssue> {
+    let latency = match metrics.latency_ms {
+        ms if ms > 100.0 => Some(Severity::High),
+        ms if ms > 50.0 => Some(Severity::Medium),
+        _ => None,
+    };
+    let jitter = match metrics.jitter_ms {
+        ms if ms > 30.0 => Some(Severity::High),
+        ms if ms > 10.0 => Some(Severity::Medium),
+        _ => None,
+    };
+    let loss = match metrics.packet_loss_percent {
+        percent if percent > 2.0 => Some(Severity::High),
+        percent if percent > 0.5 => Some(Severity::Medium),
+        percent if percent > 0.0 => Some(Severity::Low),
+        _ => None,
+    };
+    let bandwidth = match metrics.bandwidth_download_mbps {
+        mbps if mbps < 10.0 => Some(Severity::High),
+        mbps if mbps < 25.0 => Some(Severity::Medium),
+        _ => None,
+    };
+    [
+        (
+            IssueType::HighLatency,
+            latency,
+            format!("Average latency is {:.1} ms", metrics.latency_ms),
+        ),
+        (
+            IssueType::HighJitter,
+            jitter,
+            format!("Average jitter is {:.1} ms", metrics.jitter_ms),
+        ),
+        (
+            IssueType::PacketLoss,
+            loss,
+            format!(
+                "{:.1}% of latency probes were lost",
+                metrics.packet_loss_percent
+            ),
+        ),
+        (
+            IssueType::LowBandwidth,
+            bandwidth,
+            format!(
+                "Average download is {:.1} Mbps",
+                metrics.bandwidth_download_mbps
+            ),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(issue_type, severity, description)| {
+        Some(NetworkIssue {
+            issue_type,
+            severity: severity?,
+            description,
+        })
+    })
+    .collect()
+}
+
+fn recommendation(issue_type: IssueType) -> &'static str {
+    match issue_type {
+        IssueType::HighLatency => {
+            "Use a wired connection or a closer server; check for uploads saturating the link"
+        }
+        IssueType::HighJitter => {
+            "Jitter usually comes from congestion or Wi-Fi interference; try a wired connection or enable QoS on the router"
+        }
+        IssueType::PacketLoss => {
+            "Check cables and Wi-Fi signal strength; persistent loss upstream is worth reporting to your provider"
+        }
+        IssueType::LowBandwidth => {
+            "Compare with your plan's speed and retest at a quieter time; ask your provider if it stays low"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(latency_ms: f64, packet_loss_percent: f64, score: u8) -> QualityMetrics {
+        QualityMetrics {
+            latency_ms,
+            jitter_ms: 2.0,
+            packet_loss_percent,
+            bandwidth_download_mbps: 50.0,
+            overall_quality_score: score,
+        }
+    }
+
+    #[test]
+    fn judges_averages_issues_and_trend() {
+        let analysis = QualityAnalysis::from_samples(vec![
+            metrics(40.0, 0.0, 86),
+            metrics(60.0, 1.0, 80),
+            metrics(80.0, 2.0, 74),
+            metrics(140.0, 5.0, 68),
+        ]);
+        assert_eq!(analysis.test_count, 4);
+        assert_eq!(analysis.average_metrics.latency_ms, 80.0);
+        assert_eq!(analysis.average_metrics.overall_quality_score, 77);
+        assert_eq!(analysis.overall_assessment, QualityAssessment::Good);
+        assert_eq!(analysis.quality_trend, QualityTrend::Degrading);
+        let issues: Vec<_> = analysis
+            .network_issues
+            .iter()
+            .map(|issue| (issue.issue_type, issue.severity))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (IssueType::HighLatency, Severity::Medium),
+                (IssueType::PacketLoss, Severity::Medium)
+            ]
+        );
+        assert_eq!(analysis.recommendations.len(), 2);
+
+        let single = QualityAnalysis::from_samples(vec![metrics(20.0, 0.0, 94)]);
+        assert_eq!(single.quality_trend, QualityTrend::Stable);
+        assert!(single.network_issues.is_empty());
+    }
+}
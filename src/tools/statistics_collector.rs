@@ -0,0 +1,278 @@
+use super::{interface_counters, InterfaceCounters};
+use crate::utils;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// TCP states in `/proc/net/tcp`.
+const TCP_ESTABLISHED: &str = "01";
+const TCP_LISTEN: &str = "0A";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkStats {
+    pub interface_name: String,
+    /// Receive rate over the sampling interval.
+    pub bandwidth_download_mbps: f64,
+    /// Send rate over the sampling interval.
+    pub bandwidth_upload_mbps: f64,
+    /// Totals since the interface came up.
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+    pub packets_received: u64,
+    pub packets_sent: u64,
+    pub errors_received: u64,
+    pub errors_sent: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SystemStats {
+    /// Busy share of all CPUs over the sampling interval.
+    pub cpu_usage_percent: f64,
+    pub memory_usage_percent: f64,
+    /// One-minute load average.
+    pub load_average: f64,
+    pub processes_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// TCP sockets other than listeners, IPv4 and IPv6.
+    pub tcp_connections: usize,
+    pub udp_connections: usize,
+    pub established_connections: usize,
+    /// Distinct TCP ports with a listener.
+    pub listening_ports: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComprehensiveStats {
+    pub timestamp: DateTime<Utc>,
+    pub network: NetworkStats,
+    pub system: SystemStats,
+    pub connections: ConnectionStats,
+}
+
+/// Snapshots of the default interface, the system and open sockets, read
+/// from `/proc`. Useful next to a speed test to spot a busy machine or link.
+pub struct StatisticsCollector {
+    interval: Duration,
+    max_history: usize,
+    history: VecDeque<ComprehensiveStats>,
+}
+
+impl StatisticsCollector {
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            max_history: 60,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Sampling interval for rates and CPU usage.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Number of snapshots kept.
+    pub fn with_max_history(mut self, max_history: usize) -> Self {
+        self.max_history = max_history;
+        self
+    }
+
+    /// Earlier snapshots, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &ComprehensiveStats> {
+        self.history.iter()
+    }
+
+    /// Takes a snapshot; rates and CPU usage are measured over one interval.
+    pub async fn collect_comprehensive_stats(&mut self) -> Result<ComprehensiveStats> {
+        let interface = utils::default_interface()
+            .context("No default route; cannot tell which interface to measure")?;
+        let before = interface_counters(&interface)?;
+        let cpu_before = cpu_times()?;
+        let started = Instant::now();
+        sleep(self.interval).await;
+        let after = interface_counters(&interface)?;
+        let cpu_after = cpu_times()?;
+
+        let stats = ComprehensiveStats {
+            timestamp: Utc::now(),
+            network: network_stats(interface, &before, &after, started.elapsed()),
+            system: SystemStats {
+                cpu_usage_percent: cpu_usage(cpu_before, cpu_after),
+                memory_usage_percent: parse_memory_usage(&read_proc("meminfo")?)
+                    .context("Unexpected /proc/meminfo format")?,
+                load_average: read_proc("loadavg")?
+                    .split_whitespace()
+                    .next()
+                    .and_then(|load| load.parse().ok())
+                    .context("Unexpected /proc/loadavg format")?,
+                processes_count: processes_count()?,
+            },
+            connections: connection_stats(),
+        };
+
+        self.history.push_back(stats.clone());
+        while self.history.len() > self.max_history {
+            self.history.pop_front();
+        }
+        Ok(stats)
+    }
+}
+
+impl Default for StatisticsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read_proc(name: &str) -> Result<String> {
+    std::fs::read_to_string(format!("/proc/{}", name))
+        .with_context(|| format!("Failed to read /proc/{}; statistics need Linux", name))
+}
+
+fn network_stats(
+    interface_name: String,
+    before: &InterfaceCounters,
+    after: &InterfaceCounters,
+    elapsed: Duration,
+) -> NetworkStats {
+    let mbps = |before: u64, after: u64| {
+        after.saturating_sub(before) as f64 * 8.0 / 1e6 / elapsed.as_secs_f64().max(1e-3)
+    };
+    NetworkStats {
+        interface_name,
+        bandwidth_download_mbps: mbps(before.rx_bytes, after.rx_bytes),
+        bandwidth_upload_mbps: mbps(before.tx_bytes, after.tx_bytes),
+        bytes_received: after.rx_bytes,
+        bytes_sent: after.tx_bytes,
+        packets_received: after.rx_packets,
+        packets_sent: after.tx_packets,
+        errors_received: after.rx_errors,
+        errors_sent: after.tx_errors,
+    }
+}
+
+/// Busy and total jiffies of all CPUs from `/proc/stat`.
+fn cpu_times() -> Result<(u64, u64)> {
+    parse_cpu_times(&read_proc("stat")?).context("Unexpected /proc/stat format")
+}
+
+fn parse_cpu_times(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let values: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .map(|v| v.parse().ok())
+        .collect::<Option<_>>()?;
+    // user nice system idle iowait irq softirq steal ...
+    let idle = values.get(3)? + values.get(4).unwrap_or(&0);
+    let total: u64 = values.iter().take(8).sum();
+    Some((total - idle, total))
+}
+
+fn cpu_usage(
+    (busy_before, total_before): (u64, u64),
+    (busy_after, total_after): (u64, u64),
+) -> f64 {
+    let total = total_after.saturating_sub(total_before);
+    if total == 0 {
+        return 0.0;
+    }
+    busy_after.saturating_sub(busy_before) as f64 / total as f64 * 100.0
+}
+
+fn parse_memory_usage(meminfo: &str) -> Option<f64> {
+    let field = |name: &str| -> Option<f64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        line.split_whitespace().nth(1)?.parse().ok()
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    (total > 0.0).then(|| (total - available) / total * 100.0)
+}
+
+fn processes_count() -> Result<usize> {
+    Ok(std::fs::read_dir("/proc")
+        .context("Failed to read /proc; statistics need Linux")?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .count())
+}
+
+/// Counts sockets in `/proc/net`; tables that cannot be read (no IPv6, say)
+/// count as empty.
+fn connection_stats() -> ConnectionStats {
+    let table = |name: &str| read_proc(&format!("net/{}", name)).unwrap_or_default();
+    let mut stats = count_tcp(&(table("tcp") + &table("tcp6")));
+    stats.udp_connections = [table("udp"), table("udp6")]
+        .iter()
+        .map(|table| table.lines().skip(1).count())
+        .sum();
+    stats
+}
+
+/// Counts TCP sockets in the concatenated `/proc/net/tcp` and `tcp6` tables.
+fn count_tcp(tables: &str) -> ConnectionStats {
+    let mut stats = ConnectionStats::default();
+    let mut listening = BTreeSet::new();
+    for line in tables.lines() {
+        let columns: Vec<_> = line.split_whitespace().collect();
+        let [_, local, _, state, ..] = columns[..] else {
+            continue;
+        };
+        match state {
+            TCP_LISTEN => {
+                listening.extend(local.rsplit_once(':').map(|(_, port)| port.to_string()));
+            }
+            // Header lines have `st` here.
+            "st" => {}
+            state => {
+                stats.tcp_connections += 1;
+                if state == TCP_ESTABLISHED {
+                    stats.established_connections += 1;
+                }
+            }
+        }
+    }
+    stats.listening_ports = listening.len();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_memory_and_sockets() {
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n";
+        assert_eq!(parse_cpu_times(stat), Some((150, 1000)));
+        assert_eq!(cpu_usage((150, 1000), (300, 1200)), 75.0);
+
+        let meminfo = "MemTotal:       8000000 kB\nMemFree:         1000000 kB\nMemAvailable:    2000000 kB\n";
+        assert_eq!(parse_memory_usage(meminfo), Some(75.0));
+
+        let tcp = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1 1
+   1: 0100007F:2524 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 2 1
+   2: 0F02000A:0016 0202000A:C350 01 00000000:00000000 02:0000B1E2 00000000     0        0 3 4
+   3: 0F02000A:A1B2 08080808:01BB 06 00000000:00000000 03:00000000 00000000     0        0 0 3
+  sl  local_address                         remote_address                        st tx_queue
+   0: 00000000000000000000000000000000:0016 00000000000000000000000000000000:0000 0A 00000000:00000000
+";
+        assert_eq!(
+            count_tcp(tcp),
+            ConnectionStats {
+                tcp_connections: 2,
+                udp_connections: 0,
+                established_connections: 1,
+                listening_ports: 2,
+            }
+        );
+    }
+}
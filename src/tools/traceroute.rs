@@ -0,0 +1,207 @@
+use anyhow::{bail, Context, Result};
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// One hop of a route. A hop where no probe was answered is a timeout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub hop_number: u32,
+    pub hostname: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    /// Round-trip time of each answered probe in milliseconds.
+    pub rtt_ms: Vec<f64>,
+    pub avg_rtt_ms: f64,
+    pub is_timeout: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceResult {
+    pub target: String,
+    /// Address the target resolved to.
+    pub target_ip: Option<IpAddr>,
+    pub total_hops: usize,
+    /// Hops that answered at least one probe.
+    pub successful_hops: usize,
+    /// Mean of the answered hops' average round-trip times.
+    pub average_rtt_ms: f64,
+    /// The last hop is the target itself.
+    pub path_complete: bool,
+    pub hops: Vec<Hop>,
+}
+
+/// Traces the route to a host with the system `traceroute` command.
+pub struct Traceroute {
+    max_hops: u32,
+    timeout: Duration,
+    probes_per_hop: u32,
+}
+
+impl Traceroute {
+    pub fn new() -> Self {
+        Self {
+            max_hops: 30,
+            timeout: Duration::from_secs(5),
+            probes_per_hop: 3,
+        }
+    }
+
+    pub fn with_max_hops(mut self, max_hops: u32) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    /// How long to wait for each probe.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_probes_per_hop(mut self, probes_per_hop: u32) -> Self {
+        self.probes_per_hop = probes_per_hop;
+        self
+    }
+
+    pub async fn trace(&self, target: &str) -> Result<TraceResult> {
+        let output = Command::new("traceroute")
+            .arg("-m")
+            .arg(self.max_hops.to_string())
+            .arg("-w")
+            .arg(self.timeout.as_secs_f64().to_string())
+            .arg("-q")
+            .arg(self.probes_per_hop.to_string())
+            .arg(target)
+            .output()
+            .await
+            .context("Failed to run traceroute; is it installed?")?;
+        if !output.status.success() {
+            bail!(
+                "traceroute to {} failed: {}",
+                target,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(parse_output(
+            target,
+            &String::from_utf8_lossy(&output.stdout),
+        ))
+    }
+}
+
+impl Default for Traceroute {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses `traceroute` output such as
+/// `traceroute to dns.google (8.8.8.8), 30 hops max` followed by lines like
+/// ` 2  router.lan (192.168.1.1)  0.512 ms  0.480 ms *`.
+fn parse_output(target: &str, output: &str) -> TraceResult {
+    let mut lines = output.lines();
+    let target_ip = lines
+        .next()
+        .and_then(|header| header.split_once('(')?.1.split_once(')'))
+        .and_then(|(ip, _)| ip.parse().ok());
+    let hops: Vec<Hop> = lines.filter_map(parse_hop).collect();
+
+    let answered: Vec<_> = hops.iter().filter(|hop| !hop.is_timeout).collect();
+    let average_rtt_ms = if answered.is_empty() {
+        0.0
+    } else {
+        answered.iter().map(|hop| hop.avg_rtt_ms).sum::<f64>() / answered.len() as f64
+    };
+    TraceResult {
+        target: target.to_string(),
+        target_ip,
+        total_hops: hops.len(),
+        successful_hops: answered.len(),
+        average_rtt_ms,
+        path_complete: target_ip.is_some()
+            && hops.last().and_then(|hop| hop.ip_address) == target_ip,
+        hops,
+    }
+}
+
+fn parse_hop(line: &str) -> Option<Hop> {
+    let mut tokens = line.split_whitespace().peekable();
+    let hop_number = tokens.next()?.parse().ok()?;
+    let mut hostname = None;
+    let mut ip_address = None;
+    let mut rtt_ms = Vec::new();
+    while let Some(token) = tokens.next() {
+        if let Ok(rtt) = token.parse::<f64>() {
+            if tokens.next_if_eq(&"ms").is_some() {
+                rtt_ms.push(rtt);
+                continue;
+            }
+        }
+        // The first responder names the hop; later ones (load balancing)
+        // only add round-trip times.
+        if token == "*" || token.starts_with('!') || ip_address.is_some() {
+            continue;
+        }
+        if let Ok(ip) = token.parse() {
+            ip_address = Some(ip);
+        } else if let Some(ip) = tokens
+            .next_if(|next| next.starts_with('('))
+            .and_then(|next| next.trim_matches(['(', ')']).parse().ok())
+        {
+            hostname = Some(token.to_string());
+            ip_address = Some(ip);
+        }
+    }
+    let avg_rtt_ms = if rtt_ms.is_empty() {
+        0.0
+    } else {
+        rtt_ms.iter().sum::<f64>() / rtt_ms.len() as f64
+    };
+    Some(Hop {
+        hop_number,
+        hostname,
+        is_timeout: ip_address.is_none(),
+        ip_address,
+        rtt_ms,
+        avg_rtt_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hops_timeouts_and_completion() {
+        let output = "\
+traceroute to dns.google (8.8.8.8), 15 hops max, 60 byte packets
+ 1  router.lan (192.168.1.1)  0.512 ms  0.480 ms  0.470 ms
+ 2  * * *
+ 3  10.20.0.1 (10.20.0.1)  5.100 ms * 5.300 ms
+ 4  dns.google (8.8.8.8)  12.000 ms  14.000 ms  13.000 ms
+";
+        let trace = parse_output("dns.google", output);
+        assert_eq!(trace.target_ip, Some("8.8.8.8".parse().unwrap()));
+        assert_eq!(trace.total_hops, 4);
+        assert_eq!(trace.successful_hops, 3);
+        assert!(trace.path_complete);
+
+        let first = &trace.hops[0];
+        assert_eq!(first.hostname.as_deref(), Some("router.lan"));
+        assert_eq!(first.rtt_ms, [0.512, 0.480, 0.470]);
+        assert!(trace.hops[1].is_timeout);
+        assert_eq!(trace.hops[1].rtt_ms, [] as [f64; 0]);
+        assert_eq!(trace.hops[2].rtt_ms, [5.1, 5.3]);
+        assert_eq!(trace.hops[3].avg_rtt_ms, 13.0);
+
+        let numeric = parse_output(
+            "8.8.8.8",
+            "traceroute to 8.8.8.8 (8.8.8.8)\n 1  192.168.1.1  0.5 ms\n 2  * * *\n",
+        );
+        assert_eq!(
+            numeric.hops[0].ip_address,
+            Some("192.168.1.1".parse().unwrap())
+        );
+        assert_eq!(numeric.hops[0].hostname, None);
+        assert!(!numeric.path_complete);
+    }
+}
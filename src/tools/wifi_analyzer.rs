@@ -0,0 +1,167 @@
+use super::mac_vendor;
+use anyhow::{bail, Context, Result};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiScanType {
+    /// The networks NetworkManager last saw.
+    Quick,
+    /// Rescans first, which takes a few seconds.
+    Comprehensive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WifiSecurity {
+    Open,
+    Wep,
+    Wpa,
+    Wpa2,
+    Wpa3,
+    Enterprise,
+}
+
+impl WifiSecurity {
+    /// Parses NetworkManager's security column, e.g. `WPA2 WPA3` or `--`.
+    fn from_nmcli(security: &str) -> Self {
+        if security.contains("802.1X") {
+            WifiSecurity::Enterprise
+        } else if security.contains("WPA3") {
+            WifiSecurity::Wpa3
+        } else if security.contains("WPA2") {
+            WifiSecurity::Wpa2
+        } else if security.contains("WPA") {
+            WifiSecurity::Wpa
+        } else if security.contains("WEP") {
+            WifiSecurity::Wep
+        } else {
+            WifiSecurity::Open
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WifiNetwork {
+    /// Empty for hidden networks.
+    pub ssid: String,
+    pub bssid: String,
+    pub channel: u32,
+    pub frequency_mhz: u32,
+    pub signal_percentage: u8,
+    pub security: WifiSecurity,
+    pub vendor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WifiScanResult {
+    pub interface_name: String,
+    pub networks_found: usize,
+    /// Strongest signal first.
+    pub networks: Vec<WifiNetwork>,
+    pub total_duration: Duration,
+}
+
+/// Lists nearby Wi-Fi networks through NetworkManager's `nmcli`.
+pub struct WifiAnalyzer {
+    interface: String,
+}
+
+impl WifiAnalyzer {
+    pub fn new(interface: impl Into<String>) -> Self {
+        Self {
+            interface: interface.into(),
+        }
+    }
+
+    pub async fn scan_networks(&self, scan_type: WifiScanType) -> Result<WifiScanResult> {
+        let started = Instant::now();
+        let rescan = match scan_type {
+            WifiScanType::Quick => "no",
+            WifiScanType::Comprehensive => "yes",
+        };
+        let output = Command::new("nmcli")
+            .args(["-t", "-f", "SSID,BSSID,CHAN,FREQ,SIGNAL,SECURITY"])
+            .args(["device", "wifi", "list", "ifname", &self.interface])
+            .args(["--rescan", rescan])
+            .output()
+            .await
+            .context("Failed to run nmcli; Wi-Fi scans need NetworkManager")?;
+        if !output.status.success() {
+            bail!(
+                "Wi-Fi scan on {} failed: {}",
+                self.interface,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let mut networks = parse_networks(&String::from_utf8_lossy(&output.stdout));
+        networks.sort_by_key(|network| std::cmp::Reverse(network.signal_percentage));
+        Ok(WifiScanResult {
+            interface_name: self.interface.clone(),
+            networks_found: networks.len(),
+            networks,
+            total_duration: started.elapsed(),
+        })
+    }
+}
+
+/// Parses `nmcli -t` lines, where `:` separates fields and `\:` is a
+/// literal colon, e.g. `Home:AA\:BB\:CC\:DD\:EE\:FF:6:2437 MHz:72:WPA2`.
+fn parse_networks(output: &str) -> Vec<WifiNetwork> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields = split_terse(line);
+            let [ssid, bssid, channel, frequency, signal, security] = fields.as_slice() else {
+                return None;
+            };
+            Some(WifiNetwork {
+                vendor: mac_vendor(bssid).map(str::to_string),
+                ssid: ssid.clone(),
+                bssid: bssid.clone(),
+                channel: channel.parse().ok()?,
+                frequency_mhz: frequency.trim_end_matches(" MHz").parse().ok()?,
+                signal_percentage: signal.parse().ok()?,
+                security: WifiSecurity::from_nmcli(security),
+            })
+        })
+        .collect()
+}
+
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => fields.last_mut().unwrap().extend(chars.next()),
+            ':' => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_terse_nmcli_output() {
+        let output = "\
+Home:B8\\:27\\:EB\\:00\\:11\\:22:6:2437 MHz:72:WPA2
+:AA\\:BB\\:CC\\:DD\\:EE\\:FF:36:5180 MHz:40:WPA2 WPA3
+Cafe\\: Guest:11\\:22\\:33\\:44\\:55\\:66:1:2412 MHz:55:
+";
+        let networks = parse_networks(output);
+        assert_eq!(networks.len(), 3);
+        assert_eq!(networks[0].ssid, "Home");
+        assert_eq!(networks[0].bssid, "B8:27:EB:00:11:22");
+        assert_eq!(networks[0].frequency_mhz, 2437);
+        assert_eq!(networks[0].vendor.as_deref(), Some("Raspberry Pi"));
+        assert_eq!(networks[1].ssid, "");
+        assert_eq!(networks[1].channel, 36);
+        assert_eq!(networks[1].security, WifiSecurity::Wpa3);
+        assert_eq!(networks[2].ssid, "Cafe: Guest");
+        assert_eq!(networks[2].security, WifiSecurity::Open);
+    }
+}
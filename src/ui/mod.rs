@@ -0,0 +1,56 @@
+//! Terminal output and color themes.
+
+pub mod output;
+pub mod theme;
+
+pub use theme::{Theme, ThemeColors, ThemeManager};
+
+use crate::network::{Phase, Progress};
+use anyhow::Result;
+use std::io::Write;
+
+/// Renders test progress for the terminal.
+#[derive(Default)]
+pub struct UiManager {
+    themes: ThemeManager,
+}
+
+impl UiManager {
+    /// Uses the `auto` theme.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_theme(name: &str) -> Result<Self> {
+        let mut ui = Self::new();
+        ui.themes.set_theme(name)?;
+        Ok(ui)
+    }
+
+    pub fn theme(&self) -> &Theme {
+        self.themes.current()
+    }
+
+    /// Prints a line when each throughput or latency phase starts and keeps
+    /// the current throughput on one updating line.
+    pub fn print_progress(&self, progress: Progress) {
+        match progress {
+            Progress::PhaseStarted(Phase::Download) => println!("📥 Testing download speed..."),
+            Progress::PhaseStarted(Phase::Upload) => println!("📤 Testing upload speed..."),
+            Progress::PhaseStarted(Phase::Ping) => println!("🏓 Testing ping..."),
+            Progress::Sample { phase, mbps, .. } => {
+                let label = if phase == Phase::Upload {
+                    "Upload"
+                } else {
+                    "Download"
+                };
+                print!("\r{}: {:.1} Mbps", label, mbps);
+                std::io::stdout().flush().unwrap();
+            }
+            Progress::PhaseStarted(Phase::Dns | Phase::Connect) => {}
+            Progress::LatencyProbe { .. } => {}
+            Progress::PhaseFinished(Phase::Download | Phase::Upload) => println!(),
+            Progress::PhaseFinished(Phase::Dns | Phase::Connect | Phase::Ping) => {}
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Plain-text results, summaries and history statistics.
+
+use crate::batch::Summary;
+use crate::export::ResultDocument;
+use crate::history::anomaly::{self, Anomaly, AnomalyKind, Metric};
+use crate::history::HistoryStatistics;
+use crate::network::{SteadyWindow, TestResult};
+use crate::report;
+use crate::thresholds::Check;
+use crate::utils::{self, get_quality_description};
+
+/// Prints the measurements of one test and its quality score. A phase that
+/// used up `max_bytes` is noted under Data Used.
+pub fn print_result(result: &TestResult, quality_score: u8, max_bytes: Option<u64>) {
+    println!("📊 Test Results:");
+    println!("================");
+    println!(
+        "Download Speed: {}",
+        speed(
+            result.method.download,
+            result.download_speed,
+            result.download_window
+        )
+    );
+    println!(
+        "Upload Speed: {}",
+        speed(
+            result.method.upload,
+            result.upload_speed,
+            result.upload_window
+        )
+    );
+    println!("Ping: {:.1} ms", result.ping);
+    println!("Jitter: {:.1} ms", result.jitter);
+    println!("Packet Loss: {:.1}%", result.packet_loss);
+    println!("Data Used: {}", data_used(result, max_bytes));
+    println!("Test Duration: {:.1} seconds", result.test_duration);
+    if result.partial {
+        println!("⚠️  Partial result: the test was stopped early");
+    }
+    println!();
+
+    println!(
+        "🎯 Network Quality: {}/100 ({})",
+        quality_score,
+        get_quality_description(quality_score)
+    );
+}
+
+/// Bytes moved per phase, e.g. `31.2 MB download, 12.0 MB upload`, noting
+/// phases that used up `max_bytes`.
+fn data_used(result: &TestResult, max_bytes: Option<u64>) -> String {
+    let phase = |enabled: bool, bytes: u64, name: &str| {
+        enabled.then(|| {
+            let budget = if max_bytes == Some(bytes) {
+                " (budget reached)"
+            } else {
+                ""
+            };
+            format!("{} {}{}", utils::format_bytes(bytes), name, budget)
+        })
+    };
+    [
+        phase(result.method.download, result.download_bytes, "download"),
+        phase(result.method.upload, result.upload_bytes, "upload"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// E.g. `94.2 Mbps (steady state 2–9 s of 9 s)`, or `skipped` for a phase
+/// that did not run.
+fn speed(measured: bool, mbps: f64, window: Option<SteadyWindow>) -> String {
+    if measured {
+        format!("{:.1} Mbps{}", mbps, steady_note(window))
+    } else {
+        "skipped".to_string()
+    }
+}
+
+/// ` (steady state 2–9 s of 9 s)` for a measured phase, or nothing.
+fn steady_note(window: Option<SteadyWindow>) -> String {
+    window
+        .map(|window| format!(" (steady state {})", window))
+        .unwrap_or_default()
+}
+
+/// The `--quick` result, e.g. `↓ 94.2 Mbps ↑ 21.0 Mbps 12 ms`. Skipped
+/// phases are left out.
+pub fn quick_line(result: &TestResult) -> String {
+    let mut parts = Vec::new();
+    if result.method.download {
+        parts.push(format!("↓ {:.1} Mbps", result.download_speed));
+    }
+    if result.method.upload {
+        parts.push(format!("↑ {:.1} Mbps", result.upload_speed));
+    }
+    parts.push(format!("{:.0} ms", result.ping));
+    if result.partial {
+        parts.push("(partial)".to_string());
+    }
+    parts.join(" ")
+}
+
+pub fn print_batch_summary(summaries: &[(Check, Summary)], runs: u32) {
+    println!("📈 Summary of {} runs:", runs);
+    println!("=====================");
+    println!(
+        "{:<14} {:>11} {:>11} {:>11} {:>11} {:>12}",
+        "", "Mean", "Median", "Min", "Max", "95% CI"
+    );
+    for (check, summary) in summaries {
+        let ci = summary.ci95.map_or_else(
+            || "–".to_string(),
+            |ci| format!("±{}", check.format_value(ci)),
+        );
+        println!(
+            "{:<14} {:>11} {:>11} {:>11} {:>11} {:>12}",
+            check.label(),
+            check.format_value(summary.mean),
+            check.format_value(summary.median),
+            check.format_value(summary.min),
+            check.format_value(summary.max),
+            ci
+        );
+    }
+}
+
+pub fn print_comparison(document: &ResultDocument, baseline: &HistoryStatistics, days: u32) {
+    println!(
+        "📊 Compared with the last {} days ({} runs):",
+        days, baseline.total_tests
+    );
+    if baseline.total_tests == 0 {
+        println!("No saved results to compare with.");
+        return;
+    }
+    let current = [
+        document
+            .download
+            .as_ref()
+            .map(|t| t.bits_per_second / 1_000_000.0),
+        document
+            .upload
+            .as_ref()
+            .map(|t| t.bits_per_second / 1_000_000.0),
+        Some(document.latency.ping_ms),
+    ];
+    for (metric, current) in Metric::ALL.into_iter().zip(current) {
+        let (average, _, _) = baseline.summary(metric);
+        let unit = metric.unit();
+        match current {
+            Some(value) => println!(
+                "{}: {:.1} {} vs {:.1} {} average ({})",
+                metric.name(),
+                value,
+                unit,
+                average,
+                unit,
+                anomaly::format_change(average, value)
+            ),
+            None => println!(
+                "{}: skipped ({:.1} {} average)",
+                metric.name(),
+                average,
+                unit
+            ),
+        }
+    }
+}
+
+pub fn print_history_statistics(stats: &HistoryStatistics, days: u32) {
+    println!(
+        "📊 History Statistics (last {} days, {} runs)",
+        days, stats.total_tests
+    );
+    println!("============================================");
+    if stats.total_tests == 0 {
+        println!("No saved results.");
+        return;
+    }
+    println!("{:<9} {:>12} {:>12} {:>12}", "", "Average", "Best", "Worst");
+    for metric in Metric::ALL {
+        let (average, best, worst) = stats.summary(metric);
+        let cell = |value: f64| format!("{:.1} {}", value, metric.unit());
+        println!(
+            "{:<9} {:>12} {:>12} {:>12}",
+            metric.name(),
+            cell(average),
+            cell(best),
+            cell(worst)
+        );
+    }
+    let score = report::markdown::average_quality(stats);
+    println!();
+    println!(
+        "🎯 Average Quality: {}/100 ({})",
+        score,
+        get_quality_description(score)
+    );
+}
+
+/// Prints one line per anomaly found in `runs` saved runs, in local time.
+pub fn print_anomalies(anomalies: &[Anomaly], days: u32, runs: usize) {
+    println!("🔎 History Anomalies (last {} days, {} runs)", days, runs);
+    println!("============================================");
+    if anomalies.is_empty() {
+        println!("No anomalies found.");
+    }
+    for event in anomalies {
+        let date = event
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M");
+        let unit = event.metric.unit();
+        match event.kind {
+            AnomalyKind::Outlier {
+                value,
+                typical,
+                z_score,
+                baseline,
+            } => println!(
+                "{}  ⚠️  Outlier      {:<9} {:.1} {} (typical {:.1} {} for {}, z = {:+.1})",
+                date,
+                event.metric.name(),
+                value,
+                unit,
+                typical,
+                unit,
+                baseline,
+                z_score
+            ),
+            AnomalyKind::LevelShift { before, after } => println!(
+                "{}  {} Level shift  {:<9} {:.1} {} → {:.1} {} ({})",
+                date,
+                if after > before { "📈" } else { "📉" },
+                event.metric.name(),
+                before,
+                unit,
+                after,
+                unit,
+                anomaly::format_change(before, after)
+            ),
+        }
+    }
+}
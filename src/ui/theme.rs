@@ -0,0 +1,177 @@
+use crate::config::THEMES;
+use anyhow::{bail, Result};
+use ratatui::style::Color;
+
+/// The colors one theme assigns to each role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeColors {
+    pub background: Color,
+    pub foreground: Color,
+    pub primary: Color,
+    pub secondary: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub info: Color,
+    pub accent: Color,
+    pub border: Color,
+    pub text_primary: Color,
+    pub text_secondary: Color,
+    pub text_muted: Color,
+}
+
+impl ThemeColors {
+    /// Builds a palette from `0xRRGGBB` values, in field order.
+    const fn rgb(hex: [u32; 13]) -> Self {
+        Self {
+            background: Color::from_u32(hex[0]),
+            foreground: Color::from_u32(hex[1]),
+            primary: Color::from_u32(hex[2]),
+            secondary: Color::from_u32(hex[3]),
+            success: Color::from_u32(hex[4]),
+            warning: Color::from_u32(hex[5]),
+            error: Color::from_u32(hex[6]),
+            info: Color::from_u32(hex[7]),
+            accent: Color::from_u32(hex[8]),
+            border: Color::from_u32(hex[9]),
+            text_primary: Color::from_u32(hex[10]),
+            text_secondary: Color::from_u32(hex[11]),
+            text_muted: Color::from_u32(hex[12]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    pub colors: ThemeColors,
+}
+
+impl Theme {
+    /// The built-in theme called `name`, one of [`THEMES`].
+    pub fn builtin(name: &str) -> Option<Self> {
+        let colors = match name {
+            // The terminal's own palette.
+            "auto" => ThemeColors {
+                background: Color::Reset,
+                foreground: Color::Reset,
+                primary: Color::Blue,
+                secondary: Color::Magenta,
+                success: Color::Green,
+                warning: Color::Yellow,
+                error: Color::Red,
+                info: Color::Cyan,
+                accent: Color::LightMagenta,
+                border: Color::DarkGray,
+                text_primary: Color::Reset,
+                text_secondary: Color::Gray,
+                text_muted: Color::DarkGray,
+            },
+            "dracula" => ThemeColors::rgb([
+                0x282a36, 0xf8f8f2, 0xbd93f9, 0xff79c6, 0x50fa7b, 0xf1fa8c, 0xff5555, 0x8be9fd,
+                0xffb86c, 0x44475a, 0xf8f8f2, 0xbfbfbf, 0x6272a4,
+            ]),
+            "nord" => ThemeColors::rgb([
+                0x2e3440, 0xd8dee9, 0x88c0d0, 0x81a1c1, 0xa3be8c, 0xebcb8b, 0xbf616a, 0x5e81ac,
+                0xb48ead, 0x4c566a, 0xeceff4, 0xd8dee9, 0x616e88,
+            ]),
+            "solarized-dark" => ThemeColors::rgb([
+                0x002b36, 0x839496, 0x268bd2, 0x2aa198, 0x859900, 0xb58900, 0xdc322f, 0x6c71c4,
+                0xd33682, 0x073642, 0x93a1a1, 0x839496, 0x586e75,
+            ]),
+            "solarized-light" => ThemeColors::rgb([
+                0xfdf6e3, 0x657b83, 0x268bd2, 0x2aa198, 0x859900, 0xb58900, 0xdc322f, 0x6c71c4,
+                0xd33682, 0xeee8d5, 0x586e75, 0x657b83, 0x93a1a1,
+            ]),
+            "monokai" => ThemeColors::rgb([
+                0x272822, 0xf8f8f2, 0x66d9ef, 0xae81ff, 0xa6e22e, 0xe6db74, 0xf92672, 0x66d9ef,
+                0xfd971f, 0x49483e, 0xf8f8f2, 0xcfcfc2, 0x75715e,
+            ]),
+            "github" => ThemeColors::rgb([
+                0xffffff, 0x24292f, 0x0969da, 0x8250df, 0x1a7f37, 0x9a6700, 0xcf222e, 0x0550ae,
+                0xbc4c00, 0xd0d7de, 0x24292f, 0x57606a, 0x6e7781,
+            ]),
+            "tokyo-night" => ThemeColors::rgb([
+                0x1a1b26, 0xc0caf5, 0x7aa2f7, 0xbb9af7, 0x9ece6a, 0xe0af68, 0xf7768e, 0x7dcfff,
+                0xff9e64, 0x3b4261, 0xc0caf5, 0xa9b1d6, 0x565f89,
+            ]),
+            _ => return None,
+        };
+        Some(Self {
+            name: name.to_string(),
+            colors,
+        })
+    }
+}
+
+/// The built-in themes and which one is selected.
+pub struct ThemeManager {
+    themes: Vec<Theme>,
+    current: usize,
+}
+
+impl ThemeManager {
+    /// All built-in themes, with `auto` selected.
+    pub fn new() -> Self {
+        Self {
+            themes: THEMES
+                .iter()
+                .filter_map(|name| Theme::builtin(name))
+                .collect(),
+            current: 0,
+        }
+    }
+
+    pub fn get_available_themes(&self) -> Vec<String> {
+        self.themes.iter().map(|theme| theme.name.clone()).collect()
+    }
+
+    pub fn set_theme(&mut self, name: &str) -> Result<()> {
+        let Some(index) = self.themes.iter().position(|theme| theme.name == name) else {
+            bail!(
+                "Unknown theme `{}` (expected one of {})",
+                name,
+                THEMES.join(", ")
+            );
+        };
+        self.current = index;
+        Ok(())
+    }
+
+    /// Name of the selected theme.
+    pub fn get_current_theme(&self) -> &str {
+        &self.themes[self.current].name
+    }
+
+    pub fn get_theme(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|theme| theme.name == name)
+    }
+
+    pub fn current(&self) -> &Theme {
+        &self.themes[self.current]
+    }
+}
+
+impl Default for ThemeManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_configurable_theme_has_a_palette() {
+        let mut themes = ThemeManager::new();
+        assert_eq!(themes.get_available_themes(), THEMES);
+        assert_eq!(themes.get_current_theme(), "auto");
+
+        themes.set_theme("nord").unwrap();
+        assert_eq!(themes.current().colors.error, Color::Rgb(0xbf, 0x61, 0x6a));
+        let err = themes.set_theme("neon").unwrap_err().to_string();
+        assert!(err.starts_with("Unknown theme `neon`"), "{}", err);
+        assert_eq!(themes.get_current_theme(), "nord");
+    }
+}
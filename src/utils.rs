@@ -0,0 +1,302 @@
+//! Shared helpers for scoring and parsing.
+
+use anyhow::{bail, Context, Result};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// The parts of the quality score: each sub-score is 0-100.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityBreakdown {
+    pub download_score: f64,
+    pub latency_score: f64,
+}
+
+impl QualityBreakdown {
+    pub const DOWNLOAD_WEIGHT: f64 = 0.7;
+    pub const LATENCY_WEIGHT: f64 = 0.3;
+}
+
+pub fn get_quality_breakdown(download: f64, ping: f64) -> QualityBreakdown {
+    let download_score = if download >= 100.0 {
+        100.0
+    } else if download >= 50.0 {
+        80.0
+    } else if download >= 25.0 {
+        60.0
+    } else if download >= 10.0 {
+        40.0
+    } else {
+        20.0
+    };
+
+    let latency_score = if ping <= 20.0 {
+        100.0
+    } else if ping <= 50.0 {
+        80.0
+    } else if ping <= 100.0 {
+        60.0
+    } else if ping <= 200.0 {
+        40.0
+    } else {
+        20.0
+    };
+
+    QualityBreakdown {
+        download_score,
+        latency_score,
+    }
+}
+
+/// Combines download speed (70%) and ping (30%) into a 0-100 score.
+pub fn get_quality_score(download: f64, _upload: f64, ping: f64) -> u8 {
+    let parts = get_quality_breakdown(download, ping);
+    let score = parts.download_score * QualityBreakdown::DOWNLOAD_WEIGHT
+        + parts.latency_score * QualityBreakdown::LATENCY_WEIGHT;
+    (score as u8).min(100)
+}
+
+pub fn get_quality_description(score: u8) -> &'static str {
+    match score {
+        90..=100 => "Excellent",
+        80..=89 => "Very Good",
+        70..=79 => "Good",
+        60..=69 => "Fair",
+        50..=59 => "Poor",
+        _ => "Very Poor",
+    }
+}
+
+/// Parses durations like `30s`, `15m`, `2h` or `1d`. A bare number is seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let Ok(value) = number.parse::<u64>() else {
+        bail!("Invalid duration '{}': expected e.g. 30s, 15m or 2h", s);
+    };
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => bail!("Invalid duration unit in '{}': use s, m, h or d", s),
+    };
+    let seconds = value
+        .checked_mul(multiplier)
+        .with_context(|| format!("Duration '{}' is out of range", s))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Parses data sizes like `200MB`, `1.5GB` or `500k` in decimal units
+/// (1 MB = 1,000,000 bytes). A bare number is bytes.
+pub fn parse_bytes(s: &str) -> Result<u64> {
+    let (value, unit) = split_number(s)
+        .with_context(|| format!("Invalid size '{}': expected e.g. 200MB or 1.5GB", s.trim()))?;
+    let multiplier = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1e3,
+        "m" | "mb" => 1e6,
+        "g" | "gb" => 1e9,
+        _ => bail!("Invalid size unit in '{}': use B, kB, MB or GB", s.trim()),
+    };
+    let bytes = (value * multiplier).round();
+    if bytes < 1.0 {
+        bail!("Invalid size '{}': must be at least 1 byte", s.trim());
+    }
+    Ok(bytes as u64)
+}
+
+/// Parses rates like `10Mbps`, `500kbps` or `1Gbps` into Mbps. A bare number
+/// is Mbps.
+pub fn parse_rate(s: &str) -> Result<f64> {
+    let (value, unit) = split_number(s).with_context(|| {
+        format!(
+            "Invalid rate '{}': expected e.g. 10Mbps or 500kbps",
+            s.trim()
+        )
+    })?;
+    let mbps = match unit.to_ascii_lowercase().as_str() {
+        "kbps" | "k" => value / 1e3,
+        "" | "mbps" | "m" => value,
+        "gbps" | "g" => value * 1e3,
+        _ => bail!(
+            "Invalid rate unit in '{}': use kbps, Mbps or Gbps",
+            s.trim()
+        ),
+    };
+    if mbps <= 0.0 {
+        bail!("Invalid rate '{}': must be above zero", s.trim());
+    }
+    Ok(mbps)
+}
+
+/// Splits `1.5GB` into `1.5` and `GB`.
+fn split_number(s: &str) -> Option<(f64, &str)> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: f64 = number.parse().ok()?;
+    value.is_finite().then_some((value, unit.trim()))
+}
+
+/// Formats a byte count in decimal units, e.g. `31.2 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..=999 => format!("{} B", bytes),
+        1_000..=999_999 => format!("{:.1} kB", bytes as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.1} MB", bytes as f64 / 1e6),
+        _ => format!("{:.2} GB", bytes as f64 / 1e9),
+    }
+}
+
+/// Formats a rate given in Mbps with the largest unit that keeps it at
+/// least one, e.g. `500.00 Kbps`, `50.00 Mbps` or `1.5 Gbps`.
+pub fn format_speed(mbps: f64) -> String {
+    if mbps >= 1000.0 {
+        format!("{:.1} Gbps", mbps / 1000.0)
+    } else if mbps >= 1.0 {
+        format!("{:.2} Mbps", mbps)
+    } else {
+        format!("{:.2} Kbps", mbps * 1000.0)
+    }
+}
+
+/// Formats a round-trip time given in milliseconds, e.g. `25.0 ms` or
+/// `500.0 μs`.
+pub fn format_ping(ms: f64) -> String {
+    if ms >= 1.0 {
+        format!("{:.1} ms", ms)
+    } else {
+        format!("{:.1} μs", ms * 1000.0)
+    }
+}
+
+/// `value` as a percentage of `total`; zero when `total` is zero.
+pub fn calculate_percentage(value: f64, total: f64) -> f64 {
+    if total == 0.0 {
+        0.0
+    } else {
+        value / total * 100.0
+    }
+}
+
+pub fn validate_server_id(id: u32) -> bool {
+    (1..=999_999).contains(&id)
+}
+
+/// Test durations are 5 to 300 seconds.
+pub fn validate_duration(seconds: u64) -> bool {
+    (5..=300).contains(&seconds)
+}
+
+/// Tests use 1 to 16 parallel connections.
+pub fn validate_connections(connections: u32) -> bool {
+    (1..=16).contains(&connections)
+}
+
+/// Replaces every character other than ASCII letters, digits, `.`, `-` and
+/// `_` with `_`.
+pub fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Parses a listen address. A bare `:port` listens on all interfaces.
+pub fn parse_listen_addr(s: &str) -> Result<SocketAddr> {
+    let addr = if s.starts_with(':') {
+        format!("0.0.0.0{}", s)
+    } else {
+        s.to_string()
+    };
+    addr.parse().with_context(|| {
+        format!(
+            "Invalid listen address '{}': expected e.g. :9516 or 127.0.0.1:9516",
+            s
+        )
+    })
+}
+
+/// Name of the interface carrying the default route, where the platform
+/// exposes it (Linux only).
+pub fn default_interface() -> Option<String> {
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+    routes.lines().skip(1).find_map(|line| {
+        let mut columns = line.split_whitespace();
+        let iface = columns.next()?;
+        (columns.next()? == "00000000").then(|| iface.to_string())
+    })
+}
+
+/// This machine's host name, if it can be determined.
+pub fn hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| {
+            let output = std::process::Command::new("hostname").output().ok()?;
+            String::from_utf8(output.stdout).ok()
+        })
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("10w").is_err());
+        assert!(parse_duration("999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn parses_and_formats_sizes_and_rates() {
+        assert_eq!(parse_bytes("200MB").unwrap(), 200_000_000);
+        assert_eq!(parse_bytes("1.5 GB").unwrap(), 1_500_000_000);
+        assert_eq!(parse_bytes("500k").unwrap(), 500_000);
+        assert_eq!(parse_bytes("4096").unwrap(), 4096);
+        assert!(parse_bytes("0MB").is_err());
+        assert!(parse_bytes("5 TB").is_err());
+        assert_eq!(parse_rate("10Mbps").unwrap(), 10.0);
+        assert_eq!(parse_rate("500kbps").unwrap(), 0.5);
+        assert!(parse_rate("fast").is_err());
+
+        assert_eq!(format_bytes(999), "999 B");
+        assert_eq!(format_bytes(31_249_999), "31.2 MB");
+        assert_eq!(format_bytes(1_500_000_000), "1.50 GB");
+    }
+
+    #[test]
+    fn formats_speeds_and_pings() {
+        assert_eq!(format_speed(999.9), "999.90 Mbps");
+        assert_eq!(format_speed(2500.0), "2.5 Gbps");
+        assert_eq!(format_speed(0.25), "250.00 Kbps");
+        assert_eq!(format_ping(1.0), "1.0 ms");
+        assert_eq!(format_ping(0.05), "50.0 μs");
+    }
+
+    #[test]
+    fn parses_listen_addresses() {
+        assert_eq!(
+            parse_listen_addr(":9516").unwrap().to_string(),
+            "0.0.0.0:9516"
+        );
+        assert_eq!(parse_listen_addr("127.0.0.1:80").unwrap().port(), 80);
+        assert!(parse_listen_addr("localhost").is_err());
+    }
+}